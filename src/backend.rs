@@ -0,0 +1,537 @@
+//! Trash backends: the OS trash via the `trash` crate, or a plain directory.
+//!
+//! The custom backend exists for hosts without a working system trash
+//! (containers, headless servers). Items are moved into `<root>/files/` and a
+//! JSON sidecar in `<root>/info/` records where they came from, so listing and
+//! restore work the same way as with the OS trash.
+
+use serde::{Deserialize, Serialize};
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+#[cfg(feature = "full")]
+use trash::{TrashItem, TrashItemMetadata, TrashItemSize};
+
+#[derive(Debug, Clone)]
+pub enum Backend {
+    /// The platform trash/recycle bin.
+    System,
+    /// A server-managed directory (`RMCP_TRASH_DIR`).
+    Custom(CustomTrash),
+}
+
+impl Backend {
+    /// Whether listing, restore and purge are available (and compiled in).
+    pub fn supports_listing(&self) -> bool {
+        match self {
+            Self::System => cfg!(all(feature = "full", any(target_os = "linux", target_os = "windows"))),
+            Self::Custom(_) => cfg!(feature = "full"),
+        }
+    }
+
+    /// Directory on the volume where trashed files are stored, if known.
+    pub fn location(&self) -> Option<PathBuf> {
+        match self {
+            Self::System => crate::disk::system_trash_location(),
+            Self::Custom(custom) => Some(custom.root().to_path_buf()),
+        }
+    }
+
+    /// Every directory trashed files may be stored in.
+    pub fn locations(&self) -> Vec<PathBuf> {
+        match self {
+            Self::System => crate::disk::system_trash_locations(),
+            Self::Custom(custom) => vec![custom.root().to_path_buf()],
+        }
+    }
+
+    pub fn delete(&self, path: &Path) -> Result<(), trash::Error> {
+        match self {
+            Self::System => trash::delete(path),
+            Self::Custom(custom) => custom.delete(path),
+        }
+    }
+
+    pub fn delete_all<T: AsRef<Path>>(&self, paths: &[T]) -> Result<(), trash::Error> {
+        match self {
+            Self::System => trash::delete_all(paths),
+            Self::Custom(custom) => paths.iter().try_for_each(|p| custom.delete(p.as_ref())),
+        }
+    }
+
+    #[cfg(feature = "full")]
+    pub fn list(&self) -> Result<Vec<TrashItem>, trash::Error> {
+        match self {
+            #[cfg(any(target_os = "linux", target_os = "windows"))]
+            Self::System => trash::os_limited::list(),
+            #[cfg(not(any(target_os = "linux", target_os = "windows")))]
+            Self::System => Err(unsupported()),
+            Self::Custom(custom) => custom.list(),
+        }
+    }
+
+    #[cfg(feature = "full")]
+    pub fn metadata(&self, item: &TrashItem) -> Result<TrashItemMetadata, trash::Error> {
+        match self {
+            #[cfg(any(target_os = "linux", target_os = "windows"))]
+            Self::System => trash::os_limited::metadata(item),
+            #[cfg(not(any(target_os = "linux", target_os = "windows")))]
+            Self::System => {
+                let _ = item;
+                Err(unsupported())
+            }
+            Self::Custom(custom) => custom.metadata(item),
+        }
+    }
+
+    /// Where the item's data lives on disk, when the platform exposes it.
+    #[cfg(feature = "full")]
+    pub fn data_path(&self, item: &TrashItem) -> Option<PathBuf> {
+        match self {
+            #[cfg(target_os = "windows")]
+            Self::System => {
+                let path = PathBuf::from(&item.id);
+                path.symlink_metadata().is_ok().then_some(path)
+            }
+            #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+            Self::System => {
+                // Freedesktop layout: <trash>/info/<name>.trashinfo -> <trash>/files/<name>
+                let info = Path::new(&item.id);
+                let trash_dir = info.parent()?.parent()?;
+                Some(trash_dir.join("files").join(info.file_stem()?))
+            }
+            #[cfg(target_os = "macos")]
+            Self::System => {
+                let _ = item;
+                None
+            }
+            Self::Custom(custom) => Some(custom.data_path(item)),
+        }
+    }
+
+    /// Move an item's data to `target` instead of its original location and
+    /// drop it from the trash.
+    #[cfg(feature = "full")]
+    pub fn restore_to(&self, item: &TrashItem, target: &Path) -> Result<(), trash::Error> {
+        let data = self.data_path(item).ok_or_else(|| trash::Error::Unknown {
+            description: "the trash location of this item is unknown on this platform".into(),
+        })?;
+        if target.symlink_metadata().is_ok() {
+            return Err(trash::Error::RestoreCollision {
+                path: target.to_path_buf(),
+                remaining_items: vec![item.clone()],
+            });
+        }
+        move_path(&data, target).map_err(io_error)?;
+        if let Some(info) = self.info_path(item) {
+            fs::remove_file(info).map_err(io_error)?;
+        }
+        Ok(())
+    }
+
+    /// The metadata record that keeps an item listed in the trash.
+    #[cfg(feature = "full")]
+    pub fn info_path(&self, item: &TrashItem) -> Option<PathBuf> {
+        match self {
+            #[cfg(target_os = "windows")]
+            Self::System => {
+                // $R<id>.ext holds the data, $I<id>.ext the record
+                let data = Path::new(&item.id);
+                let name = data.file_name()?.to_str()?.strip_prefix("$R")?;
+                Some(data.with_file_name(format!("$I{}", name)))
+            }
+            #[cfg(target_os = "macos")]
+            Self::System => {
+                let _ = item;
+                None
+            }
+            #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+            Self::System => Some(PathBuf::from(&item.id)),
+            Self::Custom(_) => Some(PathBuf::from(&item.id)),
+        }
+    }
+
+    /// Permission bits the item should have once restored: what the custom
+    /// trash recorded, or else the mode its data has inside the trash.
+    ///
+    /// Unix only. Windows carries ACLs along with the move and has no mode to
+    /// compare, so this is always `None` there.
+    #[cfg(feature = "full")]
+    pub fn recorded_mode(&self, item: &TrashItem) -> Option<u32> {
+        if let Self::Custom(_) = self {
+            let sidecar = fs::read(&item.id)
+                .ok()
+                .and_then(|bytes| serde_json::from_slice::<Sidecar>(&bytes).ok());
+            if let Some(mode) = sidecar.and_then(|sidecar| sidecar.mode) {
+                return Some(mode);
+            }
+        }
+        file_mode(&self.data_path(item)?)
+    }
+
+    #[cfg(feature = "full")]
+    pub fn restore_all(&self, items: Vec<TrashItem>) -> Result<(), trash::Error> {
+        match self {
+            #[cfg(any(target_os = "linux", target_os = "windows"))]
+            Self::System => trash::os_limited::restore_all(items),
+            #[cfg(not(any(target_os = "linux", target_os = "windows")))]
+            Self::System => {
+                let _ = items;
+                Err(unsupported())
+            }
+            Self::Custom(custom) => custom.restore_all(items),
+        }
+    }
+
+    #[cfg(feature = "full")]
+    pub fn purge_all(&self, items: Vec<TrashItem>) -> Result<(), trash::Error> {
+        match self {
+            #[cfg(any(target_os = "linux", target_os = "windows"))]
+            Self::System => trash::os_limited::purge_all(items),
+            #[cfg(not(any(target_os = "linux", target_os = "windows")))]
+            Self::System => {
+                let _ = items;
+                Err(unsupported())
+            }
+            Self::Custom(custom) => custom.purge_all(items),
+        }
+    }
+}
+
+#[cfg(all(feature = "full", not(any(target_os = "linux", target_os = "windows"))))]
+fn unsupported() -> trash::Error {
+    trash::Error::Unknown {
+        description: "trash listing is not supported on this platform".into(),
+    }
+}
+
+/// Sidecar written next to every item in the custom trash.
+#[derive(Debug, Serialize, Deserialize)]
+struct Sidecar {
+    original_path: PathBuf,
+    time_deleted: i64,
+    /// Permission bits at trash time (Unix only).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    mode: Option<u32>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CustomTrash {
+    root: PathBuf,
+}
+
+impl CustomTrash {
+    /// Open (and create if needed) a custom trash rooted at `root`.
+    pub fn open(root: impl Into<PathBuf>) -> io::Result<Self> {
+        let root = root.into();
+        fs::create_dir_all(root.join("files"))?;
+        fs::create_dir_all(root.join("info"))?;
+        Ok(Self {
+            root: root.canonicalize()?,
+        })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn files_dir(&self) -> PathBuf {
+        self.root.join("files")
+    }
+
+    fn info_dir(&self) -> PathBuf {
+        self.root.join("info")
+    }
+
+    /// Path of the stored data for an item, derived from its sidecar id.
+    #[cfg(feature = "full")]
+    fn data_path(&self, item: &TrashItem) -> PathBuf {
+        let stem = Path::new(&item.id).file_stem().unwrap_or_default();
+        self.files_dir().join(stem)
+    }
+
+    fn delete(&self, path: &Path) -> Result<(), trash::Error> {
+        let target = absolute(path)?;
+        let name = target.file_name().ok_or(trash::Error::TargetedRoot)?;
+
+        if crate::paths::starts_with(&self.root, &target) {
+            return Err(trash::Error::Unknown {
+                description: format!(
+                    "{} contains the trash directory {}",
+                    target.display(),
+                    self.root.display()
+                ),
+            });
+        }
+        if fs::symlink_metadata(&target).is_err() {
+            return Err(trash::Error::CouldNotAccess {
+                target: target.to_string_lossy().into_owned(),
+            });
+        }
+
+        let sidecar = Sidecar {
+            original_path: target.clone(),
+            time_deleted: now_secs(),
+            mode: file_mode(&target),
+        };
+        let json = serde_json::to_vec_pretty(&sidecar).map_err(trash::into_unknown)?;
+        let (stored_name, info_path) = self.claim_name(name, &json).map_err(io_error)?;
+
+        if let Err(e) = move_path(&target, &self.files_dir().join(&stored_name)) {
+            let _ = fs::remove_file(&info_path);
+            return Err(io_error(e));
+        }
+        Ok(())
+    }
+
+    /// Pick a name under `files/` that isn't taken yet (`name`, `name.1`, ...)
+    /// and claim it by creating its sidecar, holding `json`. Returns the name
+    /// and the sidecar's path.
+    ///
+    /// The sidecar is created with `create_new`, so concurrent deletes of
+    /// files with the same name can't both pick one slot.
+    fn claim_name(&self, name: &std::ffi::OsStr, json: &[u8]) -> io::Result<(OsString, PathBuf)> {
+        use std::io::Write;
+
+        let files = self.files_dir();
+        let info = self.info_dir();
+        let mut candidate = name.to_os_string();
+        let mut n = 1;
+        loop {
+            if files.join(&candidate).symlink_metadata().is_err() {
+                let info_path = info.join(sidecar_file_name(&candidate));
+                match fs::OpenOptions::new().write(true).create_new(true).open(&info_path) {
+                    Ok(mut file) => {
+                        if let Err(e) = file.write_all(json) {
+                            let _ = fs::remove_file(&info_path);
+                            return Err(e);
+                        }
+                        return Ok((candidate, info_path));
+                    }
+                    Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {}
+                    Err(e) => return Err(e),
+                }
+            }
+            candidate = name.to_os_string();
+            candidate.push(format!(".{}", n));
+            n += 1;
+        }
+    }
+
+    #[cfg(feature = "full")]
+    fn list(&self) -> Result<Vec<TrashItem>, trash::Error> {
+        let mut items = Vec::new();
+        for entry in fs::read_dir(self.info_dir()).map_err(io_error)? {
+            let entry = entry.map_err(io_error)?;
+            let path = entry.path();
+            if path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+            let Ok(bytes) = fs::read(&path) else {
+                continue;
+            };
+            let Ok(sidecar) = serde_json::from_slice::<Sidecar>(&bytes) else {
+                tracing::warn!("Skipping unreadable trash sidecar {}", path.display());
+                continue;
+            };
+            let (Some(name), Some(parent)) =
+                (sidecar.original_path.file_name(), sidecar.original_path.parent())
+            else {
+                continue;
+            };
+            items.push(TrashItem {
+                id: path.clone().into_os_string(),
+                name: name.to_os_string(),
+                original_parent: parent.to_path_buf(),
+                time_deleted: sidecar.time_deleted,
+            });
+        }
+        Ok(items)
+    }
+
+    #[cfg(feature = "full")]
+    fn metadata(&self, item: &TrashItem) -> Result<TrashItemMetadata, trash::Error> {
+        let data = self.data_path(item);
+        let meta = fs::symlink_metadata(&data).map_err(io_error)?;
+        let size = if meta.is_dir() {
+            TrashItemSize::Entries(fs::read_dir(&data).map_err(io_error)?.count())
+        } else {
+            TrashItemSize::Bytes(meta.len())
+        };
+        Ok(TrashItemMetadata { size })
+    }
+
+    #[cfg(feature = "full")]
+    fn restore_all(&self, items: Vec<TrashItem>) -> Result<(), trash::Error> {
+        let mut seen = std::collections::HashSet::new();
+        for item in &items {
+            if !seen.insert(crate::paths::fold_case(&item.original_path())) {
+                return Err(trash::Error::RestoreTwins {
+                    path: item.original_path(),
+                    items,
+                });
+            }
+        }
+
+        let mut remaining = items;
+        while !remaining.is_empty() {
+            let item = &remaining[0];
+            let original = item.original_path();
+            if original.symlink_metadata().is_ok() {
+                return Err(trash::Error::RestoreCollision {
+                    path: original,
+                    remaining_items: remaining,
+                });
+            }
+            fs::create_dir_all(&item.original_parent).map_err(io_error)?;
+            move_path(&self.data_path(item), &original).map_err(io_error)?;
+            fs::remove_file(&item.id).map_err(io_error)?;
+            remaining.remove(0);
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "full")]
+    fn purge_all(&self, items: Vec<TrashItem>) -> Result<(), trash::Error> {
+        for item in &items {
+            let data = self.data_path(item);
+            match fs::symlink_metadata(&data) {
+                Ok(meta) if meta.is_dir() => fs::remove_dir_all(&data).map_err(io_error)?,
+                Ok(_) => fs::remove_file(&data).map_err(io_error)?,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                Err(e) => return Err(io_error(e)),
+            }
+            fs::remove_file(&item.id).map_err(io_error)?;
+        }
+        Ok(())
+    }
+}
+
+fn sidecar_file_name(stored_name: &std::ffi::OsStr) -> OsString {
+    let mut name = stored_name.to_os_string();
+    name.push(".json");
+    name
+}
+
+/// Absolute path with a canonical parent, mirroring what the `trash` crate does.
+fn absolute(path: &Path) -> Result<PathBuf, trash::Error> {
+    let target = if path.is_relative() {
+        std::env::current_dir().map_err(io_error)?.join(path)
+    } else {
+        path.to_path_buf()
+    };
+    let parent = target.parent().ok_or(trash::Error::TargetedRoot)?;
+    let parent = parent.canonicalize().map_err(|_| trash::Error::CanonicalizePath {
+        original: parent.to_path_buf(),
+    })?;
+    match target.file_name() {
+        Some(name) => Ok(parent.join(name)),
+        None => Ok(parent),
+    }
+}
+
+/// Rename, falling back to copy + remove when crossing filesystems.
+fn move_path(from: &Path, to: &Path) -> io::Result<()> {
+    match fs::rename(from, to) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+            copy_recursive(from, to)?;
+            if fs::symlink_metadata(from)?.is_dir() {
+                fs::remove_dir_all(from)
+            } else {
+                fs::remove_file(from)
+            }
+        }
+        Err(e) => Err(e),
+    }
+}
+
+fn copy_recursive(from: &Path, to: &Path) -> io::Result<()> {
+    let meta = fs::symlink_metadata(from)?;
+    if meta.is_dir() {
+        fs::create_dir(to)?;
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            copy_recursive(&entry.path(), &to.join(entry.file_name()))?;
+        }
+        fs::set_permissions(to, meta.permissions())
+    } else if meta.file_type().is_symlink() {
+        copy_symlink(from, to)
+    } else {
+        fs::copy(from, to).map(|_| ())
+    }
+}
+
+#[cfg(unix)]
+fn copy_symlink(from: &Path, to: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(fs::read_link(from)?, to)
+}
+
+#[cfg(not(unix))]
+fn copy_symlink(from: &Path, to: &Path) -> io::Result<()> {
+    fs::copy(from, to).map(|_| ())
+}
+
+/// Permission bits of `path` itself (symlinks are not followed).
+#[cfg(unix)]
+pub fn file_mode(path: &Path) -> Option<u32> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::symlink_metadata(path).ok().map(|meta| meta.permissions().mode() & 0o7777)
+}
+
+#[cfg(not(unix))]
+pub fn file_mode(_path: &Path) -> Option<u32> {
+    None
+}
+
+fn now_secs() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+fn io_error(e: io::Error) -> trash::Error {
+    match e.raw_os_error() {
+        Some(code) => trash::Error::Os {
+            code,
+            description: e.to_string(),
+        },
+        None => trash::into_unknown(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::TempDir;
+
+    #[test]
+    fn concurrent_deletes_of_same_named_files_keep_every_file() {
+        let tmp = TempDir::new("custom-race");
+        let trash = CustomTrash::open(tmp.path().join("trash")).unwrap();
+        let originals: Vec<PathBuf> = (0..32).map(|i| tmp.file(&format!("dir{}/same.txt", i), &i.to_string())).collect();
+
+        std::thread::scope(|s| {
+            for original in &originals {
+                let trash = &trash;
+                s.spawn(move || trash.delete(original).unwrap());
+            }
+        });
+
+        // Every sidecar points at the file holding that original's contents
+        let mut seen = Vec::new();
+        for entry in fs::read_dir(trash.info_dir()).unwrap() {
+            let info = entry.unwrap().path();
+            let sidecar: Sidecar = serde_json::from_slice(&fs::read(&info).unwrap()).unwrap();
+            let stored = trash.files_dir().join(info.file_stem().unwrap());
+            let index = originals.iter().position(|o| *o == sidecar.original_path).unwrap();
+            assert_eq!(fs::read_to_string(stored).unwrap(), index.to_string());
+            seen.push(index);
+        }
+        seen.sort_unstable();
+        assert_eq!(seen, (0..32).collect::<Vec<_>>());
+    }
+}
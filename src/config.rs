@@ -0,0 +1,327 @@
+//! Server configuration loaded from the environment at startup.
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Default cap on the human-readable text of a single tool response.
+pub const DEFAULT_MAX_OUTPUT_CHARS: usize = 20_000;
+
+/// Default cap on the length of a path argument, in bytes.
+pub const DEFAULT_MAX_PATH_LEN: usize = 4096;
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Maximum characters of text content per response. `None` disables truncation.
+    /// Set with `RMCP_TRASH_MAX_OUTPUT_CHARS` (`0` disables).
+    pub max_output_chars: Option<usize>,
+    /// Maximum bytes of text plus structured content per response; over it,
+    /// the text is replaced by a short note. Set with
+    /// `RMCP_TRASH_MAX_RESPONSE_BYTES` (unset or `0` disables).
+    pub max_response_bytes: Option<usize>,
+    /// Directory to use as the trash instead of the OS trash (`RMCP_TRASH_DIR`).
+    pub trash_dir: Option<PathBuf>,
+    /// Require `trash_file` callers to repeat the path in `confirm_path`
+    /// (`RMCP_TRASH_REQUIRE_PATH_CONFIRM`).
+    pub require_path_confirm: bool,
+    /// Let callers override safety refusals with `force: true` (`--allow-force`).
+    pub allow_force: bool,
+    /// Extra protected paths, one per line (`RMCP_TRASH_PROTECTED_FILE`).
+    pub protected_file: Option<PathBuf>,
+    /// JSON file defining named profiles (`RMCP_TRASH_PROFILES`).
+    pub profiles_file: Option<PathBuf>,
+    /// Longest path argument accepted, in bytes (`RMCP_TRASH_MAX_PATH_LEN`).
+    pub max_path_len: usize,
+    /// Levels of directories a scan reads below its starting directory
+    /// (`RMCP_TRASH_MAX_WALK_DEPTH`).
+    pub max_walk_depth: usize,
+    /// Directory trashed files wait in before reaching the trash
+    /// (`RMCP_TRASH_STAGING_DIR`). `None` trashes immediately.
+    pub staging_dir: Option<PathBuf>,
+    /// How long files stay staged (`RMCP_TRASH_STAGING_DELAY_SECS`).
+    pub staging_delay: Duration,
+    /// Reject relative paths to the trashing tools unless a profile supplies
+    /// a base directory (`RMCP_TRASH_REQUIRE_ABSOLUTE`).
+    pub require_absolute: bool,
+    /// Note when a trashed path is tracked by git (`RMCP_TRASH_GIT_WARNINGS`).
+    pub git_warnings: bool,
+    /// Cap on calls to tools that change files (`RMCP_TRASH_RATE_LIMIT`).
+    pub rate_limit: Option<crate::ratelimit::RateLimit>,
+    /// Tools to offer; `None` offers all (`RMCP_TRASH_ENABLED_TOOLS`).
+    pub enabled_tools: Option<Vec<String>>,
+    /// Tools to withhold, even if enabled (`RMCP_TRASH_DISABLED_TOOLS`).
+    pub disabled_tools: Vec<String>,
+    /// Phrase `empty_trash` callers must pass as `confirmation`
+    /// (`RMCP_TRASH_EMPTY_PHRASE`). `None` needs `confirm: true` instead.
+    pub empty_phrase: Option<String>,
+    /// Purge old trash items periodically (`RMCP_TRASH_SCHEDULE`).
+    pub schedule: Option<crate::schedule::Schedule>,
+    /// Prepended to every tool name, e.g. `rb_` for `rb_trash_file`
+    /// (`RMCP_TRASH_TOOL_PREFIX`). The enabled and disabled tool lists still
+    /// use the unprefixed names.
+    pub tool_prefix: Option<String>,
+    /// JSON file keeping undo groups, usage snapshots and the paths
+    /// `redo_last_restore` would trash across restarts
+    /// (`RMCP_TRASH_STATE_FILE`). `None` keeps them in memory only.
+    pub state_file: Option<PathBuf>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            max_output_chars: Some(DEFAULT_MAX_OUTPUT_CHARS),
+            max_response_bytes: None,
+            trash_dir: None,
+            require_path_confirm: false,
+            allow_force: false,
+            protected_file: None,
+            profiles_file: None,
+            max_path_len: DEFAULT_MAX_PATH_LEN,
+            max_walk_depth: crate::walk::DEFAULT_MAX_DEPTH,
+            staging_dir: None,
+            staging_delay: crate::staging::DEFAULT_DELAY,
+            require_absolute: false,
+            git_warnings: false,
+            rate_limit: None,
+            enabled_tools: None,
+            disabled_tools: Vec::new(),
+            empty_phrase: None,
+            schedule: None,
+            tool_prefix: None,
+            state_file: None,
+        }
+    }
+}
+
+impl Config {
+    /// Read the configuration, rejecting values that can't be parsed.
+    pub fn from_env() -> anyhow::Result<Self> {
+        let mut config = Self::default();
+
+        if let Ok(value) = std::env::var("RMCP_TRASH_MAX_OUTPUT_CHARS") {
+            match value.trim().parse::<usize>() {
+                Ok(0) => config.max_output_chars = None,
+                Ok(n) => config.max_output_chars = Some(n),
+                Err(_) => anyhow::bail!(
+                    "RMCP_TRASH_MAX_OUTPUT_CHARS must be a non-negative integer, got {:?}",
+                    value
+                ),
+            }
+        }
+
+        if let Ok(value) = std::env::var("RMCP_TRASH_MAX_RESPONSE_BYTES") {
+            match value.trim().parse::<usize>() {
+                Ok(0) => config.max_response_bytes = None,
+                Ok(n) => config.max_response_bytes = Some(n),
+                Err(_) => anyhow::bail!(
+                    "RMCP_TRASH_MAX_RESPONSE_BYTES must be a non-negative integer, got {:?}",
+                    value
+                ),
+            }
+        }
+
+        if let Some(dir) = std::env::var_os("RMCP_TRASH_DIR").filter(|d| !d.is_empty()) {
+            config.trash_dir = Some(PathBuf::from(dir));
+        }
+
+        if let Some(file) = std::env::var_os("RMCP_TRASH_PROTECTED_FILE").filter(|f| !f.is_empty()) {
+            config.protected_file = Some(PathBuf::from(file));
+        }
+
+        if let Some(file) = std::env::var_os("RMCP_TRASH_PROFILES").filter(|f| !f.is_empty()) {
+            config.profiles_file = Some(PathBuf::from(file));
+        }
+
+        if let Ok(value) = std::env::var("RMCP_TRASH_MAX_PATH_LEN") {
+            match value.trim().parse::<usize>() {
+                Ok(n) if n > 0 => config.max_path_len = n,
+                _ => anyhow::bail!("RMCP_TRASH_MAX_PATH_LEN must be a positive integer, got {:?}", value),
+            }
+        }
+
+        if let Ok(value) = std::env::var("RMCP_TRASH_MAX_WALK_DEPTH") {
+            match value.trim().parse::<usize>() {
+                Ok(n) if n > 0 => config.max_walk_depth = n,
+                _ => anyhow::bail!("RMCP_TRASH_MAX_WALK_DEPTH must be a positive integer, got {:?}", value),
+            }
+        }
+
+        if let Some(dir) = std::env::var_os("RMCP_TRASH_STAGING_DIR").filter(|d| !d.is_empty()) {
+            config.staging_dir = Some(PathBuf::from(dir));
+        }
+
+        if let Some(file) = std::env::var_os("RMCP_TRASH_STATE_FILE").filter(|f| !f.is_empty()) {
+            config.state_file = Some(PathBuf::from(file));
+        }
+
+        if let Ok(value) = std::env::var("RMCP_TRASH_STAGING_DELAY_SECS") {
+            match value.trim().parse::<u64>() {
+                Ok(secs) => config.staging_delay = Duration::from_secs(secs),
+                Err(_) => anyhow::bail!(
+                    "RMCP_TRASH_STAGING_DELAY_SECS must be a non-negative integer, got {:?}",
+                    value
+                ),
+            }
+        }
+
+        if let Ok(value) = std::env::var("RMCP_TRASH_RATE_LIMIT") {
+            if !value.trim().is_empty() {
+                match crate::ratelimit::RateLimit::parse(&value) {
+                    Some(limit) => config.rate_limit = Some(limit),
+                    None => anyhow::bail!(
+                        "RMCP_TRASH_RATE_LIMIT must look like 30/min, 5/s or 100/h, got {:?}",
+                        value
+                    ),
+                }
+            }
+        }
+
+        if let Ok(value) = std::env::var("RMCP_TRASH_SCHEDULE") {
+            if !value.trim().is_empty() {
+                match crate::schedule::Schedule::parse(&value) {
+                    Some(schedule) => config.schedule = Some(schedule),
+                    None => anyhow::bail!(
+                        "RMCP_TRASH_SCHEDULE must look like 30d or 30d/6h (age, then how often to run), got {:?}",
+                        value
+                    ),
+                }
+            }
+        }
+
+        if let Ok(value) = std::env::var("RMCP_TRASH_TOOL_PREFIX") {
+            let prefix = value.trim();
+            // The characters MCP allows in tool names
+            if !prefix.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.')) {
+                anyhow::bail!(
+                    "RMCP_TRASH_TOOL_PREFIX may only contain letters, digits, `_`, `-` and `.`, got {:?}",
+                    value
+                );
+            }
+            if !prefix.is_empty() {
+                config.tool_prefix = Some(prefix.to_string());
+            }
+        }
+
+        if let Some(names) = env_list("RMCP_TRASH_ENABLED_TOOLS") {
+            config.enabled_tools = Some(names);
+        }
+        config.disabled_tools = env_list("RMCP_TRASH_DISABLED_TOOLS").unwrap_or_default();
+
+        if let Ok(phrase) = std::env::var("RMCP_TRASH_EMPTY_PHRASE") {
+            if !phrase.trim().is_empty() {
+                config.empty_phrase = Some(phrase.trim().to_string());
+            }
+        }
+
+        config.require_path_confirm = env_flag("RMCP_TRASH_REQUIRE_PATH_CONFIRM")?;
+        config.require_absolute = env_flag("RMCP_TRASH_REQUIRE_ABSOLUTE")?;
+        config.git_warnings = env_flag("RMCP_TRASH_GIT_WARNINGS")?;
+
+        Ok(config)
+    }
+
+    /// Check settings that refer to the filesystem before anything uses them.
+    ///
+    /// File contents are parsed later by their loaders, which fail start-up too.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        for (var, dir) in [("RMCP_TRASH_DIR", &self.trash_dir), ("RMCP_TRASH_STAGING_DIR", &self.staging_dir)] {
+            if let Some(dir) = dir {
+                if dir.exists() && !dir.is_dir() {
+                    anyhow::bail!("{} {} exists but is not a directory", var, dir.display());
+                }
+            }
+        }
+        if let Some(file) = &self.state_file {
+            // Created on the first save, so it only has to be able to exist
+            if file.is_dir() {
+                anyhow::bail!("RMCP_TRASH_STATE_FILE {} is a directory", file.display());
+            }
+            if file.parent().is_some_and(|dir| !dir.as_os_str().is_empty() && !dir.is_dir()) {
+                anyhow::bail!("RMCP_TRASH_STATE_FILE {} is not in an existing directory", file.display());
+            }
+        }
+        for (var, file) in [
+            ("RMCP_TRASH_PROTECTED_FILE", &self.protected_file),
+            ("RMCP_TRASH_PROFILES", &self.profiles_file),
+        ] {
+            if let Some(file) = file {
+                if !file.is_file() {
+                    anyhow::bail!("{} {} does not exist or is not a file", var, file.display());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Log the settings in effect, one line per setting.
+    pub fn log_effective(&self) {
+        let unset = || "(unset)".to_string();
+        let path = |p: &Option<PathBuf>| p.as_ref().map(|p| p.display().to_string()).unwrap_or_else(unset);
+        tracing::info!("Effective configuration:");
+        tracing::info!(
+            "  max_output_chars = {}",
+            self.max_output_chars.map(|n| n.to_string()).unwrap_or_else(|| "unlimited".into())
+        );
+        tracing::info!(
+            "  max_response_bytes = {}",
+            self.max_response_bytes.map(|n| n.to_string()).unwrap_or_else(|| "unlimited".into())
+        );
+        tracing::info!("  trash_dir = {}", path(&self.trash_dir));
+        tracing::info!("  require_path_confirm = {}", self.require_path_confirm);
+        tracing::info!("  allow_force = {}", self.allow_force);
+        tracing::info!("  protected_file = {}", path(&self.protected_file));
+        tracing::info!("  profiles_file = {}", path(&self.profiles_file));
+        tracing::info!("  max_path_len = {}", self.max_path_len);
+        tracing::info!("  max_walk_depth = {}", self.max_walk_depth);
+        tracing::info!("  staging_dir = {}", path(&self.staging_dir));
+        tracing::info!("  staging_delay_secs = {}", self.staging_delay.as_secs());
+        tracing::info!("  require_absolute = {}", self.require_absolute);
+        tracing::info!("  git_warnings = {}", self.git_warnings);
+        tracing::info!(
+            "  rate_limit = {}",
+            self.rate_limit.map(|limit| limit.to_string()).unwrap_or_else(|| "unlimited".into())
+        );
+        tracing::info!(
+            "  enabled_tools = {}",
+            self.enabled_tools.as_ref().map(|names| names.join(",")).unwrap_or_else(|| "(all)".into())
+        );
+        tracing::info!("  disabled_tools = {}", self.disabled_tools.join(","));
+        tracing::info!("  tool_prefix = {}", self.tool_prefix.as_deref().unwrap_or("(none)"));
+        tracing::info!("  state_file = {}", path(&self.state_file));
+        tracing::info!(
+            "  schedule = {}",
+            self.schedule.map(|schedule| schedule.to_string()).unwrap_or_else(|| "(off)".into())
+        );
+        // Logs reach the client, so the phrase itself is never shown
+        tracing::info!("  empty_phrase = {}", if self.empty_phrase.is_some() { "(set)" } else { "(unset)" });
+    }
+
+    /// Whether the tool called `name` is offered under the enabled and disabled lists.
+    pub fn tool_enabled(&self, name: &str) -> bool {
+        self.enabled_tools.as_ref().is_none_or(|names| names.iter().any(|n| n == name))
+            && !self.disabled_tools.iter().any(|n| n == name)
+    }
+}
+
+/// A comma-separated environment variable, or `None` if unset or empty.
+fn env_list(name: &str) -> Option<Vec<String>> {
+    let value = std::env::var(name).ok()?;
+    let items: Vec<String> = value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_owned)
+        .collect();
+    (!items.is_empty()).then_some(items)
+}
+
+/// A boolean environment variable; unset or empty is `false`.
+fn env_flag(name: &str) -> anyhow::Result<bool> {
+    let Ok(value) = std::env::var(name) else {
+        return Ok(false);
+    };
+    match value.trim().to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Ok(true),
+        "" | "0" | "false" | "no" | "off" => Ok(false),
+        _ => anyhow::bail!("{} must be a boolean (1/0, true/false, yes/no, on/off), got {:?}", name, value),
+    }
+}
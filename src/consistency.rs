@@ -0,0 +1,161 @@
+//! Detection of trash metadata and data that have lost their counterpart.
+//!
+//! Both supported layouts keep one metadata file per item in `info/` and the
+//! item itself in `files/`: the XDG trash names them `<name>.trashinfo` and
+//! `<name>`, the custom backend `<name>.json` and `<name>`.
+
+use crate::backend::Backend;
+use serde::Serialize;
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy)]
+enum Format {
+    /// XDG `.trashinfo` key file.
+    TrashInfo,
+    /// Custom backend JSON sidecar.
+    Sidecar,
+}
+
+impl Format {
+    fn suffix(self) -> &'static str {
+        match self {
+            Self::TrashInfo => ".trashinfo",
+            Self::Sidecar => ".json",
+        }
+    }
+
+    /// Whether `contents` has what the format requires to restore the item.
+    fn is_valid(self, contents: &[u8]) -> bool {
+        match self {
+            Self::TrashInfo => {
+                let text = String::from_utf8_lossy(contents);
+                let mut lines = text.lines().map(str::trim).filter(|line| !line.is_empty());
+                lines.next() == Some("[Trash Info]") && {
+                    let keys: Vec<&str> = lines.filter_map(|line| line.split_once('=')).map(|(k, _)| k).collect();
+                    keys.contains(&"Path") && keys.contains(&"DeletionDate")
+                }
+            }
+            Self::Sidecar => serde_json::from_slice::<serde_json::Value>(contents)
+                .is_ok_and(|value| value.get("original_path").is_some_and(|p| p.is_string())),
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct DirCheck {
+    pub dir: String,
+    /// Metadata files whose item is missing from `files/`.
+    pub orphaned_metadata: Vec<String>,
+    /// Items in `files/` with no metadata file.
+    pub orphaned_data: Vec<String>,
+    /// Metadata files that can't be parsed; left alone even with `fix`.
+    pub invalid_metadata: Vec<String>,
+    /// Orphans removed because `fix` was set.
+    pub removed: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl DirCheck {
+    pub fn is_consistent(&self) -> bool {
+        self.orphaned_metadata.is_empty()
+            && self.orphaned_data.is_empty()
+            && self.invalid_metadata.is_empty()
+            && self.error.is_none()
+    }
+}
+
+/// Trash directories `backend` keeps metadata in, with their format.
+///
+/// Only the XDG trash (Linux) and the custom backend have a layout that can
+/// be inspected.
+fn layouts(backend: &Backend) -> Vec<(PathBuf, Format)> {
+    match backend {
+        Backend::System if cfg!(target_os = "linux") => crate::disk::system_trash_locations()
+            .into_iter()
+            .map(|dir| (dir, Format::TrashInfo))
+            .collect(),
+        Backend::System => Vec::new(),
+        Backend::Custom(custom) => vec![(custom.root().to_path_buf(), Format::Sidecar)],
+    }
+}
+
+/// Check every trash directory of `backend`, or `None` when its layout is opaque.
+///
+/// With `fix`, orphaned metadata files are deleted and orphaned data is
+/// permanently deleted.
+pub fn check(backend: &Backend, fix: bool) -> Option<Vec<DirCheck>> {
+    let layouts = layouts(backend);
+    if layouts.is_empty() {
+        return None;
+    }
+    Some(
+        layouts
+            .into_iter()
+            .map(|(dir, format)| {
+                let mut report = DirCheck {
+                    dir: dir.to_string_lossy().into_owned(),
+                    ..Default::default()
+                };
+                if let Err(e) = check_dir(&dir, format, fix, &mut report) {
+                    report.error = Some(e.to_string());
+                }
+                report
+            })
+            .collect(),
+    )
+}
+
+fn check_dir(dir: &Path, format: Format, fix: bool, report: &mut DirCheck) -> io::Result<()> {
+    let info_dir = dir.join("info");
+    let files_dir = dir.join("files");
+    let suffix = format.suffix();
+
+    for entry in read_dir_or_empty(&info_dir)? {
+        let path = entry?.path();
+        let Some(name) = path.file_name().and_then(|n| n.to_str()?.strip_suffix(suffix).map(str::to_owned)) else {
+            continue;
+        };
+        let valid = fs::read(&path).is_ok_and(|contents| format.is_valid(&contents));
+        if !valid {
+            report.invalid_metadata.push(path.to_string_lossy().into_owned());
+        } else if files_dir.join(&name).symlink_metadata().is_err() {
+            report.orphaned_metadata.push(path.to_string_lossy().into_owned());
+            if fix {
+                fs::remove_file(&path)?;
+                report.removed += 1;
+            }
+        }
+    }
+
+    for entry in read_dir_or_empty(&files_dir)? {
+        let path = entry?.path();
+        let mut info_name = OsString::from(path.file_name().unwrap_or_default());
+        info_name.push(suffix);
+        if info_dir.join(info_name).symlink_metadata().is_ok() {
+            continue;
+        }
+        report.orphaned_data.push(path.to_string_lossy().into_owned());
+        if fix {
+            if path.symlink_metadata()?.is_dir() {
+                fs::remove_dir_all(&path)?;
+            } else {
+                fs::remove_file(&path)?;
+            }
+            report.removed += 1;
+        }
+    }
+    Ok(())
+}
+
+/// Entries of `dir`, treating a missing directory as empty.
+fn read_dir_or_empty(dir: &Path) -> io::Result<Box<dyn Iterator<Item = io::Result<fs::DirEntry>>>> {
+    match fs::read_dir(dir) {
+        Ok(entries) => Ok(Box::new(entries)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Box::new(std::iter::empty())),
+        Err(e) => Err(e),
+    }
+}
@@ -0,0 +1,197 @@
+//! Filesystem capacity and mount-flag queries (statvfs / GetDiskFreeSpaceExW).
+
+use serde::Serialize;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct DiskSpace {
+    pub total_bytes: u64,
+    pub used_bytes: u64,
+    /// Space available to this process (excludes root-reserved blocks on Unix).
+    pub free_bytes: u64,
+}
+
+/// Capacity of the filesystem containing `path`.
+///
+/// A path that doesn't exist yet is resolved to its nearest existing ancestor.
+pub fn disk_space(path: &Path) -> io::Result<DiskSpace> {
+    let existing = nearest_existing(path).ok_or_else(|| {
+        io::Error::new(io::ErrorKind::NotFound, format!("no existing ancestor of {}", path.display()))
+    })?;
+    query(&existing)
+}
+
+fn nearest_existing(path: &Path) -> Option<PathBuf> {
+    path.ancestors().find(|p| p.exists()).map(Path::to_path_buf)
+}
+
+#[cfg(unix)]
+fn statvfs(path: &Path) -> io::Result<libc::statvfs> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = CString::new(path.as_os_str().as_bytes())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    // SAFETY: `c_path` is a valid NUL-terminated string and `stat` is a
+    // properly sized out-parameter.
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(stat)
+}
+
+#[cfg(unix)]
+fn query(path: &Path) -> io::Result<DiskSpace> {
+    let stat = statvfs(path)?;
+    let block = stat.f_frsize as u64;
+    let total = stat.f_blocks as u64 * block;
+    let free_all = stat.f_bfree as u64 * block;
+    Ok(DiskSpace {
+        total_bytes: total,
+        used_bytes: total.saturating_sub(free_all),
+        free_bytes: stat.f_bavail as u64 * block,
+    })
+}
+
+#[cfg(windows)]
+fn query(path: &Path) -> io::Result<DiskSpace> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::Storage::FileSystem::GetDiskFreeSpaceExW;
+
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+    let (mut available, mut total, mut free_all) = (0u64, 0u64, 0u64);
+    // SAFETY: `wide` is NUL-terminated and the out-parameters are valid u64s.
+    let ok = unsafe { GetDiskFreeSpaceExW(wide.as_ptr(), &mut available, &mut total, &mut free_all) };
+    if ok == 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(DiskSpace {
+        total_bytes: total,
+        used_bytes: total.saturating_sub(free_all),
+        free_bytes: available,
+    })
+}
+
+#[cfg(not(any(unix, windows)))]
+fn query(_path: &Path) -> io::Result<DiskSpace> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "disk space is not available on this platform"))
+}
+
+/// Whether the filesystem containing `path` is mounted read-only.
+///
+/// Read from the statvfs mount flags; always false where those aren't
+/// available.
+#[cfg(unix)]
+pub fn is_read_only(path: &Path) -> bool {
+    nearest_existing(path)
+        .and_then(|existing| statvfs(&existing).ok())
+        .is_some_and(|stat| stat.f_flag & libc::ST_RDONLY != 0)
+}
+
+#[cfg(not(unix))]
+pub fn is_read_only(_path: &Path) -> bool {
+    false
+}
+
+/// The mount location that `path` lives under when that volume isn't
+/// currently attached, e.g. `/media/me/USB` for a drive that was unplugged.
+///
+/// Only removable-media locations are considered (`/media`, `/run/media`,
+/// `/mnt`, `/Volumes`, or a missing drive letter on Windows), so an ordinary
+/// directory that was deleted isn't mistaken for a missing volume.
+#[cfg(feature = "full")]
+pub fn unavailable_volume(path: &Path) -> Option<PathBuf> {
+    #[cfg(windows)]
+    {
+        let root: PathBuf = path.components().take(2).collect();
+        (!root.as_os_str().is_empty() && !root.exists()).then_some(root)
+    }
+
+    #[cfg(not(windows))]
+    {
+        const MOUNT_BASES: &[&str] = &["/media", "/run/media", "/mnt", "/Volumes"];
+
+        if path.exists() {
+            return None;
+        }
+        let anchor = nearest_existing(path)?;
+        let next = path.strip_prefix(&anchor).ok()?.components().next()?;
+        // A mounted volume whose directory was deleted is still available
+        if crate::guard::is_mount_point(&anchor) {
+            return None;
+        }
+        let under_base = |dir: &Path| MOUNT_BASES.iter().any(|base| dir == Path::new(base));
+        // /media/<user>/<label> and /run/media/<user>/<label> nest one level deeper
+        let per_user = |dir: &Path| dir == Path::new("/media") || dir == Path::new("/run/media");
+        if under_base(&anchor) || anchor.parent().is_some_and(per_user) {
+            Some(anchor.join(next))
+        } else {
+            None
+        }
+    }
+}
+
+/// Where trashed files end up for the OS trash on this platform.
+pub fn system_trash_location() -> Option<PathBuf> {
+    #[cfg(target_os = "windows")]
+    {
+        std::env::var("SystemDrive").ok().map(|drive| PathBuf::from(format!("{}\\", drive)))
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        crate::paths::home_dir().map(|home| home.join(".Trash"))
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    {
+        std::env::var_os("XDG_DATA_HOME")
+            .filter(|d| !d.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| crate::paths::home_dir().map(|home| home.join(".local/share")))
+            .map(|data| data.join("Trash"))
+    }
+}
+
+/// Every trash directory the OS trash may use: the home trash plus one per
+/// mounted volume that has one.
+pub fn system_trash_locations() -> Vec<PathBuf> {
+    // One $Recycle.Bin per drive; the per-user folder inside is named by SID
+    #[cfg(target_os = "windows")]
+    let mut locations: Vec<PathBuf> = (b'A'..=b'Z')
+        .map(|letter| PathBuf::from(format!("{}:\\$Recycle.Bin", letter as char)))
+        .filter(|bin| bin.is_dir())
+        .collect();
+
+    #[cfg(not(target_os = "windows"))]
+    let mut locations: Vec<PathBuf> = system_trash_location().into_iter().collect();
+
+    #[cfg(target_os = "macos")]
+    {
+        // SAFETY: getuid has no preconditions and cannot fail.
+        let uid = unsafe { libc::getuid() };
+        if let Ok(volumes) = std::fs::read_dir("/Volumes") {
+            locations.extend(
+                volumes
+                    .flatten()
+                    .map(|volume| volume.path().join(".Trashes").join(uid.to_string()))
+                    .filter(|trash| trash.is_dir()),
+            );
+        }
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    {
+        // Includes $topdir/.Trash/$uid and $topdir/.Trash-$uid on every mount
+        if let Ok(folders) = trash::os_limited::trash_folders() {
+            locations.extend(folders);
+        }
+    }
+
+    locations.sort();
+    locations.dedup();
+    locations
+}
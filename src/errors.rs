@@ -0,0 +1,211 @@
+//! Turning `trash::Error`s into messages an agent can act on.
+
+use serde::Serialize;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Message for a failed trash operation on `target` (if known).
+pub fn trash_failure_message(target: Option<&Path>, e: &trash::Error) -> String {
+    if is_read_only_fs(target, e) {
+        let path = target.map(Path::to_path_buf).or_else(|| error_path(e));
+        let mount = path
+            .as_deref()
+            .and_then(|p| p.ancestors().find(|dir| crate::guard::is_mount_point(dir)))
+            .map(|dir| format!(" (mounted at {})", dir.display()))
+            .unwrap_or_default();
+        return format!(
+            "{} is on a read-only filesystem{} and cannot be modified; nothing on it can be moved \
+             to the trash until it is remounted read-write.\n(Details: {})",
+            path.map(|p| p.display().to_string()).unwrap_or_else(|| "One of the paths".into()),
+            mount,
+            e
+        );
+    }
+    if is_permission_denied(e) {
+        let path = target
+            .map(Path::to_path_buf)
+            .or_else(|| error_path(e))
+            .map(|p| p.display().to_string())
+            .unwrap_or_else(|| "one of the paths".into());
+        return format!(
+            "Permission denied trashing {}. {}\n(Details: {})",
+            path,
+            permission_remedy(target),
+            e
+        );
+    }
+    format!("Failed to trash: {}", e)
+}
+
+/// Whether the error is an EACCES/EPERM-style refusal from the OS.
+pub fn is_permission_denied(e: &trash::Error) -> bool {
+    match e {
+        #[cfg(all(unix, not(target_os = "macos"), not(target_os = "ios"), not(target_os = "android")))]
+        trash::Error::FileSystem { source, .. } => source.kind() == io::ErrorKind::PermissionDenied,
+        trash::Error::Os { code, .. } => os_code_kind(*code) == io::ErrorKind::PermissionDenied,
+        _ => false,
+    }
+}
+
+/// Whether the error comes from a read-only filesystem (EROFS, or a
+/// write-protected volume on Windows), or `target` sits on a filesystem
+/// whose mount flags say it is read-only.
+pub fn is_read_only_fs(target: Option<&Path>, e: &trash::Error) -> bool {
+    let kind = match e {
+        #[cfg(all(unix, not(target_os = "macos"), not(target_os = "ios"), not(target_os = "android")))]
+        trash::Error::FileSystem { source, .. } => Some(source.kind()),
+        trash::Error::Os { code, .. } => Some(os_code_kind(*code)),
+        _ => None,
+    };
+    if kind == Some(io::ErrorKind::ReadOnlyFilesystem) {
+        return true;
+    }
+    match target {
+        Some(target) => crate::disk::is_read_only(target),
+        None => error_path(e).is_some_and(|path| crate::disk::is_read_only(&path)),
+    }
+}
+
+/// The path the `trash` crate reported, where the variant carries one.
+fn error_path(e: &trash::Error) -> Option<PathBuf> {
+    match e {
+        #[cfg(all(unix, not(target_os = "macos"), not(target_os = "ios"), not(target_os = "android")))]
+        trash::Error::FileSystem { path, .. } => Some(path.clone()),
+        _ => None,
+    }
+}
+
+/// Map a raw OS code to an `io::ErrorKind`, unwrapping Win32 HRESULTs.
+fn os_code_kind(code: i32) -> io::ErrorKind {
+    const FACILITY_WIN32: u32 = 0x8007_0000;
+    const ERROR_WRITE_PROTECT: u32 = 19;
+    let code = code as u32;
+    let raw = if code & 0xFFFF_0000 == FACILITY_WIN32 {
+        code & 0xFFFF
+    } else {
+        code
+    };
+    // std leaves the write-protected media error uncategorized
+    if cfg!(windows) && raw == ERROR_WRITE_PROTECT {
+        return io::ErrorKind::ReadOnlyFilesystem;
+    }
+    io::Error::from_raw_os_error(raw as i32).kind()
+}
+
+#[cfg(target_os = "windows")]
+fn permission_remedy(target: Option<&Path>) -> String {
+    let read_only = target
+        .and_then(|p| std::fs::metadata(p).ok())
+        .is_some_and(|m| m.permissions().readonly());
+    if read_only {
+        let path = target.map(|p| p.display().to_string()).unwrap_or_default();
+        format!(
+            "The file has the read-only attribute set; clear it with `attrib -r \"{}\"` and retry.",
+            path
+        )
+    } else {
+        "Check that your account has delete permission on the file and its folder, \
+         and that it isn't owned by another user or protected by an administrator policy."
+            .into()
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn permission_remedy(_target: Option<&Path>) -> String {
+    "Check the file's ownership and that its parent directory is writable (`ls -ld`); \
+     files under System Integrity Protection (e.g. /System, /usr) cannot be trashed, \
+     and the server may need Full Disk Access for protected user folders."
+        .into()
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn permission_remedy(target: Option<&Path>) -> String {
+    let parent = target
+        .and_then(Path::parent)
+        .map(|p| p.display().to_string())
+        .unwrap_or_else(|| "the parent directory".into());
+    format!(
+        "Moving a file requires write permission on its parent directory ({}); \
+         check ownership with `ls -ld` and fix it with chown/chmod, or run the server as the owning user.",
+        parent
+    )
+}
+
+/// A `trash::Error` broken into its parts, for bug reports and for agents
+/// that branch on OS error codes (`last_error_detail`).
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorDetail {
+    /// The `trash::Error` variant, e.g. `FileSystem` or `Os`.
+    pub variant: &'static str,
+    pub message: String,
+    /// Raw OS error code (errno, or an HRESULT/Win32 code on Windows).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub os_code: Option<i32>,
+    /// `io::ErrorKind` of the code, e.g. `PermissionDenied`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub io_kind: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// Trash items involved, for restore collisions and twins.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub items: Vec<String>,
+    /// Unix time the error was recorded.
+    pub at: i64,
+}
+
+impl ErrorDetail {
+    pub fn of(e: &trash::Error) -> Self {
+        let names = |items: &[trash::TrashItem]| -> Vec<String> {
+            items.iter().map(|item| item.original_path().to_string_lossy().into_owned()).collect()
+        };
+        let mut detail = Self {
+            variant: "",
+            message: e.to_string(),
+            os_code: None,
+            io_kind: None,
+            path: error_path(e).map(|p| p.to_string_lossy().into_owned()),
+            items: Vec::new(),
+            at: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs() as i64),
+        };
+        detail.variant = match e {
+            trash::Error::Unknown { .. } => "Unknown",
+            trash::Error::Os { code, .. } => {
+                detail.os_code = Some(*code);
+                detail.io_kind = Some(format!("{:?}", os_code_kind(*code)));
+                "Os"
+            }
+            #[cfg(all(unix, not(target_os = "macos"), not(target_os = "ios"), not(target_os = "android")))]
+            trash::Error::FileSystem { source, .. } => {
+                detail.os_code = source.raw_os_error();
+                detail.io_kind = Some(format!("{:?}", source.kind()));
+                "FileSystem"
+            }
+            trash::Error::TargetedRoot => "TargetedRoot",
+            trash::Error::CouldNotAccess { target } => {
+                detail.path = Some(target.clone());
+                "CouldNotAccess"
+            }
+            trash::Error::CanonicalizePath { original } => {
+                detail.path = Some(original.to_string_lossy().into_owned());
+                "CanonicalizePath"
+            }
+            trash::Error::ConvertOsString { original } => {
+                detail.path = Some(original.to_string_lossy().into_owned());
+                "ConvertOsString"
+            }
+            trash::Error::RestoreCollision { path, remaining_items } => {
+                detail.path = Some(path.to_string_lossy().into_owned());
+                detail.items = names(remaining_items);
+                "RestoreCollision"
+            }
+            trash::Error::RestoreTwins { path, items } => {
+                detail.path = Some(path.to_string_lossy().into_owned());
+                detail.items = names(items);
+                "RestoreTwins"
+            }
+        };
+        detail
+    }
+}
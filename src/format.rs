@@ -0,0 +1,47 @@
+//! Human-readable formatting helpers.
+
+/// Format a byte count with binary units, e.g. `1.5 GiB`.
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 6] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+/// Format a duration in its largest whole unit, e.g. `3 h` or `12 days`.
+pub fn format_duration(secs: u64) -> String {
+    match secs {
+        0..60 => format!("{} s", secs),
+        60..3_600 => format!("{} min", secs / 60),
+        3_600..86_400 => format!("{} h", secs / 3_600),
+        _ => format!("{} days", secs / 86_400),
+    }
+}
+
+/// One-line outcome of a trashing call, ready to show as is, e.g.
+/// `Moved 3 items (2.1 MiB) to trash; 1 skipped`. The size is left out when
+/// unknown (directories aren't measured).
+pub fn trash_summary(moved: usize, bytes: Option<u64>, skipped: usize, dry_run: bool) -> String {
+    let mut summary = format!(
+        "{} {} item{}",
+        if dry_run { "Would move" } else { "Moved" },
+        moved,
+        if moved == 1 { "" } else { "s" }
+    );
+    if let Some(bytes) = bytes.filter(|_| moved > 0) {
+        summary.push_str(&format!(" ({})", format_bytes(bytes)));
+    }
+    summary.push_str(" to trash");
+    if skipped > 0 {
+        summary.push_str(&format!("; {} skipped", skipped));
+    }
+    summary
+}
@@ -0,0 +1,40 @@
+//! Advisory check for files tracked by git (`RMCP_TRASH_GIT_WARNINGS`).
+
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Working tree containing `path`: the nearest ancestor with a `.git` entry
+/// (a directory, or a file for worktrees and submodules).
+fn work_tree(path: &Path) -> Option<PathBuf> {
+    path.ancestors()
+        .skip(1)
+        .find(|dir| dir.join(".git").symlink_metadata().is_ok())
+        .map(Path::to_path_buf)
+}
+
+/// Whether git tracks `path` (for a directory, anything below it).
+///
+/// Any failure to run git counts as untracked, since this only drives a hint.
+pub fn is_tracked(path: &Path) -> bool {
+    let Some(tree) = work_tree(path) else {
+        return false;
+    };
+    Command::new("git")
+        .arg("-C")
+        .arg(&tree)
+        .args(["ls-files", "--error-unmatch", "--"])
+        .arg(path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+/// The note shown for tracked paths.
+pub fn tracked_note(path: &Path) -> String {
+    format!(
+        "Note: {} is tracked by git; `git rm` would also record the removal in the repository",
+        path.display()
+    )
+}
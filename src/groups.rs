@@ -0,0 +1,74 @@
+//! Undo groups: paths trashed under a caller-chosen name (`group` on the
+//! trashing tools), so `restore_group` and `purge_group` can act on them as
+//! one unit.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// One trashed path recorded in a group.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Member {
+    /// Absolute path it was trashed from.
+    pub original: PathBuf,
+    /// Unix time it was trashed.
+    pub trashed_at: i64,
+}
+
+#[derive(Debug, Default)]
+pub struct UndoGroups {
+    groups: Mutex<BTreeMap<String, Vec<Member>>>,
+}
+
+impl UndoGroups {
+    pub fn from_map(groups: BTreeMap<String, Vec<Member>>) -> Self {
+        Self { groups: Mutex::new(groups) }
+    }
+
+    /// Every group and its members, for the state file.
+    pub fn to_map(&self) -> BTreeMap<String, Vec<Member>> {
+        self.groups.lock().unwrap().clone()
+    }
+
+    /// Add `originals`, trashed just now, to `group`.
+    pub fn record(&self, group: &str, originals: impl IntoIterator<Item = PathBuf>) {
+        let trashed_at = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs() as i64);
+        let mut groups = self.groups.lock().unwrap();
+        let members = groups.entry(group.to_string()).or_default();
+        members.extend(originals.into_iter().map(|original| Member { original, trashed_at }));
+        if members.is_empty() {
+            groups.remove(group);
+        }
+    }
+
+    pub fn members(&self, group: &str) -> Option<Vec<Member>> {
+        self.groups.lock().unwrap().get(group).cloned()
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.groups.lock().unwrap().keys().cloned().collect()
+    }
+
+    /// Drop the members of `group` for which `done` holds, and the group
+    /// itself once it is empty.
+    pub fn remove(&self, group: &str, done: impl Fn(&Member) -> bool) {
+        let mut groups = self.groups.lock().unwrap();
+        if let Some(members) = groups.get_mut(group) {
+            members.retain(|member| !done(member));
+            if members.is_empty() {
+                groups.remove(group);
+            }
+        }
+    }
+}
+
+/// The `group` argument, trimmed, or an error message if it is blank.
+pub fn validate(group: Option<&str>) -> Result<Option<&str>, String> {
+    match group.map(str::trim) {
+        Some("") => Err("`group` must not be empty".into()),
+        other => Ok(other),
+    }
+}
@@ -0,0 +1,398 @@
+//! Safety checks that refuse dangerous trash targets before touching them.
+
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// Where a protection rule came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleSource {
+    /// Compiled-in system locations.
+    Builtin,
+    /// Listed in the file named by `RMCP_TRASH_PROTECTED_FILE`.
+    ProtectedFile,
+    /// Listed in the `protected` entries of the active profile.
+    Profile,
+    /// The path is the root of a mounted filesystem.
+    MountPoint,
+    /// The path is the server's working directory or one of its ancestors.
+    WorkingDirectory,
+}
+
+impl RuleSource {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Builtin => "built-in protected path",
+            Self::ProtectedFile => "protected paths file",
+            Self::Profile => "profile rule",
+            Self::MountPoint => "mount point",
+            Self::WorkingDirectory => "working directory",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Rule {
+    path: PathBuf,
+    /// Protect everything below `path` too, not just `path` itself.
+    subtree: bool,
+    source: RuleSource,
+}
+
+/// The rule that protects a given path.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProtectedMatch {
+    pub source: RuleSource,
+    /// The protected path that matched (the target itself for mount points,
+    /// the working directory for its ancestors).
+    pub rule: String,
+}
+
+/// Paths that must never be trashed.
+#[derive(Debug, Clone)]
+pub struct ProtectedPaths {
+    rules: Vec<Rule>,
+}
+
+impl Default for ProtectedPaths {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+impl ProtectedPaths {
+    /// Filesystem roots, the home directory, and core system directories.
+    pub fn builtin() -> Self {
+        let mut rules = Vec::new();
+        let mut exact = |path: PathBuf| {
+            rules.push(Rule {
+                path,
+                subtree: false,
+                source: RuleSource::Builtin,
+            })
+        };
+        if let Some(home) = crate::paths::home_dir() {
+            exact(canonical_or_self(home));
+        }
+
+        #[cfg(windows)]
+        let system: Vec<PathBuf> = {
+            let var = |name: &str| std::env::var_os(name).map(PathBuf::from);
+            [var("SystemRoot"), var("ProgramFiles"), var("ProgramFiles(x86)"), var("ProgramData")]
+                .into_iter()
+                .flatten()
+                .collect()
+        };
+        #[cfg(target_os = "macos")]
+        let system: Vec<PathBuf> = ["/System", "/Library", "/bin", "/sbin", "/usr", "/private/etc", "/cores"]
+            .into_iter()
+            .map(PathBuf::from)
+            .collect();
+        #[cfg(not(any(windows, target_os = "macos")))]
+        let system: Vec<PathBuf> = [
+            "/bin", "/boot", "/dev", "/etc", "/lib", "/lib32", "/lib64", "/proc", "/run", "/sbin", "/sys",
+            "/usr",
+        ]
+        .into_iter()
+        .map(PathBuf::from)
+        .collect();
+
+        rules.extend(system.into_iter().map(|path| Rule {
+            path: canonical_or_self(path),
+            subtree: true,
+            source: RuleSource::Builtin,
+        }));
+        Self { rules }
+    }
+
+    /// Protect `path` and everything below it.
+    pub fn add(&mut self, path: PathBuf, source: RuleSource) {
+        self.rules.push(Rule {
+            path: canonical_or_self(path),
+            subtree: true,
+            source,
+        });
+    }
+
+    /// Add the entries of a protected paths file: one path per line, `#`
+    /// starts a comment. Each entry protects itself and everything below it.
+    pub fn load_file(&mut self, file: &Path) -> std::io::Result<usize> {
+        let contents = std::fs::read_to_string(file)?;
+        let before = self.rules.len();
+        for line in contents.lines() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            self.add(crate::paths::expand(line), RuleSource::ProtectedFile);
+        }
+        Ok(self.rules.len() - before)
+    }
+
+    /// The first rule protecting `path`, which should already be resolved
+    /// with [`crate::paths::resolve`].
+    pub fn check(&self, path: &Path) -> Option<ProtectedMatch> {
+        let folded = crate::paths::fold_case(path);
+        let hit = self.rules.iter().find(|rule| {
+            let rule_path = crate::paths::fold_case(&rule.path);
+            if rule.subtree {
+                folded.starts_with(&rule_path)
+            } else {
+                folded == rule_path
+            }
+        });
+        if let Some(rule) = hit {
+            return Some(ProtectedMatch {
+                source: rule.source,
+                rule: rule.path.display().to_string(),
+            });
+        }
+        if path.parent().is_none() || is_mount_point(path) {
+            return Some(ProtectedMatch {
+                source: RuleSource::MountPoint,
+                rule: path.display().to_string(),
+            });
+        }
+        // Relative paths resolve against the working directory, so it has to stay put
+        if let Ok(cwd) = std::env::current_dir() {
+            let cwd = canonical_or_self(cwd);
+            if crate::paths::starts_with(&cwd, path) {
+                return Some(ProtectedMatch {
+                    source: RuleSource::WorkingDirectory,
+                    rule: cwd.display().to_string(),
+                });
+            }
+        }
+        None
+    }
+}
+
+fn canonical_or_self(path: PathBuf) -> PathBuf {
+    crate::paths::canonicalize_existing(&path)
+}
+
+/// Whether `path` is the root of a mounted filesystem.
+///
+/// Symlinks are never mount points themselves: trashing the link is harmless.
+#[cfg(unix)]
+pub fn is_mount_point(path: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    let Ok(meta) = path.symlink_metadata() else {
+        return false;
+    };
+    if !meta.is_dir() {
+        return false;
+    }
+    let Ok(canonical) = path.canonicalize() else {
+        return false;
+    };
+    match canonical.parent() {
+        // The filesystem root
+        None => true,
+        Some(parent) => parent
+            .metadata()
+            .map(|parent_meta| parent_meta.dev() != meta.dev() || parent_meta.ino() == meta.ino())
+            .unwrap_or(false),
+    }
+}
+
+/// Whether `path` is a drive root or a volume mounted into a folder.
+#[cfg(windows)]
+pub fn is_mount_point(path: &Path) -> bool {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::Storage::FileSystem::GetVolumePathNameW;
+
+    let Ok(meta) = path.symlink_metadata() else {
+        return false;
+    };
+    if !meta.is_dir() {
+        return false;
+    }
+    let Ok(canonical) = path.canonicalize() else {
+        return false;
+    };
+
+    let wide: Vec<u16> = canonical.as_os_str().encode_wide().chain(Some(0)).collect();
+    let mut volume = vec![0u16; wide.len() + 1];
+    // SAFETY: `wide` is NUL-terminated and `volume` is at least as long as the input.
+    let ok = unsafe { GetVolumePathNameW(wide.as_ptr(), volume.as_mut_ptr(), volume.len() as u32) };
+    if ok == 0 {
+        return canonical.parent().is_none();
+    }
+    let len = volume.iter().position(|&c| c == 0).unwrap_or(volume.len());
+    let volume = String::from_utf16_lossy(&volume[..len]);
+    let trim = |s: &str| s.trim_end_matches('\\').to_lowercase();
+    trim(&volume) == trim(&canonical.to_string_lossy())
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn is_mount_point(path: &Path) -> bool {
+    path.parent().is_none()
+}
+
+/// Whether another process appears to hold `path` open in a way that would
+/// make trashing it misbehave.
+///
+/// On Unix this only sees advisory locks (`flock`), so a file that is merely
+/// open is not reported. Directories are never reported.
+#[cfg(unix)]
+pub fn in_use(path: &Path) -> bool {
+    use std::os::unix::io::AsRawFd;
+
+    if !path.symlink_metadata().is_ok_and(|meta| meta.is_file()) {
+        return false;
+    }
+    let Ok(file) = std::fs::File::open(path) else {
+        return false;
+    };
+    // SAFETY: the descriptor belongs to `file`, which outlives the call; the
+    // lock is released when `file` is dropped.
+    let locked = unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } != 0;
+    locked && std::io::Error::last_os_error().raw_os_error() == Some(libc::EWOULDBLOCK)
+}
+
+/// Whether another process has `path` open without allowing it to be shared,
+/// which would make the move into the recycle bin fail.
+#[cfg(windows)]
+pub fn in_use(path: &Path) -> bool {
+    use std::os::windows::fs::OpenOptionsExt;
+    const ERROR_SHARING_VIOLATION: i32 = 32;
+
+    if !path.symlink_metadata().is_ok_and(|meta| meta.is_file()) {
+        return false;
+    }
+    std::fs::OpenOptions::new()
+        .read(true)
+        .share_mode(0)
+        .open(path)
+        .is_err_and(|e| e.raw_os_error() == Some(ERROR_SHARING_VIOLATION))
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn in_use(_path: &Path) -> bool {
+    false
+}
+
+/// The file a path named when it was checked, so a swap before it is
+/// trashed (renamed over, or deleted and recreated) can be noticed.
+///
+/// Only Unix exposes a stable identity (device and inode); elsewhere
+/// [`Identity::of`] returns `None` and nothing is compared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Identity {
+    dev: u64,
+    ino: u64,
+}
+
+impl Identity {
+    #[cfg(unix)]
+    pub fn of(path: &Path) -> Option<Self> {
+        use std::os::unix::fs::MetadataExt;
+
+        let meta = path.symlink_metadata().ok()?;
+        Some(Self {
+            dev: meta.dev(),
+            ino: meta.ino(),
+        })
+    }
+
+    #[cfg(not(unix))]
+    pub fn of(_path: &Path) -> Option<Self> {
+        None
+    }
+
+    /// Whether `path` still names the file seen when `checked` was taken;
+    /// true when there was nothing to compare.
+    pub fn unchanged(checked: Option<Self>, path: &Path) -> bool {
+        checked.is_none_or(|checked| Self::of(path) == Some(checked))
+    }
+}
+
+/// Other hard links to the file at `path`, which keep its data on disk after
+/// this name is trashed. Always 0 for directories, and off Unix.
+#[cfg(unix)]
+pub fn other_links(path: &Path) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+
+    path.symlink_metadata()
+        .ok()
+        .filter(|meta| !meta.is_dir())
+        .map_or(0, |meta| meta.nlink().saturating_sub(1))
+}
+
+#[cfg(not(unix))]
+pub fn other_links(_path: &Path) -> u64 {
+    0
+}
+
+/// The note shown for trashed files that still have other hard links.
+pub fn hard_link_note(path: &Path, links: u64) -> String {
+    format!(
+        "Note: {} has {} other hard link(s); its data stays on disk, reachable through them, until those are removed too",
+        path.display(),
+        links
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::paths::resolve;
+
+    /// No rules, so only the mount point and working directory checks apply.
+    fn unprotected() -> ProtectedPaths {
+        ProtectedPaths { rules: Vec::new() }
+    }
+
+    #[test]
+    fn current_directory_is_refused() {
+        let hit = unprotected().check(&resolve(Path::new("."))).unwrap();
+        assert_eq!(hit.source, RuleSource::WorkingDirectory);
+        let cwd = std::env::current_dir().unwrap().canonicalize().unwrap();
+        assert_eq!(hit.rule, cwd.display().to_string());
+    }
+
+    #[test]
+    fn ancestor_of_current_directory_is_refused() {
+        assert!(unprotected().check(&resolve(Path::new(".."))).is_some());
+    }
+
+    #[test]
+    fn entries_below_current_directory_are_allowed() {
+        assert!(unprotected().check(&resolve(Path::new("src"))).is_none());
+    }
+
+    /// `home/safe/../protected`, as `~/safe/../protected` expands, is the
+    /// protected directory.
+    #[test]
+    fn parent_components_cannot_evade_a_rule() {
+        let home = crate::testutil::TempDir::new("guard-dotdot");
+        home.dir("safe");
+        let mut rules = unprotected();
+        rules.add(home.dir("protected"), RuleSource::ProtectedFile);
+
+        for input in ["safe/../protected", "safe/../protected/file.txt", "./safe/./../protected/"] {
+            let hit = rules.check(&resolve(&home.path().join(input)));
+            assert_eq!(hit.map(|hit| hit.source), Some(RuleSource::ProtectedFile), "{}", input);
+        }
+        assert!(rules.check(&resolve(&home.path().join("protected/../safe"))).is_none());
+    }
+
+    /// When `safe` is a symlink, `safe/..` is the link target's parent, as it
+    /// is for the filesystem, not `home`.
+    #[cfg(unix)]
+    #[test]
+    fn parent_components_through_a_symlink_follow_the_link() {
+        let home = crate::testutil::TempDir::new("guard-dotdot-link");
+        home.dir("elsewhere/deep");
+        let protected = home.dir("elsewhere/protected");
+        std::os::unix::fs::symlink(home.path().join("elsewhere/deep"), home.path().join("safe")).unwrap();
+        let mut rules = unprotected();
+        rules.add(protected.clone(), RuleSource::ProtectedFile);
+
+        let resolved = resolve(&home.path().join("safe/../protected"));
+        assert_eq!(resolved, protected);
+        assert_eq!(rules.check(&resolved).map(|hit| hit.source), Some(RuleSource::ProtectedFile));
+    }
+}
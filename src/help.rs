@@ -0,0 +1,179 @@
+//! Tool reference for the `help` tool, built from the router's tool list so
+//! it always matches what the server actually offers.
+
+use rmcp::model::Tool;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+#[derive(Debug, Serialize)]
+pub struct HelpResult {
+    pub tools: Vec<ToolHelp>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ToolHelp {
+    pub name: String,
+    pub description: String,
+    pub params: Vec<ParamHelp>,
+    /// Arguments for a call that only sets the required parameters.
+    pub example: Value,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ParamHelp {
+    pub name: String,
+    /// JSON type, e.g. `string` or `array of string`.
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub required: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Accepted values, for enum parameters.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub values: Vec<String>,
+}
+
+pub fn describe(tool: &Tool) -> ToolHelp {
+    let schema = &tool.input_schema;
+    let defs = schema.get("$defs").and_then(Value::as_object);
+    let required: Vec<&str> = schema
+        .get("required")
+        .and_then(Value::as_array)
+        .map(|names| names.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+
+    let mut params: Vec<ParamHelp> = schema
+        .get("properties")
+        .and_then(Value::as_object)
+        .into_iter()
+        .flatten()
+        .map(|(name, property)| {
+            let (kind, values) = kind_of(property, defs);
+            ParamHelp {
+                name: name.clone(),
+                kind,
+                required: required.contains(&name.as_str()),
+                description: property.get("description").and_then(Value::as_str).map(str::to_owned),
+                values,
+            }
+        })
+        .collect();
+    // Required parameters first; the schema lists properties by name
+    params.sort_by_key(|param| !param.required);
+
+    let example: Map<String, Value> = params
+        .iter()
+        .filter(|param| param.required)
+        .map(|param| (param.name.clone(), example_value(param)))
+        .collect();
+    ToolHelp {
+        name: tool.name.to_string(),
+        description: tool.description.as_deref().unwrap_or_default().to_owned(),
+        params,
+        example: Value::Object(example),
+    }
+}
+
+/// Type name and enum values of a property, following `$ref`s into the
+/// schema's `$defs` and looking past the `null` branch of optional enums.
+fn kind_of(schema: &Value, defs: Option<&Map<String, Value>>) -> (String, Vec<String>) {
+    if let Some(target) = schema.get("$ref").and_then(Value::as_str) {
+        let resolved = target
+            .strip_prefix("#/$defs/")
+            .and_then(|name| defs?.get(name));
+        return match resolved {
+            Some(def) => kind_of(def, defs),
+            None => ("object".into(), Vec::new()),
+        };
+    }
+    if let Some(branches) = schema.get("anyOf").and_then(Value::as_array) {
+        if let Some(branch) = branches.iter().find(|branch| branch.get("const") != Some(&Value::Null)) {
+            return kind_of(branch, defs);
+        }
+    }
+    let consts: Vec<String> = schema
+        .get("oneOf")
+        .and_then(Value::as_array)
+        .map(|branches| {
+            branches
+                .iter()
+                .filter_map(|branch| branch.get("const").and_then(Value::as_str).map(str::to_owned))
+                .collect()
+        })
+        .or_else(|| {
+            schema
+                .get("enum")
+                .and_then(Value::as_array)
+                .map(|values| values.iter().filter_map(Value::as_str).map(str::to_owned).collect())
+        })
+        .unwrap_or_default();
+    if !consts.is_empty() {
+        return ("string".into(), consts);
+    }
+
+    let kind = match schema.get("type") {
+        Some(Value::String(kind)) => kind.clone(),
+        // e.g. ["string", "null"]
+        Some(Value::Array(kinds)) => kinds
+            .iter()
+            .filter_map(Value::as_str)
+            .find(|kind| *kind != "null")
+            .unwrap_or("null")
+            .to_owned(),
+        _ => "any".into(),
+    };
+    if kind == "array" {
+        if let Some(items) = schema.get("items") {
+            return (format!("array of {}", kind_of(items, defs).0), Vec::new());
+        }
+    }
+    (kind, Vec::new())
+}
+
+/// A plausible value for a required parameter, picked from its name and type.
+fn example_value(param: &ParamHelp) -> Value {
+    if let Some(first) = param.values.first() {
+        return json!(first);
+    }
+    let name = param.name.as_str();
+    let text = if name.contains("pattern") || name == "glob" {
+        "*.log"
+    } else if name.contains("dir") || name == "destination" {
+        "/home/user/Downloads"
+    } else if name.contains("path") || name.contains("file") {
+        "/home/user/notes.txt"
+    } else {
+        "..."
+    };
+    match param.kind.as_str() {
+        "string" => json!(text),
+        "array of string" => json!([text]),
+        "integer" | "number" => json!(1),
+        "boolean" => json!(true),
+        kind if kind.starts_with("array") => json!([]),
+        _ => json!({}),
+    }
+}
+
+/// Text block for one tool: description, parameters and the example call.
+pub fn render(help: &ToolHelp) -> String {
+    let mut out = format!("{}: {}", help.name, help.description);
+    for param in &help.params {
+        let kind = if param.values.is_empty() {
+            param.kind.clone()
+        } else {
+            param.values.join(" | ")
+        };
+        out.push_str(&format!(
+            "\n  {} ({}{})",
+            param.name,
+            kind,
+            if param.required { ", required" } else { "" }
+        ));
+        if let Some(description) = &param.description {
+            out.push_str(&format!(": {}", description));
+        }
+    }
+    out.push_str(&format!("\n  Example: {} {}", help.name, help.example));
+    out
+}
@@ -0,0 +1,3818 @@
+//! Listing, restore and purge tools, built with the `full` feature.
+//!
+//! Everything here needs to enumerate the trash, which the `trash` crate only
+//! supports through `os_limited` on Linux and Windows (or via the custom
+//! backend). Builds without the feature only move files to the trash.
+
+use crate::profiles::Scope;
+use crate::progress::Progress;
+use crate::{consistency, disk, format, groups, hash, mime, paths, pattern, script, wipe};
+use crate::{known_total_size, tree_size, BatchTiming, EntryResult, EntryStatus, Outcome, TrashServer};
+use rmcp::{handler::server::wrapper::Parameters, model::*, service::RequestContext, ErrorData as McpError, RoleServer};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+// === Parameter Types ===
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct RestoreParams {
+    #[schemars(description = "Name of the file to restore from trash (partial match supported)")]
+    pub name: Option<String>,
+    #[schemars(description = "Exact percent-encoded raw name (`name_encoded` from list_trash), for names that aren't valid UTF-8; used instead of `name`")]
+    pub name_encoded: Option<String>,
+    #[schemars(description = "Existing directory to restore into when an item's original volume is no longer mounted")]
+    pub destination: Option<String>,
+    #[schemars(description = "Profile whose base directory and protected rules apply")]
+    pub profile: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct CheckRestoreParams {
+    #[schemars(description = "Name of the trashed item to check (partial match supported)")]
+    pub name: Option<String>,
+    #[schemars(description = "Exact percent-encoded raw name (`name_encoded` from list_trash), for names that aren't valid UTF-8; used instead of `name`")]
+    pub name_encoded: Option<String>,
+    #[schemars(description = "Profile whose base directory limits which trash items are checked")]
+    pub profile: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct RestoreFromDirParams {
+    #[schemars(description = "Original directory the items were trashed from; every item whose original parent is this directory is restored")]
+    pub dir: String,
+    #[schemars(description = "Profile whose base directory and protected rules apply")]
+    pub profile: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct RestoreLastFromDirParams {
+    #[schemars(description = "Original directory; the most recently trashed item that was at or below it is restored")]
+    pub dir: String,
+    #[schemars(description = "Profile whose base directory and protected rules apply")]
+    pub profile: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum OnConflict {
+    /// Leave the colliding item in the trash.
+    #[default]
+    Skip,
+    /// Restore next to the existing file under a new name.
+    Rename,
+    /// Restore nothing if any item would collide.
+    Abort,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct RestoreAllParams {
+    #[schemars(description = "Only report which items would restore and which would collide; restore nothing")]
+    pub dry_run: Option<bool>,
+    #[schemars(description = "What to do when an original path is occupied: skip (default), rename, or abort")]
+    pub on_conflict: Option<OnConflict>,
+    #[schemars(description = "Profile whose base directory limits which trash items are affected")]
+    pub profile: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct RestoreMatchingParams {
+    #[schemars(description = "Pattern matched (case-insensitively) against each item's name and original path")]
+    pub pattern: String,
+    #[schemars(description = "How to match: substring (default), exact, or glob (`*` and `?` wildcards)")]
+    pub match_kind: Option<pattern::MatchKind>,
+    #[schemars(description = "Must be true to actually restore; not needed with dry_run")]
+    pub confirm: Option<bool>,
+    #[schemars(description = "Only list the items that would be restored")]
+    pub dry_run: Option<bool>,
+    #[schemars(description = "Existing directory to restore under instead of the original locations, recreating each item's original directory structure below it (e.g. /home/me/a.txt goes to <dest_root>/home/me/a.txt)")]
+    pub dest_root: Option<String>,
+    #[schemars(description = "Profile whose base directory limits which trash items are affected")]
+    pub profile: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct RestoreGlobParams {
+    #[schemars(description = "Shell glob matched (case-insensitively) against each item's name, e.g. `*.pdf`")]
+    pub glob: String,
+    #[schemars(description = "What to do when an original path is occupied: skip (default), rename, or abort")]
+    pub on_conflict: Option<OnConflict>,
+    #[schemars(description = "Must be true to actually restore; not needed with dry_run")]
+    pub confirm: Option<bool>,
+    #[schemars(description = "Only report what each item would do")]
+    pub dry_run: Option<bool>,
+    #[schemars(description = "Existing directory to restore under instead of the original locations, recreating each item's original directory structure below it (e.g. /home/me/a.txt goes to <dest_root>/home/me/a.txt)")]
+    pub dest_root: Option<String>,
+    #[schemars(description = "Profile whose base directory limits which trash items are affected")]
+    pub profile: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct RestoreRecentParams {
+    #[schemars(description = "Restore items trashed in the last this many minutes")]
+    pub minutes: Option<u64>,
+    #[schemars(description = "Restore items trashed in the last this many hours; give this or minutes")]
+    pub hours: Option<u64>,
+    #[schemars(description = "What to do when an original path is occupied: skip (default), rename, or abort")]
+    pub on_conflict: Option<OnConflict>,
+    #[schemars(description = "Only report what each item would do")]
+    pub dry_run: Option<bool>,
+    #[schemars(description = "Profile whose base directory limits which trash items are affected")]
+    pub profile: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct RestoreGroupParams {
+    #[schemars(description = "Undo group given as `group` when the items were trashed")]
+    pub group: String,
+    #[schemars(description = "What to do when an original path is occupied: skip (default), rename, or abort")]
+    pub on_conflict: Option<OnConflict>,
+    #[schemars(description = "Only report what each item would do")]
+    pub dry_run: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct PurgeGroupParams {
+    #[schemars(description = "Undo group given as `group` when the items were trashed")]
+    pub group: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ExportFromTrashParams {
+    #[schemars(description = "Pattern matched (case-insensitively) against each item's name and original path")]
+    pub pattern: String,
+    #[schemars(description = "How to match: substring (default), exact, or glob (`*` and `?` wildcards)")]
+    pub match_kind: Option<pattern::MatchKind>,
+    #[schemars(description = "Existing directory the items are copied into")]
+    pub destination: String,
+    #[schemars(description = "Permanently delete each item from the trash once it has been copied out")]
+    pub purge_after: Option<bool>,
+    #[schemars(description = "Profile whose base directory limits which trash items are affected")]
+    pub profile: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct VerifyInventoryParams {
+    #[schemars(description = "File containing a previous list_trash structured result")]
+    pub inventory_path: Option<String>,
+    #[schemars(description = "A previous list_trash structured result, inline (`{ count, items }`); used instead of inventory_path")]
+    pub inventory: Option<serde_json::Value>,
+    #[schemars(description = "Profile whose base directory limits which trash items are compared")]
+    pub profile: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ListTrashParams {
+    #[schemars(description = "Order of the items: newest (most recently deleted first, default), oldest, name or path")]
+    pub sort_by: Option<SortBy>,
+    #[schemars(description = "Skip this many items of the sorted listing (default 0), for paging with `limit`")]
+    pub offset: Option<usize>,
+    #[schemars(description = "Return at most this many items. A page is not a complete inventory for verify_inventory")]
+    pub limit: Option<usize>,
+    #[schemars(description = "For items whose name has no known extension, read the first bytes of the trashed data to detect its type (slower on large listings)")]
+    pub sniff_content: Option<bool>,
+    #[schemars(description = "Also return the items as a tree built from their original paths (`tree`), and show the text listing that way, to see the directory structure of what was deleted")]
+    pub as_tree: Option<bool>,
+    #[schemars(description = "Profile whose base directory limits which trash items are listed")]
+    pub profile: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SortBy {
+    /// Most recently deleted first.
+    #[default]
+    Newest,
+    Oldest,
+    /// By name, then newest first.
+    Name,
+    /// By original path, then newest first.
+    Path,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ListDirInTrashParams {
+    #[schemars(description = "Original directory; every trashed item that was at or below it is listed")]
+    pub dir: String,
+    #[schemars(description = "Show the text listing as an indented tree instead of flat paths")]
+    pub as_tree: Option<bool>,
+    #[schemars(description = "Profile whose base directory limits which trash items are listed")]
+    pub profile: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ConsistencyCheckParams {
+    #[schemars(description = "Delete orphaned metadata and permanently delete orphaned data files. Unparseable metadata is only reported")]
+    pub fix: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct TrashDuplicatesParams {
+    #[schemars(description = "Only hash trashed files at least this many bytes large (default 1, skipping empty files)")]
+    pub min_size_bytes: Option<u64>,
+    #[schemars(description = "Only hash trashed files at most this many bytes large")]
+    pub max_size_bytes: Option<u64>,
+    #[schemars(description = "Profile whose base directory limits which trash items are affected")]
+    pub profile: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct TrashAgeHistogramParams {
+    #[schemars(description = "Bucket boundaries in days since deletion, increasing (default [1, 7, 30, 90], giving <1d, 1-7d, 7-30d, 30-90d and >=90d)")]
+    pub boundaries_days: Option<Vec<u64>>,
+    #[schemars(description = "Profile whose base directory limits which trash items are affected")]
+    pub profile: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct PurgePreviewParams {
+    #[schemars(description = "Patterns matched (case-insensitively) against each item's name and original path; an item matching any of them is included")]
+    pub patterns: Vec<String>,
+    #[schemars(description = "How to match: substring (default), exact, or glob (`*` and `?` wildcards)")]
+    pub match_kind: Option<pattern::MatchKind>,
+    #[schemars(description = "Profile whose base directory limits which trash items are affected")]
+    pub profile: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct PermanentlyDeleteParams {
+    #[schemars(description = "Exact name of the trashed item(s) to delete permanently")]
+    pub name: Option<String>,
+    #[schemars(description = "Exact percent-encoded raw name (`name_encoded` from list_trash), for names that aren't valid UTF-8; used instead of `name`")]
+    pub name_encoded: Option<String>,
+    #[schemars(description = "Overwrite file contents with zeros before deleting (best-effort: not reliable on SSDs or copy-on-write filesystems). Directories have each contained file wiped; files with other hard links are not wiped, since that would destroy the other copies")]
+    pub secure: Option<bool>,
+    #[schemars(description = "Profile whose base directory limits which trash items are affected")]
+    pub profile: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct EmptyTrashParams {
+    #[schemars(description = "The confirmation phrase, when the server requires one (RMCP_TRASH_EMPTY_PHRASE). Ask the user for it; it is not available to tools")]
+    pub confirmation: Option<String>,
+    #[schemars(description = "Must be true to actually empty the trash, when the server requires no confirmation phrase")]
+    pub confirm: Option<bool>,
+    #[schemars(description = "Profile whose base directory limits which trash items are affected")]
+    pub profile: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ProfileParams {
+    #[schemars(description = "Profile whose base directory limits which trash items are affected")]
+    pub profile: Option<String>,
+}
+
+// === Structured Output ===
+
+/// The `list_trash` payload, which doubles as the inventory format for
+/// `verify_inventory`.
+#[derive(Debug, Serialize, Deserialize)]
+struct TrashListing {
+    count: usize,
+    items: Vec<TrashEntry>,
+    /// Items in the whole listing, of which `items` may be one page.
+    #[serde(default)]
+    total: usize,
+    /// `offset` for the next page, when there is one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    next_offset: Option<usize>,
+    /// `items` nested by original path, one root per volume, with `as_tree`.
+    #[serde(default, skip_deserializing, skip_serializing_if = "Option::is_none")]
+    tree: Option<Vec<TreeNode>>,
+}
+
+/// One path component in the `as_tree` view of `list_trash`.
+#[derive(Debug, Default, Serialize)]
+struct TreeNode {
+    /// Component name; for a root, the volume's root such as `/` or `C:\`.
+    name: String,
+    /// Items trashed from exactly this path, several if it was trashed more than once.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    items: Vec<TrashEntry>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    children: Vec<TreeNode>,
+}
+
+impl TreeNode {
+    /// Nest `entries` by the components of their original paths.
+    fn build(entries: &[TrashEntry]) -> Vec<Self> {
+        use std::path::Component;
+
+        let mut roots = Vec::new();
+        for entry in entries {
+            let mut root = String::new();
+            let mut parts = Vec::new();
+            for component in Path::new(&entry.original_path).components() {
+                match component {
+                    Component::Prefix(_) | Component::RootDir => root.push_str(&component.as_os_str().to_string_lossy()),
+                    other => parts.push(other.as_os_str().to_string_lossy().into_owned()),
+                }
+            }
+            let mut node = Self::child(&mut roots, root);
+            for part in parts {
+                node = Self::child(&mut node.children, part);
+            }
+            node.items.push(entry.clone());
+        }
+        Self::sort(&mut roots);
+        roots
+    }
+
+    fn child(nodes: &mut Vec<Self>, name: String) -> &mut Self {
+        match nodes.iter().position(|node| node.name == name) {
+            Some(index) => &mut nodes[index],
+            None => {
+                nodes.push(Self { name, ..Default::default() });
+                nodes.last_mut().unwrap()
+            }
+        }
+    }
+
+    fn sort(nodes: &mut [Self]) {
+        nodes.sort_by(|a, b| a.name.cmp(&b.name));
+        for node in nodes {
+            Self::sort(&mut node.children);
+        }
+    }
+
+    /// Indented text lines; directories that only hold other items end in `/`.
+    fn render(&self, depth: usize, out: &mut String) {
+        out.push_str(&format!("\n{}{}", "  ".repeat(depth), self.name));
+        if !self.children.is_empty() && !self.name.ends_with(['/', '\\']) {
+            out.push('/');
+        }
+        match self.items.len() {
+            0 => {}
+            1 if self.children.is_empty() => {}
+            1 => out.push_str(" (trashed)"),
+            n => out.push_str(&format!(" (trashed {} times)", n)),
+        }
+        for child in &self.children {
+            child.render(depth + 1, out);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct TrashEntry {
+    /// Display name; invalid UTF-8 is replaced with U+FFFD.
+    name: String,
+    /// Percent-encoded raw name, accepted by restore and purge tools.
+    #[serde(default)]
+    name_encoded: String,
+    original_path: String,
+    time_deleted: i64,
+    /// Detected type, from the extension or (with `sniff_content`) the content.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    mime_type: Option<String>,
+}
+
+impl TrashEntry {
+    /// What identifies the same trashed item across two listings.
+    fn key(&self) -> (&str, i64) {
+        (&self.original_path, self.time_deleted)
+    }
+}
+
+impl From<&trash::TrashItem> for TrashEntry {
+    fn from(item: &trash::TrashItem) -> Self {
+        Self {
+            name: item.name.to_string_lossy().into_owned(),
+            name_encoded: paths::encode_name(&item.name),
+            original_path: item.original_path().to_string_lossy().into_owned(),
+            time_deleted: item.time_deleted,
+            mime_type: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct RestorableListing {
+    /// Items whose original volume is attached, so they can be restored now.
+    restorable: Vec<TrashEntry>,
+    /// Items from a volume that isn't mounted at the moment.
+    unavailable: Vec<StrandedEntry>,
+}
+
+#[derive(Debug, Serialize)]
+struct StrandedEntry {
+    #[serde(flatten)]
+    entry: TrashEntry,
+    /// Mount location of the missing volume, e.g. `/media/me/USB`.
+    volume: String,
+}
+
+#[derive(Debug, Serialize)]
+struct InventoryDiff {
+    /// In the trash now but not in the inventory.
+    added: Vec<TrashEntry>,
+    /// In the inventory but no longer in the trash (restored or purged).
+    removed: Vec<TrashEntry>,
+    unchanged: usize,
+}
+
+/// Trashed files with identical content.
+#[derive(Debug, Serialize)]
+struct DuplicateGroup {
+    sha256: String,
+    size_bytes: u64,
+    /// The most recently trashed copy.
+    keep: TrashEntry,
+    /// The other copies, safe to purge if only one is wanted.
+    purge_candidates: Vec<TrashEntry>,
+}
+
+#[derive(Debug, Serialize)]
+struct DuplicateReport {
+    /// Files whose content was hashed (only those sharing a size with another).
+    hashed: usize,
+    groups: Vec<DuplicateGroup>,
+    /// Bytes freed by purging every candidate.
+    reclaimable_bytes: u64,
+    /// Files that couldn't be read, with the reason.
+    errors: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct RedoRestoreResult {
+    items: Vec<EntryResult>,
+    trashed: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct EmptyTrashResult {
+    purged: usize,
+    /// Total size of the purged items whose size is known.
+    reclaimed_bytes: u64,
+    /// Items left in the trash, with the reason.
+    failed: Vec<String>,
+    timing: BatchTiming,
+}
+
+/// Where a restored item came from and where it ended up.
+#[derive(Debug, Serialize)]
+struct RestoredItem {
+    original_path: String,
+    restored_path: String,
+    /// Whether `restored_path` is the original location, rather than a
+    /// destination override or a rename that avoided a collision.
+    in_place: bool,
+    /// `restored_path` without lossy conversion, for `redo_last_restore`.
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl RestoredItem {
+    fn new(original: &Path, restored: &Path) -> Self {
+        Self {
+            original_path: original.to_string_lossy().into_owned(),
+            restored_path: restored.to_string_lossy().into_owned(),
+            in_place: original == restored,
+            path: restored.to_path_buf(),
+        }
+    }
+
+    /// Text line: the path, plus where it went if that's elsewhere.
+    fn describe(&self) -> String {
+        if self.in_place {
+            self.original_path.clone()
+        } else {
+            format!("{} -> {}", self.original_path, self.restored_path)
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+struct RestoreResult {
+    restored: Vec<RestoredItem>,
+    collisions: Vec<String>,
+    failed: Vec<String>,
+    mode_mismatches: Vec<ModeMismatch>,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct RestoreAllResult {
+    dry_run: bool,
+    /// Everything restored (or, in a dry run, that would be), renamed items included.
+    restored: Vec<RestoredItem>,
+    conflicts: Vec<String>,
+    failed: Vec<String>,
+    mode_mismatches: Vec<ModeMismatch>,
+}
+
+#[derive(Debug, Serialize)]
+struct RestoreMatchingResult {
+    dry_run: bool,
+    matched: Vec<String>,
+    /// Where each of `matched` goes under `dest_root`, in the same order.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    destinations: Vec<String>,
+    restored: Vec<RestoredItem>,
+    collisions: Vec<String>,
+    failed: Vec<String>,
+    /// Matches left in the trash because they can't be placed under
+    /// `dest_root` safely, with the reason.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    skipped: Vec<String>,
+    mode_mismatches: Vec<ModeMismatch>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum GlobRestoreStatus {
+    Restored,
+    /// Restored under a new name because the original path was occupied.
+    Renamed,
+    /// Dry run: the original path is free.
+    WouldRestore,
+    /// The original path is occupied; left in the trash.
+    Collision,
+    /// The original path is on a volume that isn't mounted; left in the trash.
+    VolumeUnavailable,
+    /// The destination under `dest_root` can't be built safely; left in the trash.
+    Skipped,
+    Failed,
+}
+
+#[derive(Debug, Serialize)]
+struct GlobRestoreItem {
+    name: String,
+    original_path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    restored_path: Option<String>,
+    /// `restored_path` without lossy conversion, for `redo_last_restore`.
+    #[serde(skip)]
+    restored: Option<PathBuf>,
+    status: GlobRestoreStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct RestoreGlobResult {
+    dry_run: bool,
+    /// Set when `on_conflict: abort` stopped the restore.
+    aborted: bool,
+    restored: usize,
+    items: Vec<GlobRestoreItem>,
+    mode_mismatches: Vec<ModeMismatch>,
+}
+
+/// Trash items that came from one original directory.
+#[derive(Debug, Serialize)]
+struct DirGroup {
+    dir: String,
+    count: usize,
+    /// Total size of the items whose size is known.
+    bytes: u64,
+    /// Items (usually directories) whose size the trash doesn't report.
+    unsized_items: usize,
+}
+
+/// Trash items sharing a file extension.
+#[derive(Debug, Serialize)]
+struct ExtensionGroup {
+    /// Lowercased, without the dot; `(none)` for names without one.
+    extension: String,
+    count: usize,
+    bytes: u64,
+    unsized_items: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct ExtensionSummary {
+    count: usize,
+    bytes: u64,
+    groups: Vec<ExtensionGroup>,
+}
+
+/// Trash items deleted between `min_days` and `max_days` ago.
+#[derive(Debug, Serialize)]
+struct AgeBucket {
+    label: String,
+    min_days: u64,
+    /// Exclusive; `None` for the open-ended oldest bucket.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_days: Option<u64>,
+    count: usize,
+    bytes: u64,
+    unsized_items: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct AgeHistogram {
+    count: usize,
+    bytes: u64,
+    buckets: Vec<AgeBucket>,
+}
+
+/// Free space on one trash volume before and after a purge.
+#[derive(Debug, Serialize)]
+struct VolumeProjection {
+    location: String,
+    count: usize,
+    purge_bytes: u64,
+    /// `None` when the filesystem couldn't be queried.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    free_bytes: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    projected_free_bytes: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+struct PurgePreview {
+    matched: Vec<TrashEntry>,
+    purge_bytes: u64,
+    volumes: Vec<VolumeProjection>,
+}
+
+#[derive(Debug, Serialize)]
+struct DirTrashEntry {
+    original_path: String,
+    /// Path relative to the requested directory; empty for the directory itself.
+    relative_path: String,
+    name_encoded: String,
+    /// `None` for directories and items the trash doesn't size.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    size_bytes: Option<u64>,
+    time_deleted: i64,
+}
+
+#[derive(Debug, Serialize)]
+struct DirTrashListing {
+    dir: String,
+    count: usize,
+    /// Total size of the items whose size is known.
+    bytes: u64,
+    items: Vec<DirTrashEntry>,
+}
+
+#[derive(Debug, Serialize)]
+struct TrashedDir {
+    #[serde(flatten)]
+    entry: TrashEntry,
+    /// Regular files anywhere below the directory.
+    files: usize,
+    bytes: u64,
+    /// Set when the directory couldn't be fully walked; totals are partial.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct TrashedDirListing {
+    count: usize,
+    bytes: u64,
+    dirs: Vec<TrashedDir>,
+}
+
+/// Sidecar written next to each exported item.
+#[derive(Debug, Serialize)]
+struct ExportSidecar<'a> {
+    #[serde(flatten)]
+    entry: &'a TrashEntry,
+    exported_at: i64,
+}
+
+#[derive(Debug, Serialize)]
+struct ExportedItem {
+    original_path: String,
+    exported_path: String,
+    sidecar_path: String,
+    bytes: u64,
+    purged: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct ExportResult {
+    destination: String,
+    exported: Vec<ExportedItem>,
+    failed: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct ConsistencyReport {
+    consistent: bool,
+    dirs: Vec<consistency::DirCheck>,
+}
+
+#[derive(Debug, Serialize)]
+struct RestoreGroupResult {
+    group: String,
+    #[serde(flatten)]
+    restore: RestoreGlobResult,
+    /// Members no longer in the trash (restored or purged some other way,
+    /// or still staged).
+    missing: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct PurgeGroupResult {
+    group: String,
+    /// Original paths of the deleted items.
+    deleted: Vec<String>,
+    /// Members left in the trash (and in the group), with the reason.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    failed: Vec<String>,
+    /// Members no longer in the trash.
+    missing: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct PermanentlyDeleteResult {
+    /// Original paths of the deleted items.
+    deleted: Vec<String>,
+    /// Whether their contents were overwritten first.
+    wiped: bool,
+    /// Files left as they were by the wipe, with the reason; their other
+    /// names still hold the data.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    not_wiped: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct EmptyTrashPreview {
+    count: usize,
+    bytes: u64,
+    groups: Vec<DirGroup>,
+}
+
+/// A restored item whose permission bits differ from the recorded ones.
+#[derive(Debug, Serialize)]
+struct ModeMismatch {
+    path: String,
+    /// Octal, e.g. `0755`.
+    expected: String,
+    found: String,
+    /// Whether the recorded mode was successfully put back.
+    reapplied: bool,
+}
+
+// === Helpers ===
+
+/// Item count and known total size of a group of trash items.
+#[derive(Debug, Default)]
+struct SizeTally {
+    count: usize,
+    bytes: u64,
+    /// Items (usually directories) whose size the trash doesn't report.
+    unsized_items: usize,
+}
+
+/// Outcome of restoring a set of trash items one at a time.
+#[derive(Debug, Default)]
+struct RestoreReport {
+    restored: Vec<RestoredItem>,
+    collisions: Vec<PathBuf>,
+    failed: Vec<(PathBuf, String)>,
+    /// Items left in the trash because their destination under the root
+    /// couldn't be built safely, with the reason.
+    skipped: Vec<(PathBuf, String)>,
+    mode_mismatches: Vec<ModeMismatch>,
+}
+
+impl From<RestoreReport> for RestoreResult {
+    fn from(report: RestoreReport) -> Self {
+        let lossy = |path: &PathBuf| path.to_string_lossy().into_owned();
+        Self {
+            restored: report.restored,
+            collisions: report.collisions.iter().map(lossy).collect(),
+            failed: report
+                .failed
+                .iter()
+                .map(|(path, e)| format!("{}: {}", path.display(), e))
+                .collect(),
+            // `skipped` is only filled when restoring under a root, which
+            // these results never do
+            mode_mismatches: report.mode_mismatches,
+        }
+    }
+}
+
+/// Text lines describing permission differences found after a restore.
+fn mode_report(mismatches: &[ModeMismatch]) -> String {
+    if mismatches.is_empty() {
+        return String::new();
+    }
+    let mut text = String::from("\nPermission differences after restore:");
+    for m in mismatches {
+        text.push_str(&format!(
+            "\n  {}: expected {}, found {} ({})",
+            m.path,
+            m.expected,
+            m.found,
+            if m.reapplied { "reapplied" } else { "could not reapply" }
+        ));
+    }
+    text
+}
+
+/// Whether one item could be restored to its original path right now.
+#[derive(Debug, Serialize)]
+struct RestoreCheck {
+    name: String,
+    original_path: String,
+    restorable: bool,
+    /// The item's data is still in the trash.
+    in_trash: bool,
+    /// Removable volume the original path is on, when it isn't mounted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    unavailable_volume: Option<String>,
+    /// Missing parent directories are created by the restore.
+    parent_exists: bool,
+    /// The parent, or its nearest existing ancestor, accepts new entries.
+    parent_writable: bool,
+    /// Something already occupies the original path.
+    collision: bool,
+    /// Why the restore would fail, if it would.
+    problems: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct CheckRestoreResult {
+    items: Vec<RestoreCheck>,
+}
+
+/// Whether new entries can be created in `dir` by this process.
+#[cfg(unix)]
+fn dir_writable(dir: &Path) -> bool {
+    use std::os::unix::ffi::OsStrExt;
+
+    let Ok(c_path) = std::ffi::CString::new(dir.as_os_str().as_bytes()) else {
+        return false;
+    };
+    // SAFETY: `c_path` is a valid NUL-terminated string.
+    unsafe { libc::access(c_path.as_ptr(), libc::W_OK | libc::X_OK) == 0 }
+}
+
+/// Whether new entries can be created in `dir`; only the read-only attribute
+/// is seen, not ACLs.
+#[cfg(not(unix))]
+fn dir_writable(dir: &Path) -> bool {
+    dir.metadata().is_ok_and(|meta| !meta.permissions().readonly())
+}
+
+/// How a restore or purge call picks items by name.
+enum NameQuery {
+    /// Case-insensitive substring (or exact, for purges) of the display name.
+    Text(String),
+    /// Exact raw bytes, from a `name_encoded` argument.
+    Raw(Vec<u8>),
+}
+
+impl NameQuery {
+    fn from_params(name: Option<&str>, name_encoded: Option<&str>) -> Result<Self, McpError> {
+        match (name_encoded, name) {
+            (Some(encoded), _) => paths::decode_name(encoded).map(Self::Raw).ok_or_else(|| {
+                McpError::invalid_params(format!("Malformed name_encoded '{}'", encoded), None)
+            }),
+            (None, Some(name)) => Ok(Self::Text(name.to_string())),
+            (None, None) => Err(McpError::invalid_params("Provide `name` or `name_encoded`", None)),
+        }
+    }
+
+    fn matches(&self, name: &std::ffi::OsStr, partial: bool) -> bool {
+        match self {
+            Self::Raw(raw) => name.as_encoded_bytes() == raw.as_slice(),
+            Self::Text(text) if partial => {
+                name.to_string_lossy().to_lowercase().contains(&text.to_lowercase())
+            }
+            Self::Text(text) => name.to_string_lossy() == *text,
+        }
+    }
+
+    fn display(&self) -> String {
+        match self {
+            Self::Text(text) => text.clone(),
+            Self::Raw(raw) => String::from_utf8_lossy(raw).into_owned(),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn reapply_mode(path: &Path, mode: u32) -> std::io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    // set_permissions follows symlinks; a link's own mode is meaningless
+    if std::fs::symlink_metadata(path)?.file_type().is_symlink() {
+        return Ok(());
+    }
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))
+}
+
+#[cfg(not(unix))]
+fn reapply_mode(_path: &Path, _mode: u32) -> std::io::Result<()> {
+    Ok(())
+}
+
+/// First free sibling of `path` named `stem (<label> N).ext`.
+fn free_sibling(path: &Path, label: &str) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let ext = path
+        .extension()
+        .map(|ext| format!(".{}", ext.to_string_lossy()))
+        .unwrap_or_default();
+    (1..)
+        .map(|n| path.with_file_name(format!("{} ({} {}){}", stem, label, n, ext)))
+        .find(|candidate| candidate.symlink_metadata().is_err())
+        .unwrap_or_else(|| path.to_path_buf())
+}
+
+/// Copy a file, symlink or directory tree to `to`, returning the bytes copied.
+///
+/// Symlinks are copied as links, not followed.
+fn copy_tree(from: &Path, to: &Path) -> std::io::Result<u64> {
+    let meta = from.symlink_metadata()?;
+    if meta.is_dir() {
+        std::fs::create_dir(to)?;
+        let mut bytes = 0;
+        for entry in std::fs::read_dir(from)? {
+            let entry = entry?;
+            bytes += copy_tree(&entry.path(), &to.join(entry.file_name()))?;
+        }
+        std::fs::set_permissions(to, meta.permissions())?;
+        Ok(bytes)
+    } else if meta.file_type().is_symlink() {
+        copy_symlink(&std::fs::read_link(from)?, to)?;
+        Ok(0)
+    } else {
+        std::fs::copy(from, to)
+    }
+}
+
+#[cfg(unix)]
+fn copy_symlink(target: &Path, link: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+#[cfg(windows)]
+fn copy_symlink(target: &Path, link: &Path) -> std::io::Result<()> {
+    std::os::windows::fs::symlink_file(target, link)
+}
+
+#[cfg(not(any(unix, windows)))]
+fn copy_symlink(_target: &Path, _link: &Path) -> std::io::Result<()> {
+    Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "symlinks are not supported on this platform"))
+}
+
+/// Normalize a directory argument for comparison against `original_parent`.
+fn normalize_dir(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| paths::resolve(path))
+}
+
+/// Text lines for each item of a per-item restore, then any mode mismatches.
+fn glob_restore_lines(result: &RestoreGlobResult) -> String {
+    let mut msg = String::new();
+    for item in &result.items {
+        let label = match item.status {
+            GlobRestoreStatus::Restored => "restored",
+            GlobRestoreStatus::Renamed => "renamed",
+            GlobRestoreStatus::WouldRestore => "would restore",
+            GlobRestoreStatus::Collision => "collision",
+            GlobRestoreStatus::VolumeUnavailable => "volume unavailable",
+            GlobRestoreStatus::Skipped => "skipped",
+            GlobRestoreStatus::Failed => "failed",
+        };
+        msg.push_str(&format!("\n  [{}] {}", label, item.original_path));
+        if let Some(restored) = item.restored_path.as_ref().filter(|p| **p != item.original_path) {
+            msg.push_str(&format!(" -> {}", restored));
+        }
+        if let Some(detail) = &item.detail {
+            msg.push_str(&format!(": {}", detail));
+        }
+    }
+    msg.push_str(&mode_report(&result.mode_mismatches));
+    msg
+}
+
+/// Why an item whose [`under_root`] destination can't be built is skipped.
+const OUTSIDE_ROOT: &str = "its recorded original path contains `..`, which could lead outside dest_root";
+
+/// `original` recreated below `root`: its root (and drive, as a plain
+/// directory like `C`) become the first levels under `root`.
+///
+/// `None` if `original` has a `..` component, which could climb out of
+/// `root`. Trashes record absolute, normalized paths, so only a crafted or
+/// corrupt record has one.
+fn under_root(root: &Path, original: &Path) -> Option<PathBuf> {
+    use std::path::{Component, Prefix};
+
+    let mut target = root.to_path_buf();
+    for component in original.components() {
+        match component {
+            Component::Prefix(prefix) => match prefix.kind() {
+                Prefix::Disk(letter) | Prefix::VerbatimDisk(letter) => target.push((letter as char).to_string()),
+                _ => target.push(prefix.as_os_str().to_string_lossy().replace(['\\', '?', ':'], "")),
+            },
+            Component::RootDir | Component::CurDir => {}
+            Component::ParentDir => return None,
+            Component::Normal(part) => target.push(part),
+        }
+    }
+    Some(target)
+}
+
+// === Server ===
+
+impl TrashServer {
+    /// Response for a tool the system trash can't back on this platform, naming
+    /// the tool and how to get it working.
+    fn unsupported(&self, tool: &str) -> CallToolResult {
+        let platform = if cfg!(target_os = "macos") {
+            "the macOS Trash, whose contents can't be read or restored programmatically"
+        } else {
+            "this platform's system trash (listing works on Linux and Windows)"
+        };
+        self.notice(
+            Outcome::Unsupported,
+            format!(
+                "{} is not supported with {}. Set RMCP_TRASH_DIR to use a server-managed trash directory, \
+                 where every tool works; files trashed before that stay in the system trash",
+                tool, platform
+            ),
+        )
+    }
+
+    /// Poll the listing until an item trashed from `original` at or after
+    /// `since` shows up, and describe the outcome.
+    pub(crate) async fn confirm_in_trash(&self, original: &Path, since: std::time::SystemTime) -> String {
+        const TIMEOUT: std::time::Duration = std::time::Duration::from_secs(3);
+        const INTERVAL: std::time::Duration = std::time::Duration::from_millis(100);
+
+        if !self.backend.supports_listing() {
+            return "Not confirmed: the trash can't be listed on this platform".into();
+        }
+
+        // Trash timestamps have one-second resolution
+        let since = since
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64 - 1)
+            .unwrap_or(0);
+        let original = paths::fold_case(original);
+        let started = Instant::now();
+        loop {
+            match self.backend.list() {
+                Ok(items) => {
+                    if items
+                        .iter()
+                        .any(|item| item.time_deleted >= since && paths::fold_case(&item.original_path()) == original)
+                    {
+                        return format!("Confirmed in trash after {} ms", started.elapsed().as_millis());
+                    }
+                }
+                Err(e) => return format!("Not confirmed: failed to list trash: {}", e),
+            }
+            if started.elapsed() >= TIMEOUT {
+                tracing::warn!("{} did not appear in the trash listing", original.display());
+                return format!(
+                    "Not confirmed: the item did not appear in the trash listing within {} s; \
+                     it may have been deleted permanently instead of trashed",
+                    TIMEOUT.as_secs()
+                );
+            }
+            tokio::time::sleep(INTERVAL).await;
+        }
+    }
+
+    /// Restore the item trashed from `original` at or after `since`, for
+    /// `replace_file` when the replacement couldn't be moved into place.
+    pub(crate) fn put_back_replaced(&self, original: &Path, since: std::time::SystemTime) -> Result<(), String> {
+        if let Some(staging) = &self.staging {
+            return Err(format!("it is in the staging area {}", staging.dir().display()));
+        }
+        if !self.backend.supports_listing() {
+            return Err("the trash can't be listed on this platform".into());
+        }
+        // Trash timestamps have one-second resolution
+        let since = since
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64 - 1)
+            .unwrap_or(0);
+        let folded = paths::fold_case(original);
+        let item = self
+            .backend
+            .list()
+            .map_err(|e| format!("failed to list trash: {}", e))?
+            .into_iter()
+            .filter(|item| item.time_deleted >= since && paths::fold_case(&item.original_path()) == folded)
+            .max_by_key(|item| item.time_deleted)
+            .ok_or("it was not found in the trash listing")?;
+        self.backend.restore_all(vec![item]).map_err(|e| e.to_string())
+    }
+
+    /// Write a script to `script` that restores the items just trashed from
+    /// `originals` (at or after `since`), returning how many it covers and the
+    /// originals that couldn't be found in the trash.
+    pub(crate) fn write_restore_script(
+        &self,
+        script: &Path,
+        originals: &[PathBuf],
+        since: std::time::SystemTime,
+    ) -> Result<(usize, Vec<PathBuf>), String> {
+        if self.staging.is_some() {
+            return Err("the items are staged and not in the trash yet".into());
+        }
+        if !self.backend.supports_listing() {
+            return Err("the trash can't be listed on this platform".into());
+        }
+        if script.symlink_metadata().is_ok() {
+            return Err(format!("{} already exists", script.display()));
+        }
+
+        // Trash timestamps have one-second resolution
+        let since = since
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64 - 1)
+            .unwrap_or(0);
+        let mut items = self.backend.list().map_err(|e| format!("failed to list trash: {}", e))?;
+        items.retain(|item| item.time_deleted >= since);
+        items.sort_by_key(|item| std::cmp::Reverse(item.time_deleted));
+
+        let mut steps = Vec::new();
+        let mut unmatched = Vec::new();
+        for original in originals {
+            let folded = paths::fold_case(original);
+            let found = items
+                .iter()
+                .find(|item| paths::fold_case(&item.original_path()) == folded)
+                .and_then(|item| Some((self.backend.data_path(item)?, self.backend.info_path(item))));
+            match found {
+                Some((data, info)) => steps.push(script::Step {
+                    data,
+                    info,
+                    original: original.clone(),
+                }),
+                None => unmatched.push(original.clone()),
+            }
+        }
+
+        std::fs::write(script, script::render(&steps)).map_err(|e| format!("failed to write {}: {}", script.display(), e))?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let _ = std::fs::set_permissions(script, std::fs::Permissions::from_mode(0o755));
+        }
+        tracing::info!("Wrote restore script for {} item(s) to {}", steps.len(), script.display());
+        Ok((steps.len(), unmatched))
+    }
+
+    /// Trash items whose original location lies inside `scope`.
+    fn list_in_scope(&self, scope: &Scope) -> Result<Vec<trash::TrashItem>, trash::Error> {
+        let mut items = self.backend.list()?;
+        if scope.base_dir.is_some() {
+            items.retain(|item| scope.contains(&item.original_path()));
+        }
+        Ok(items)
+    }
+
+    /// Tally items by `key`, largest groups first.
+    fn tally_by<K: Ord>(&self, items: &[trash::TrashItem], key: impl Fn(&trash::TrashItem) -> K) -> Vec<(K, SizeTally)> {
+        let mut groups: std::collections::BTreeMap<K, SizeTally> = std::collections::BTreeMap::new();
+        for item in items {
+            let group = groups.entry(key(item)).or_default();
+            group.count += 1;
+            match self.backend.metadata(item).ok().and_then(|meta| meta.size.size()) {
+                Some(bytes) => group.bytes += bytes,
+                None => group.unsized_items += 1,
+            }
+        }
+        let mut groups: Vec<(K, SizeTally)> = groups.into_iter().collect();
+        groups.sort_by(|(_, a), (_, b)| b.bytes.cmp(&a.bytes).then(b.count.cmp(&a.count)));
+        groups
+    }
+
+    /// Group items by original parent directory, largest groups first.
+    fn group_by_dir(&self, items: &[trash::TrashItem]) -> Vec<DirGroup> {
+        self.tally_by(items, |item| item.original_parent.clone())
+            .into_iter()
+            .map(|(dir, tally)| DirGroup {
+                dir: dir.to_string_lossy().into_owned(),
+                count: tally.count,
+                bytes: tally.bytes,
+                unsized_items: tally.unsized_items,
+            })
+            .collect()
+    }
+
+    /// Compare a restored path's permission bits with `expected` and put the
+    /// recorded mode back if they differ. Unix only; see [`Backend::recorded_mode`].
+    ///
+    /// [`Backend::recorded_mode`]: crate::backend::Backend::recorded_mode
+    fn verify_mode(&self, path: &Path, expected: Option<u32>) -> Option<ModeMismatch> {
+        let expected = expected?;
+        let found = crate::backend::file_mode(path)?;
+        if found == expected {
+            return None;
+        }
+        let reapplied = reapply_mode(path, expected).is_ok();
+        tracing::warn!(
+            "Restored {} with mode {:04o}, expected {:04o} (reapplied: {})",
+            path.display(),
+            found,
+            expected,
+            reapplied
+        );
+        Some(ModeMismatch {
+            path: path.to_string_lossy().into_owned(),
+            expected: format!("{:04o}", expected),
+            found: format!("{:04o}", found),
+            reapplied,
+        })
+    }
+
+    /// Restore items individually so one collision doesn't halt the rest.
+    ///
+    /// Items are restored newest first, so when the same path was trashed more
+    /// than once the most recent version wins and the older ones are reported as
+    /// collisions.
+    ///
+    /// With `root`, each item goes to its original path rebuilt under `root`
+    /// (see [`under_root`]) rather than back in place.
+    fn restore_each(&self, mut items: Vec<trash::TrashItem>, root: Option<&Path>) -> RestoreReport {
+        items.sort_by_key(|item| std::cmp::Reverse(item.time_deleted));
+
+        let mut report = RestoreReport::default();
+        for item in items {
+            let original = item.original_path();
+            let expected = self.backend.recorded_mode(&item);
+            let target = match root.map(|root| under_root(root, &original)) {
+                None => original.clone(),
+                Some(Some(target)) => target,
+                Some(None) => {
+                    report.skipped.push((original, OUTSIDE_ROOT.to_string()));
+                    continue;
+                }
+            };
+            let outcome = match root {
+                None => self.backend.restore_all(vec![item]),
+                Some(_) => match target.parent().map(std::fs::create_dir_all).transpose() {
+                    Ok(_) => self.backend.restore_to(&item, &target),
+                    Err(e) => {
+                        report.failed.push((original, format!("could not create its directory: {}", e)));
+                        continue;
+                    }
+                },
+            };
+            match outcome {
+                Ok(()) => {
+                    self.stats.record_restored(1);
+                    report.mode_mismatches.extend(self.verify_mode(&target, expected));
+                    report.restored.push(RestoredItem::new(&original, &target));
+                }
+                Err(e) => {
+                    self.stats.record_error(&e);
+                    match e {
+                        trash::Error::RestoreCollision { path, .. } => report.collisions.push(path),
+                        e => report.failed.push((original, e.to_string())),
+                    }
+                }
+            }
+        }
+        report
+    }
+
+    /// [`Self::restore_each`] with an outcome per item and an `on_conflict`
+    /// policy. In a dry run, or when `abort` finds a collision, each item's
+    /// outcome is only planned.
+    fn restore_planned(
+        &self,
+        mut matches: Vec<trash::TrashItem>,
+        on_conflict: OnConflict,
+        dry_run: bool,
+        root: Option<&Path>,
+    ) -> RestoreGlobResult {
+        // Newest first, so the most recent copy of a path trashed twice wins
+        matches.sort_by_key(|item| std::cmp::Reverse(item.time_deleted));
+        let entry = |item: &trash::TrashItem, status, detail| GlobRestoreItem {
+            name: item.name.to_string_lossy().into_owned(),
+            original_path: item.original_path().to_string_lossy().into_owned(),
+            restored_path: None,
+            restored: None,
+            status,
+            detail,
+        };
+        // An unplugged original volume only matters when restoring in place
+        let unavailable = |item: &trash::TrashItem| {
+            disk::unavailable_volume(&item.original_parent)
+                .filter(|_| root.is_none())
+                .map(|volume| format!("{} is not mounted", volume.display()))
+        };
+        // `None` when the item can't be placed under `root` safely
+        let destination = |item: &trash::TrashItem| match root {
+            Some(root) => under_root(root, &item.original_path()),
+            None => Some(item.original_path()),
+        };
+
+        let mut seen = std::collections::HashSet::new();
+        let planned: Vec<GlobRestoreItem> = matches
+            .iter()
+            .map(|item| {
+                let Some(target) = destination(item) else {
+                    return entry(item, GlobRestoreStatus::Skipped, Some(OUTSIDE_ROOT.to_string()));
+                };
+                let mut planned = if let Some(detail) = unavailable(item) {
+                    entry(item, GlobRestoreStatus::VolumeUnavailable, Some(detail))
+                } else if target.symlink_metadata().is_ok() || !seen.insert(paths::fold_case(&target)) {
+                    let detail = (on_conflict == OnConflict::Rename).then(|| "would be restored under a new name".to_string());
+                    entry(item, GlobRestoreStatus::Collision, detail)
+                } else {
+                    entry(item, GlobRestoreStatus::WouldRestore, None)
+                };
+                if root.is_some() {
+                    planned.restored_path = Some(target.to_string_lossy().into_owned());
+                }
+                planned
+            })
+            .collect();
+        let aborted = !dry_run
+            && on_conflict == OnConflict::Abort
+            && planned.iter().any(|item| item.status == GlobRestoreStatus::Collision);
+
+        let mut result = RestoreGlobResult {
+            dry_run,
+            aborted,
+            restored: 0,
+            items: Vec::new(),
+            mode_mismatches: Vec::new(),
+        };
+        if dry_run || aborted {
+            result.items = planned;
+        } else {
+            for item in matches {
+                let Some(wanted) = destination(&item) else {
+                    result.items.push(entry(&item, GlobRestoreStatus::Skipped, Some(OUTSIDE_ROOT.to_string())));
+                    continue;
+                };
+                if let Some(detail) = unavailable(&item) {
+                    result.items.push(entry(&item, GlobRestoreStatus::VolumeUnavailable, Some(detail)));
+                    continue;
+                }
+                let occupied = wanted.symlink_metadata().is_ok();
+                if occupied && on_conflict != OnConflict::Rename {
+                    result.items.push(entry(&item, GlobRestoreStatus::Collision, None));
+                    continue;
+                }
+                let target = if occupied { free_sibling(&wanted, "restored") } else { wanted.clone() };
+                let expected = self.backend.recorded_mode(&item);
+                let mut restored = entry(&item, GlobRestoreStatus::Restored, None);
+                if root.is_some() {
+                    if let Err(e) = target.parent().map(std::fs::create_dir_all).transpose() {
+                        restored.status = GlobRestoreStatus::Failed;
+                        restored.detail = Some(format!("could not create its directory: {}", e));
+                        result.items.push(restored);
+                        continue;
+                    }
+                }
+                let outcome = if occupied || root.is_some() {
+                    self.backend.restore_to(&item, &target)
+                } else {
+                    self.backend.restore_all(vec![item])
+                };
+                match outcome {
+                    Ok(()) => {
+                        self.stats.record_restored(1);
+                        result.restored += 1;
+                        result.mode_mismatches.extend(self.verify_mode(&target, expected));
+                        if occupied {
+                            restored.status = GlobRestoreStatus::Renamed;
+                        }
+                        restored.restored_path = Some(target.to_string_lossy().into_owned());
+                        restored.restored = Some(target);
+                    }
+                    Err(e) => {
+                        self.stats.record_error(&e);
+                        restored.status = match e {
+                            trash::Error::RestoreCollision { .. } => GlobRestoreStatus::Collision,
+                            _ => GlobRestoreStatus::Failed,
+                        };
+                        if restored.status == GlobRestoreStatus::Failed {
+                            restored.detail = Some(e.to_string());
+                        }
+                    }
+                }
+                result.items.push(restored);
+            }
+        }
+        result
+    }
+
+    /// The directory named by a `dest_root` argument, or the refusal when it
+    /// isn't an existing directory inside the profile's base directory.
+    fn dest_root(&self, scope: &Scope, dest: &str) -> Result<Result<PathBuf, CallToolResult>, McpError> {
+        self.check_path_len(dest)?;
+        let dir = scope.path(dest);
+        if !dir.is_dir() {
+            return Ok(Err(self.notice(
+                Outcome::NothingToDo,
+                format!("dest_root is not an existing directory: {}", dest)
+            )));
+        }
+        if !scope.contains(&paths::resolve(&dir)) {
+            return Ok(Err(self.notice(Outcome::Refused, format!(
+                "dest_root {} is outside the base directory of profile '{}'",
+                dir.display(),
+                scope.name.unwrap_or_default()
+            ))));
+        }
+        Ok(Ok(normalize_dir(&dir)))
+    }
+
+    /// The trash items for the members of undo group `group`, and the
+    /// members that aren't in the trash any more.
+    ///
+    /// A member matches the oldest item from its original path trashed no
+    /// earlier than it was recorded, so a path trashed again later keeps
+    /// its newer copy out of the group.
+    fn group_in_trash(&self, group: &str) -> Result<(Vec<trash::TrashItem>, Vec<groups::Member>), CallToolResult> {
+        let Some(members) = self.undo_groups.members(group) else {
+            let known = self.undo_groups.names();
+            return Err(self.notice(
+                Outcome::NothingToDo,
+                format!(
+                    "No undo group named '{}' (known: {})",
+                    group,
+                    if known.is_empty() { "none".into() } else { known.join(", ") }
+                ),
+            ));
+        };
+        let mut items = self
+            .backend
+            .list()
+            .map_err(|e| self.notice(Outcome::Failed, format!("Failed to list trash: {}", e)))?;
+        items.sort_by_key(|item| item.time_deleted);
+
+        let mut found = Vec::new();
+        let mut missing = Vec::new();
+        for member in members {
+            let folded = paths::fold_case(&member.original);
+            // Trash timestamps have one-second resolution
+            let position = items.iter().position(|item| {
+                item.time_deleted >= member.trashed_at - 1 && paths::fold_case(&item.original_path()) == folded
+            });
+            match position {
+                Some(index) => found.push(items.remove(index)),
+                None => missing.push(member),
+            }
+        }
+        Ok((found, missing))
+    }
+
+    /// Make `restored` the set `redo_last_restore` puts back in the trash,
+    /// unless the call restored nothing.
+    fn remember_restore(&self, restored: impl IntoIterator<Item = PathBuf>) {
+        let restored: Vec<PathBuf> = restored.into_iter().collect();
+        if !restored.is_empty() {
+            *self.last_restore.lock().unwrap() = restored;
+            self.save_state();
+        }
+    }
+}
+
+#[rmcp::tool_router(router = listing_router, vis = "pub(crate)")]
+impl TrashServer {
+    #[rmcp::tool(description = "List items currently in the system trash, most recently deleted first unless sort_by says otherwise, optionally one page at a time, with each item's detected MIME type (Linux/Windows only)")]
+    pub async fn list_trash(
+        &self,
+        Parameters(params): Parameters<ListTrashParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let scope = self.scope(params.profile.as_deref())?;
+
+        if !self.backend.supports_listing() {
+            return Ok(self.unsupported("list_trash"));
+        }
+
+        match self.list_in_scope(&scope) {
+            Ok(mut items) => {
+                let newest = |a: &trash::TrashItem, b: &trash::TrashItem| b.time_deleted.cmp(&a.time_deleted);
+                match params.sort_by.unwrap_or_default() {
+                    SortBy::Newest => items.sort_by(newest),
+                    SortBy::Oldest => items.sort_by_key(|item| item.time_deleted),
+                    SortBy::Name => items.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| newest(a, b))),
+                    SortBy::Path => items.sort_by(|a, b| {
+                        a.original_path().cmp(&b.original_path()).then_with(|| newest(a, b))
+                    }),
+                }
+                let total = items.len();
+                let offset = params.offset.unwrap_or(0).min(total);
+                let end = params.limit.map_or(total, |limit| offset.saturating_add(limit).min(total));
+                let items = &items[offset..end];
+                let sniff = params.sniff_content == Some(true);
+                let detect = |item: &trash::TrashItem| {
+                    mime::by_extension(Path::new(&item.name)).or_else(|| {
+                        let data = self.backend.data_path(item).filter(|_| sniff)?;
+                        mime::sniff(&data).ok()
+                    })
+                };
+                let mut listing = TrashListing {
+                    count: items.len(),
+                    items: items
+                        .iter()
+                        .map(|item| TrashEntry {
+                            mime_type: detect(item).map(str::to_owned),
+                            ..TrashEntry::from(item)
+                        })
+                        .collect(),
+                    total,
+                    next_offset: (end < total).then_some(end),
+                    tree: None,
+                };
+                if params.as_tree == Some(true) {
+                    listing.tree = Some(TreeNode::build(&listing.items));
+                }
+                if total == 0 {
+                    Ok(self.structured_result("Trash is empty", listing))
+                } else if items.is_empty() {
+                    Ok(self.structured_result(
+                        format!("No items at offset {} (trash has {} items)", params.offset.unwrap_or(0), total),
+                        listing,
+                    ))
+                } else {
+                    let list: Vec<String> = items
+                        .iter()
+                        .map(|item| match item.name.to_str() {
+                            Some(name) => name.to_string(),
+                            None => format!(
+                                "{} (name_encoded: {})",
+                                item.name.to_string_lossy(),
+                                paths::encode_name(&item.name)
+                            ),
+                        })
+                        .collect();
+                    let heading = if items.len() == total {
+                        format!("Trash contents ({} items):", total)
+                    } else {
+                        format!("Trash contents (items {}-{} of {}):", offset + 1, end, total)
+                    };
+                    let mut msg = match &listing.tree {
+                        Some(tree) => {
+                            let mut msg = heading;
+                            for root in tree {
+                                root.render(0, &mut msg);
+                            }
+                            msg
+                        }
+                        None => format!("{}\n{}", heading, list.join("\n")),
+                    };
+                    if let Some(next) = listing.next_offset {
+                        msg.push_str(&format!("\nMore items: call again with offset {}", next));
+                    }
+                    Ok(self.structured_result(msg, listing))
+                }
+            }
+            Err(e) => Ok(self.notice(
+                Outcome::Failed,
+                format!("Failed to list trash: {}", e)
+            )),
+        }
+    }
+
+    #[rmcp::tool(description = "List trashed items that can be restored right now, separately from those whose original volume (e.g. an unplugged USB drive) isn't mounted (Linux/Windows only)")]
+    pub async fn list_restorable(
+        &self,
+        Parameters(params): Parameters<ProfileParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let scope = self.scope(params.profile.as_deref())?;
+
+        if !self.backend.supports_listing() {
+            return Ok(self.unsupported("list_restorable"));
+        }
+
+        let items = match self.list_in_scope(&scope) {
+            Ok(items) => items,
+            Err(e) => {
+                return Ok(self.notice(
+                    Outcome::Failed,
+                    format!("Failed to list trash: {}", e)
+                ));
+            }
+        };
+        if items.is_empty() {
+            return Ok(self.notice(Outcome::NothingToDo, "Trash is empty"));
+        }
+
+        let mut listing = RestorableListing {
+            restorable: Vec::new(),
+            unavailable: Vec::new(),
+        };
+        for item in &items {
+            match disk::unavailable_volume(&item.original_parent) {
+                Some(volume) => listing.unavailable.push(StrandedEntry {
+                    entry: TrashEntry::from(item),
+                    volume: volume.to_string_lossy().into_owned(),
+                }),
+                None => listing.restorable.push(TrashEntry::from(item)),
+            }
+        }
+
+        let mut msg = format!(
+            "{} of {} item(s) can be restored now",
+            listing.restorable.len(),
+            items.len()
+        );
+        for entry in &listing.restorable {
+            msg.push_str(&format!("\n  [restorable] {}", entry.original_path));
+        }
+        for stranded in &listing.unavailable {
+            msg.push_str(&format!(
+                "\n  [unavailable] {}: {} is not mounted",
+                stranded.entry.original_path, stranded.volume
+            ));
+        }
+        Ok(self.structured_result(msg, listing))
+    }
+
+    #[rmcp::tool(description = "List trashed items that came from a directory or anywhere below it, with sizes and deletion times, as a flat list or a tree (Linux/Windows only)")]
+    pub async fn list_dir_in_trash(
+        &self,
+        Parameters(params): Parameters<ListDirInTrashParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let scope = self.scope(params.profile.as_deref())?;
+
+        if !self.backend.supports_listing() {
+            return Ok(self.unsupported("list_dir_in_trash"));
+        }
+
+        self.check_path_len(&params.dir)?;
+        let dir = normalize_dir(&scope.path(&params.dir));
+        let mut items: Vec<trash::TrashItem> = match self.list_in_scope(&scope) {
+            Ok(items) => items
+                .into_iter()
+                .filter(|item| paths::starts_with(&item.original_path(), &dir))
+                .collect(),
+            Err(e) => {
+                return Ok(self.notice(
+                    Outcome::Failed,
+                    format!("Failed to list trash: {}", e)
+                ));
+            }
+        };
+        if items.is_empty() {
+            return Ok(self.notice(
+                Outcome::NothingToDo,
+                format!("No items in trash from {} or below it", dir.display())
+            ));
+        }
+        items.sort_by_key(|item| item.original_path());
+
+        let entries: Vec<DirTrashEntry> = items
+            .iter()
+            .map(|item| {
+                let original = item.original_path();
+                let relative = original
+                    .components()
+                    .skip(dir.components().count())
+                    .collect::<PathBuf>();
+                DirTrashEntry {
+                    original_path: original.to_string_lossy().into_owned(),
+                    relative_path: relative.to_string_lossy().into_owned(),
+                    name_encoded: paths::encode_name(&item.name),
+                    size_bytes: self.backend.metadata(item).ok().and_then(|meta| meta.size.size()),
+                    time_deleted: item.time_deleted,
+                }
+            })
+            .collect();
+        let bytes = entries.iter().filter_map(|entry| entry.size_bytes).sum();
+
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs() as i64);
+        let describe = |entry: &DirTrashEntry| {
+            let size = entry.size_bytes.map_or_else(|| "size unknown".to_string(), format::format_bytes);
+            let age = format::format_duration((now - entry.time_deleted).max(0) as u64);
+            format!("({}, deleted {} ago)", size, age)
+        };
+        let mut msg = format!(
+            "{} item(s) in trash from {} ({}):",
+            entries.len(),
+            dir.display(),
+            format::format_bytes(bytes)
+        );
+        if params.as_tree == Some(true) {
+            // Print each directory level once, indenting entries beneath it
+            let mut printed: Vec<std::ffi::OsString> = Vec::new();
+            for entry in &entries {
+                let parts: Vec<_> = Path::new(&entry.relative_path)
+                    .iter()
+                    .map(|part| part.to_os_string())
+                    .collect();
+                let (leaf, parents) = match parts.split_last() {
+                    Some((leaf, parents)) => (leaf.to_string_lossy().into_owned(), parents),
+                    None => (".".to_string(), &[][..]),
+                };
+                let shared = printed.iter().zip(parents).take_while(|(a, b)| a == b).count();
+                printed.truncate(shared);
+                for part in &parents[shared..] {
+                    msg.push_str(&format!("\n{}{}/", "  ".repeat(printed.len() + 1), part.to_string_lossy()));
+                    printed.push(part.clone());
+                }
+                msg.push_str(&format!("\n{}{} {}", "  ".repeat(parents.len() + 1), leaf, describe(entry)));
+            }
+        } else {
+            for entry in &entries {
+                msg.push_str(&format!("\n  {} {}", entry.original_path, describe(entry)));
+            }
+        }
+
+        let listing = DirTrashListing {
+            dir: dir.to_string_lossy().into_owned(),
+            count: entries.len(),
+            bytes,
+            items: entries,
+        };
+        Ok(self.structured_result(msg, listing))
+    }
+
+    #[rmcp::tool(description = "List only the directories in the trash, newest first, with the number of files and total size inside each (Linux/Windows only)")]
+    pub async fn list_trashed_dirs(
+        &self,
+        Parameters(params): Parameters<ProfileParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let scope = self.scope(params.profile.as_deref())?;
+
+        if !self.backend.supports_listing() {
+            return Ok(self.unsupported("list_trashed_dirs"));
+        }
+
+        let mut items = match self.list_in_scope(&scope) {
+            Ok(items) => items,
+            Err(e) => {
+                return Ok(self.notice(
+                    Outcome::Failed,
+                    format!("Failed to list trash: {}", e)
+                ));
+            }
+        };
+        items.sort_by_key(|item| std::cmp::Reverse(item.time_deleted));
+
+        let found: Vec<(TrashEntry, PathBuf)> = items
+            .iter()
+            .filter_map(|item| Some((TrashEntry::from(item), self.backend.data_path(item)?)))
+            .filter(|(_, data)| data.symlink_metadata().is_ok_and(|meta| meta.is_dir()))
+            .collect();
+        let max_depth = self.config.max_walk_depth;
+        let mut progress = Progress::new(&context);
+        let dirs: Vec<TrashedDir> = crate::blocking(move || {
+            found
+                .into_iter()
+                .map(|(entry, data)| {
+                    let (files, bytes, error) = match tree_size(&data, max_depth, &mut progress) {
+                        Ok((files, bytes, limits)) => (files, bytes, limits.note()),
+                        Err(e) => (0, 0, Some(e.to_string())),
+                    };
+                    TrashedDir {
+                        entry,
+                        files,
+                        bytes,
+                        error,
+                    }
+                })
+                .collect()
+        })
+        .await;
+
+        let bytes = dirs.iter().map(|dir| dir.bytes).sum();
+        let mut msg = if dirs.is_empty() {
+            "No directories in trash".to_string()
+        } else {
+            format!("Directories in trash ({}, {}):", dirs.len(), format::format_bytes(bytes))
+        };
+        for dir in &dirs {
+            msg.push_str(&format!(
+                "\n  {} ({} file(s), {})",
+                dir.entry.original_path,
+                dir.files,
+                format::format_bytes(dir.bytes)
+            ));
+            if let Some(error) = &dir.error {
+                msg.push_str(&format!(" [incomplete: {}]", error));
+            }
+        }
+        Ok(self.structured_result(
+            msg,
+            TrashedDirListing {
+                count: dirs.len(),
+                bytes,
+                dirs,
+            },
+        ))
+    }
+
+    #[rmcp::tool(description = "Compare the trash against a previously saved list_trash result and report which items were added and which were removed since (Linux/Windows only)")]
+    pub async fn verify_inventory(
+        &self,
+        Parameters(params): Parameters<VerifyInventoryParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let scope = self.scope(params.profile.as_deref())?;
+
+        let inventory = match (params.inventory, params.inventory_path.as_deref()) {
+            (Some(value), _) => value,
+            (None, Some(path)) => {
+                self.check_path_len(path)?;
+                let path = scope.path(path);
+                let bytes = std::fs::read(&path).map_err(|e| {
+                    McpError::invalid_params(format!("Failed to read inventory {}: {}", path.display(), e), None)
+                })?;
+                serde_json::from_slice(&bytes).map_err(|e| {
+                    McpError::invalid_params(format!("Inventory {} is not JSON: {}", path.display(), e), None)
+                })?
+            }
+            (None, None) => {
+                return Err(McpError::invalid_params("Provide `inventory` or `inventory_path`", None));
+            }
+        };
+        let inventory: TrashListing = serde_json::from_value(inventory).map_err(|e| {
+            McpError::invalid_params(format!("Inventory is not a list_trash result: {}", e), None)
+        })?;
+
+        if !self.backend.supports_listing() {
+            return Ok(self.unsupported("verify_inventory"));
+        }
+
+        let current: Vec<TrashEntry> = match self.list_in_scope(&scope) {
+            Ok(items) => items.iter().map(TrashEntry::from).collect(),
+            Err(e) => {
+                return Ok(self.notice(
+                    Outcome::Failed,
+                    format!("Failed to list trash: {}", e)
+                ));
+            }
+        };
+
+        let before: std::collections::HashSet<_> = inventory.items.iter().map(TrashEntry::key).collect();
+        let now: std::collections::HashSet<_> = current.iter().map(TrashEntry::key).collect();
+        let added: Vec<TrashEntry> = current.iter().filter(|e| !before.contains(&e.key())).cloned().collect();
+        let removed: Vec<TrashEntry> = inventory
+            .items
+            .iter()
+            .filter(|e| !now.contains(&e.key()))
+            .cloned()
+            .collect();
+        let unchanged = current.len() - added.len();
+
+        let mut msg = if added.is_empty() && removed.is_empty() {
+            format!("Trash matches the inventory ({} item(s))", unchanged)
+        } else {
+            format!(
+                "Trash differs from the inventory: {} added, {} removed, {} unchanged",
+                added.len(),
+                removed.len(),
+                unchanged
+            )
+        };
+        for entry in &added {
+            msg.push_str(&format!("\n  + {}", entry.original_path));
+        }
+        for entry in &removed {
+            msg.push_str(&format!("\n  - {}", entry.original_path));
+        }
+        Ok(self.structured_result(msg, InventoryDiff { added, removed, unchanged }))
+    }
+
+    #[rmcp::tool(description = "Check whether trashed items could be restored to their original location without restoring them: still in the trash, original volume mounted, parent directory writable, nothing in the way (Linux/Windows only)")]
+    pub async fn check_restore(
+        &self,
+        Parameters(params): Parameters<CheckRestoreParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let scope = self.scope(params.profile.as_deref())?;
+        let query = NameQuery::from_params(params.name.as_deref(), params.name_encoded.as_deref())?;
+
+        if !self.backend.supports_listing() {
+            return Ok(self.unsupported("check_restore"));
+        }
+
+        let items = match self.list_in_scope(&scope) {
+            Ok(items) => items,
+            Err(e) => {
+                return Ok(self.notice(
+                    Outcome::Failed,
+                    format!("Failed to list trash: {}", e)
+                ));
+            }
+        };
+        let matches: Vec<_> = items
+            .into_iter()
+            .filter(|item| query.matches(&item.name, true))
+            .collect();
+        if matches.is_empty() {
+            return Ok(self.notice(
+                Outcome::NothingToDo,
+                format!("No items in trash matching '{}'", query.display())
+            ));
+        }
+
+        let checks: Vec<RestoreCheck> = matches
+            .iter()
+            .map(|item| {
+                let original = item.original_path();
+                let parent = &item.original_parent;
+                let in_trash = self.backend.data_path(item).is_none_or(|data| data.symlink_metadata().is_ok());
+                let volume = disk::unavailable_volume(parent);
+                let parent_exists = parent.is_dir();
+                let anchor = parent.ancestors().find(|dir| dir.is_dir());
+                let parent_writable = anchor.is_some_and(dir_writable);
+                let collision = original.symlink_metadata().is_ok();
+
+                let mut problems = Vec::new();
+                if !in_trash {
+                    problems.push("its data is missing from the trash".to_string());
+                }
+                if let Some(volume) = &volume {
+                    problems.push(format!("{} is not mounted", volume.display()));
+                } else if !parent_writable {
+                    problems.push(format!(
+                        "{} is not writable",
+                        anchor.unwrap_or(parent).display()
+                    ));
+                }
+                if collision {
+                    problems.push(format!("{} already exists", original.display()));
+                }
+                RestoreCheck {
+                    name: item.name.to_string_lossy().into_owned(),
+                    original_path: original.to_string_lossy().into_owned(),
+                    restorable: problems.is_empty(),
+                    in_trash,
+                    unavailable_volume: volume.map(|v| v.to_string_lossy().into_owned()),
+                    parent_exists,
+                    parent_writable,
+                    collision,
+                    problems,
+                }
+            })
+            .collect();
+
+        let ready = checks.iter().filter(|check| check.restorable).count();
+        let mut msg = format!("{} of {} matching item(s) can be restored in place", ready, checks.len());
+        for check in &checks {
+            if check.restorable {
+                msg.push_str(&format!("\n  [ok] {}", check.original_path));
+            } else {
+                msg.push_str(&format!("\n  [blocked] {}: {}", check.original_path, check.problems.join("; ")));
+            }
+        }
+        if checks.iter().any(|check| check.unavailable_volume.is_some()) {
+            msg.push_str("\nItems on an unmounted volume can be restored elsewhere with restore_from_trash's `destination`");
+        }
+        if checks.iter().any(|check| check.collision) {
+            msg.push_str("\nItems whose path is taken can be restored under a new name with restore_glob's `on_conflict: rename`");
+        }
+        Ok(self.structured_result(msg, CheckRestoreResult { items: checks }))
+    }
+
+    #[rmcp::tool(description = "Restore a file from trash to its original location (Linux/Windows only)")]
+    pub async fn restore_from_trash(
+        &self,
+        Parameters(params): Parameters<RestoreParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let scope = self.scope(params.profile.as_deref())?;
+
+        if let Some(limited) = self.rate_limited("restore_from_trash") {
+            return Ok(limited);
+        }
+        let query = NameQuery::from_params(params.name.as_deref(), params.name_encoded.as_deref())?;
+
+        if !self.backend.supports_listing() {
+            return Ok(self.unsupported("restore_from_trash"));
+        }
+
+        match self.list_in_scope(&scope) {
+            Ok(items) => {
+                let matches: Vec<_> = items
+                    .into_iter()
+                    .filter(|item| query.matches(&item.name, true))
+                    .collect();
+
+                if matches.is_empty() {
+                    return Ok(self.notice(
+                        Outcome::NothingToDo,
+                        format!("No items in trash matching '{}'", query.display())
+                    ));
+                }
+
+                // Items whose original volume is unplugged can't go back in place
+                let (stranded, matches): (Vec<_>, Vec<_>) = matches
+                    .into_iter()
+                    .partition(|item| disk::unavailable_volume(&item.original_parent).is_some());
+
+                let destination = match params.destination.as_deref() {
+                    Some(dest) if !stranded.is_empty() => {
+                        self.check_path_len(dest)?;
+                        let dir = scope.path(dest);
+                        if !dir.is_dir() {
+                            return Ok(self.notice(
+                                Outcome::NothingToDo,
+                                format!("Destination is not an existing directory: {}", dest)
+                            ));
+                        }
+                        if !scope.contains(&paths::resolve(&dir)) {
+                            return Ok(self.notice(Outcome::Refused, format!(
+                                "Destination {} is outside the base directory of profile '{}'",
+                                dir.display(),
+                                scope.name.unwrap_or_default()
+                            )));
+                        }
+                        Some(dir)
+                    }
+                    _ => None,
+                };
+
+                if destination.is_none() && !stranded.is_empty() {
+                    let mut msg = String::from("Cannot restore: the original volume is not available");
+                    for item in &stranded {
+                        let volume = disk::unavailable_volume(&item.original_parent).unwrap_or_default();
+                        msg.push_str(&format!(
+                            "\n  {} ({} is not mounted)",
+                            item.original_path().display(),
+                            volume.display()
+                        ));
+                    }
+                    msg.push_str("\nReconnect the drive and retry, or pass `destination` to restore into a directory on an available volume.");
+                    if !matches.is_empty() {
+                        msg.push_str(&format!("\nNothing was restored ({} other match(es) left in trash).", matches.len()));
+                    }
+                    return Ok(self.notice(Outcome::Failed, msg));
+                }
+
+                let mut msg = String::new();
+                let mut result = RestoreResult::default();
+                if !matches.is_empty() {
+                    let count = matches.len();
+                    let expected: Vec<_> = matches
+                        .iter()
+                        .map(|item| (item.original_path(), self.backend.recorded_mode(item)))
+                        .collect();
+
+                    match self.backend.restore_all(matches) {
+                        Ok(()) => {
+                            self.stats.record_restored(count);
+                            msg.push_str(&format!("Restored {} item(s):", count));
+                            for (path, mode) in expected {
+                                msg.push_str(&format!("\n  {}", path.display()));
+                                result.mode_mismatches.extend(self.verify_mode(&path, mode));
+                                result.restored.push(RestoredItem::new(&path, &path));
+                            }
+                            msg.push_str(&mode_report(&result.mode_mismatches));
+                        }
+                        Err(e) => {
+                            self.stats.record_error(&e);
+                            return Ok(self.notice(
+                                Outcome::Failed,
+                                format!("Failed to restore: {}", e)
+                            ));
+                        }
+                    }
+                }
+
+                if let Some(dir) = destination {
+                    if !msg.is_empty() {
+                        msg.push('\n');
+                    }
+                    msg.push_str(&format!(
+                        "Restored {} item(s) from an unavailable volume to {}:",
+                        stranded.len(),
+                        dir.display()
+                    ));
+                    for item in &stranded {
+                        let path = dir.join(&item.name);
+                        let outcome = self.backend.restore_to(item, &path);
+                        match &outcome {
+                            Ok(()) => self.stats.record_restored(1),
+                            Err(e) => self.stats.record_error(e),
+                        }
+                        match outcome {
+                            Ok(()) => {
+                                tracing::info!("Restored {} to {}", item.original_path().display(), path.display());
+                                let restored = RestoredItem::new(&item.original_path(), &path);
+                                msg.push_str(&format!("\n  {}", restored.describe()));
+                                result.restored.push(restored);
+                            }
+                            Err(trash::Error::RestoreCollision { path, .. }) => {
+                                msg.push_str(&format!(
+                                    "\n  {}: {} already exists, left in trash",
+                                    item.original_path().display(),
+                                    path.display()
+                                ));
+                                result.collisions.push(path.to_string_lossy().into_owned());
+                            }
+                            Err(e) => {
+                                msg.push_str(&format!("\n  {}: failed: {}", item.original_path().display(), e));
+                                result.failed.push(format!("{}: {}", item.original_path().display(), e));
+                            }
+                        }
+                    }
+                }
+                self.remember_restore(result.restored.iter().map(|r| r.path.clone()));
+                Ok(self.structured_result(msg, result))
+            }
+            Err(e) => Ok(self.notice(
+                Outcome::Failed,
+                format!("Failed to list trash: {}", e)
+            )),
+        }
+    }
+
+    #[rmcp::tool(description = "Restore every trashed item that was originally in the given directory (Linux/Windows only)")]
+    pub async fn restore_from_dir(
+        &self,
+        Parameters(params): Parameters<RestoreFromDirParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let scope = self.scope(params.profile.as_deref())?;
+
+        if let Some(limited) = self.rate_limited("restore_from_dir") {
+            return Ok(limited);
+        }
+
+        if !self.backend.supports_listing() {
+            return Ok(self.unsupported("restore_from_dir"));
+        }
+
+        match self.list_in_scope(&scope) {
+            Ok(items) => {
+                self.check_path_len(&params.dir)?;
+                let dir = normalize_dir(&scope.path(&params.dir));
+                let folded = paths::fold_case(&dir);
+                let matches: Vec<_> = items
+                    .into_iter()
+                    .filter(|item| paths::fold_case(&item.original_parent) == folded)
+                    .collect();
+
+                if matches.is_empty() {
+                    return Ok(self.notice(
+                        Outcome::NothingToDo,
+                        format!("No items in trash from '{}'", dir.display())
+                    ));
+                }
+
+                let report = self.restore_each(matches, None);
+                let mut msg = format!(
+                    "Restored {} item(s) to {}",
+                    report.restored.len(),
+                    dir.display()
+                );
+                for restored in &report.restored {
+                    msg.push_str(&format!("\n  {}", restored.describe()));
+                }
+                if !report.collisions.is_empty() {
+                    msg.push_str(&format!(
+                        "\nCollisions (path already exists, left in trash): {}",
+                        report.collisions.len()
+                    ));
+                    for path in &report.collisions {
+                        msg.push_str(&format!("\n  {}", path.display()));
+                    }
+                }
+                if !report.failed.is_empty() {
+                    msg.push_str(&format!("\nFailed: {}", report.failed.len()));
+                    for (path, e) in &report.failed {
+                        msg.push_str(&format!("\n  {}: {}", path.display(), e));
+                    }
+                }
+                msg.push_str(&mode_report(&report.mode_mismatches));
+                self.remember_restore(report.restored.iter().map(|r| r.path.clone()));
+                Ok(self.structured_result(msg, RestoreResult::from(report)))
+            }
+            Err(e) => Ok(self.notice(
+                Outcome::Failed,
+                format!("Failed to list trash: {}", e)
+            )),
+        }
+    }
+
+    #[rmcp::tool(description = "Restore only the most recently trashed item that came from a directory or anywhere below it, e.g. to undo the last delete in ~/Downloads when other deletions happened since (Linux/Windows only)")]
+    pub async fn restore_last_from_dir(
+        &self,
+        Parameters(params): Parameters<RestoreLastFromDirParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let scope = self.scope(params.profile.as_deref())?;
+
+        if let Some(limited) = self.rate_limited("restore_last_from_dir") {
+            return Ok(limited);
+        }
+
+        if !self.backend.supports_listing() {
+            return Ok(self.unsupported("restore_last_from_dir"));
+        }
+
+        self.check_path_len(&params.dir)?;
+        let dir = normalize_dir(&scope.path(&params.dir));
+        let mut items: Vec<trash::TrashItem> = match self.list_in_scope(&scope) {
+            Ok(items) => items
+                .into_iter()
+                .filter(|item| paths::starts_with(&item.original_path(), &dir))
+                .collect(),
+            Err(e) => {
+                return Ok(self.notice(
+                    Outcome::Failed,
+                    format!("Failed to list trash: {}", e)
+                ));
+            }
+        };
+        items.sort_by_key(|item| std::cmp::Reverse(item.time_deleted));
+        let Some(latest) = items.first().map(|item| item.time_deleted) else {
+            return Ok(self.notice(
+                Outcome::NothingToDo,
+                format!("No items in trash from {} or below it", dir.display())
+            ));
+        };
+        // Deletion times are in whole seconds, so a batch can tie
+        let tied = items.iter().skip(1).take_while(|item| item.time_deleted == latest).count();
+        items.truncate(1);
+
+        let report = self.restore_each(items, None);
+        let mut msg = match (report.restored.first(), report.collisions.first(), report.failed.first()) {
+            (Some(restored), _, _) => format!("Restored {}", restored.describe()),
+            (None, Some(path), _) => format!("Not restored: {} already exists; the item is still in the trash", path.display()),
+            (None, None, Some((path, e))) => format!("Failed to restore {}: {}", path.display(), e),
+            (None, None, None) => "Nothing was restored".to_string(),
+        };
+        if tied > 0 {
+            msg.push_str(&format!(
+                "\n{} other item(s) from {} were trashed in the same second and are still in the trash",
+                tied,
+                dir.display()
+            ));
+        }
+        msg.push_str(&mode_report(&report.mode_mismatches));
+        self.remember_restore(report.restored.iter().map(|r| r.path.clone()));
+        Ok(self.structured_result(msg, RestoreResult::from(report)))
+    }
+
+    #[rmcp::tool(description = "Restore every trashed item whose name or original path matches a pattern (Linux/Windows only). Requires confirm: true; use dry_run to preview the set")]
+    pub async fn restore_matching(
+        &self,
+        Parameters(params): Parameters<RestoreMatchingParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let scope = self.scope(params.profile.as_deref())?;
+
+        if params.dry_run != Some(true) {
+            if let Some(limited) = self.rate_limited("restore_matching") {
+                return Ok(limited);
+            }
+        }
+
+        if !self.backend.supports_listing() {
+            return Ok(self.unsupported("restore_matching"));
+        }
+
+        let dry_run = params.dry_run == Some(true);
+        if !dry_run && params.confirm != Some(true) {
+            return Ok(self.notice(
+                Outcome::Refused,
+                "restore_matching can restore many files at once; pass confirm: true to proceed, or dry_run: true to preview"
+            ));
+        }
+
+        let root = match params.dest_root.as_deref().map(|dest| self.dest_root(&scope, dest)).transpose()? {
+            Some(Ok(root)) => Some(root),
+            Some(Err(refusal)) => return Ok(refusal),
+            None => None,
+        };
+
+        let items = match self.list_in_scope(&scope) {
+            Ok(items) => items,
+            Err(e) => {
+                return Ok(self.notice(
+                    Outcome::Failed,
+                    format!("Failed to list trash: {}", e)
+                ));
+            }
+        };
+        let pattern = pattern::Pattern::new(&params.pattern, params.match_kind.unwrap_or_default());
+        let matches: Vec<_> = items
+            .into_iter()
+            .filter(|item| {
+                pattern.matches(&item.name.to_string_lossy())
+                    || pattern.matches(&item.original_path().to_string_lossy())
+            })
+            .collect();
+
+        if matches.is_empty() {
+            return Ok(self.notice(
+                Outcome::NothingToDo,
+                format!("No items in trash matching '{}'", params.pattern)
+            ));
+        }
+
+        let mut skipped = Vec::new();
+        let mut destinations = Vec::new();
+        let matches: Vec<_> = match &root {
+            Some(root) => matches
+                .into_iter()
+                .filter(|item| match under_root(root, &item.original_path()) {
+                    Some(destination) => {
+                        destinations.push(destination.to_string_lossy().into_owned());
+                        true
+                    }
+                    None => {
+                        skipped.push(format!("{}: {}", item.original_path().display(), OUTSIDE_ROOT));
+                        false
+                    }
+                })
+                .collect(),
+            None => matches,
+        };
+        let matched: Vec<String> = matches
+            .iter()
+            .map(|item| item.original_path().to_string_lossy().into_owned())
+            .collect();
+        if dry_run {
+            let mut msg = format!("Dry run: {} item(s) would be restored:", matched.len());
+            for (i, path) in matched.iter().enumerate() {
+                msg.push_str(&format!("\n  {}", path));
+                if let Some(destination) = destinations.get(i) {
+                    msg.push_str(&format!(" -> {}", destination));
+                }
+            }
+            if !skipped.is_empty() {
+                msg.push_str(&format!("\nSkipped:\n  {}", skipped.join("\n  ")));
+            }
+            let result = RestoreMatchingResult {
+                dry_run,
+                matched,
+                destinations,
+                restored: Vec::new(),
+                collisions: Vec::new(),
+                failed: Vec::new(),
+                skipped,
+                mode_mismatches: Vec::new(),
+            };
+            return Ok(self.structured_result(msg, result));
+        }
+
+        let report = self.restore_each(matches, root.as_deref());
+        tracing::info!("Restored {} item(s) matching {}", report.restored.len(), params.pattern);
+        let lossy = |paths: &[PathBuf]| -> Vec<String> {
+            paths.iter().map(|p| p.to_string_lossy().into_owned()).collect()
+        };
+        skipped.extend(report.skipped.iter().map(|(path, reason)| format!("{}: {}", path.display(), reason)));
+        let result = RestoreMatchingResult {
+            dry_run,
+            matched,
+            destinations,
+            restored: report.restored,
+            collisions: lossy(&report.collisions),
+            failed: report
+                .failed
+                .iter()
+                .map(|(path, e)| format!("{}: {}", path.display(), e))
+                .collect(),
+            skipped,
+            mode_mismatches: report.mode_mismatches,
+        };
+        let mut msg = format!("Restored {} item(s)", result.restored.len());
+        for restored in &result.restored {
+            msg.push_str(&format!("\n  {}", restored.describe()));
+        }
+        if !result.collisions.is_empty() {
+            msg.push_str(&format!(
+                "\nCollisions (path already exists, left in trash):\n  {}",
+                result.collisions.join("\n  ")
+            ));
+        }
+        if !result.failed.is_empty() {
+            msg.push_str(&format!("\nFailed:\n  {}", result.failed.join("\n  ")));
+        }
+        if !result.skipped.is_empty() {
+            msg.push_str(&format!("\nSkipped:\n  {}", result.skipped.join("\n  ")));
+        }
+        msg.push_str(&mode_report(&result.mode_mismatches));
+        self.remember_restore(result.restored.iter().map(|r| r.path.clone()));
+        Ok(self.structured_result(msg, result))
+    }
+
+    #[rmcp::tool(description = "Move the items put back by the most recent restore to the trash again, reporting a result per item. Each restore can be redone once; the last restore is remembered across server restarts only when RMCP_TRASH_STATE_FILE is set")]
+    pub async fn redo_last_restore(
+        &self,
+        Parameters(params): Parameters<ProfileParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let scope = self.scope(params.profile.as_deref())?;
+
+        if let Some(limited) = self.rate_limited("redo_last_restore") {
+            return Ok(limited);
+        }
+
+        let paths = std::mem::take(&mut *self.last_restore.lock().unwrap());
+        if paths.is_empty() {
+            let since = if self.state_file.is_some() { "" } else { " since the server started" };
+            return Ok(self.notice(
+                Outcome::NothingToDo,
+                format!("No restore to redo: nothing has been restored{} since the last redo", since),
+            ));
+        }
+        self.save_state();
+
+        let mut result = RedoRestoreResult {
+            items: Vec::new(),
+            trashed: 0,
+        };
+        let mut bytes = 0;
+        for path in paths {
+            let (status, detail) = if path.symlink_metadata().is_err() {
+                (EntryStatus::Missing, None)
+            } else if let Some(reason) = self.refusal(&scope, &path, None) {
+                self.stats.record_refused(1);
+                (EntryStatus::Refused, Some(reason))
+            } else {
+                let size = path.symlink_metadata().ok().filter(|meta| meta.is_file()).map_or(0, |meta| meta.len());
+                match self.send_to_trash(&path) {
+                    Ok(()) => {
+                        result.trashed += 1;
+                        bytes += size;
+                        (EntryStatus::Trashed, None)
+                    }
+                    Err(e) => {
+                        tracing::warn!("Failed to trash {}: {}", path.display(), e);
+                        self.stats.record_error(&e);
+                        (EntryStatus::Failed, Some(crate::errors::trash_failure_message(Some(&path), &e)))
+                    }
+                }
+            };
+            result.items.push(EntryResult {
+                path: path.to_string_lossy().into_owned(),
+                status,
+                detail,
+            });
+        }
+        if result.trashed > 0 {
+            tracing::info!("Re-trashed {} restored item(s)", result.trashed);
+            self.stats.record_batch();
+            self.stats.record_trashed(result.trashed, bytes);
+        }
+
+        let mut msg = format!(
+            "Moved {} of {} restored item(s) back to trash",
+            result.trashed,
+            result.items.len()
+        );
+        for item in &result.items {
+            msg.push_str(&format!("\n  [{}] {}", item.status.label(), item.path));
+            if let Some(detail) = &item.detail {
+                msg.push_str(&format!(": {}", detail));
+            }
+        }
+        Ok(self.structured_result(msg, result))
+    }
+
+    #[rmcp::tool(description = "Restore every trashed item whose name matches a shell glob (e.g. `*.pdf`) to its original location, reporting an outcome per item (Linux/Windows only). Requires confirm: true unless dry_run")]
+    pub async fn restore_glob(
+        &self,
+        Parameters(params): Parameters<RestoreGlobParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let scope = self.scope(params.profile.as_deref())?;
+
+        if params.dry_run != Some(true) {
+            if let Some(limited) = self.rate_limited("restore_glob") {
+                return Ok(limited);
+            }
+        }
+
+        if !self.backend.supports_listing() {
+            return Ok(self.unsupported("restore_glob"));
+        }
+
+        let dry_run = params.dry_run == Some(true);
+        if !dry_run && params.confirm != Some(true) {
+            return Ok(self.notice(
+                Outcome::Refused,
+                "restore_glob can restore many files at once; pass confirm: true to proceed, or dry_run: true to preview"
+            ));
+        }
+
+        let root = match params.dest_root.as_deref().map(|dest| self.dest_root(&scope, dest)).transpose()? {
+            Some(Ok(root)) => Some(root),
+            Some(Err(refusal)) => return Ok(refusal),
+            None => None,
+        };
+
+        let items = match self.list_in_scope(&scope) {
+            Ok(items) => items,
+            Err(e) => {
+                return Ok(self.notice(
+                    Outcome::Failed,
+                    format!("Failed to list trash: {}", e)
+                ));
+            }
+        };
+        let glob = pattern::Pattern::new(&params.glob, pattern::MatchKind::Glob);
+        let matches: Vec<_> = items
+            .into_iter()
+            .filter(|item| glob.matches(&item.name.to_string_lossy()))
+            .collect();
+        if matches.is_empty() {
+            return Ok(self.notice(
+                Outcome::NothingToDo,
+                format!("No items in trash named like '{}'", params.glob)
+            ));
+        }
+
+        let result = self.restore_planned(matches, params.on_conflict.unwrap_or_default(), dry_run, root.as_deref());
+        if !dry_run && !result.aborted {
+            tracing::info!("Restored {} item(s) named like {}", result.restored, params.glob);
+        }
+
+        let mut msg = if dry_run {
+            let free = result.items.iter().filter(|i| i.status == GlobRestoreStatus::WouldRestore).count();
+            format!(
+                "Dry run: {} of {} item(s) named like '{}' would restore {}",
+                free,
+                result.items.len(),
+                params.glob,
+                root.as_ref().map_or_else(|| "in place".to_string(), |root| format!("under {}", root.display()))
+            )
+        } else if result.aborted {
+            format!(
+                "Aborted: some of the {} item(s) named like '{}' would collide with existing files; nothing was restored",
+                result.items.len(),
+                params.glob
+            )
+        } else {
+            format!(
+                "Restored {} of {} item(s) named like '{}'",
+                result.restored,
+                result.items.len(),
+                params.glob
+            )
+        };
+        msg.push_str(&glob_restore_lines(&result));
+        self.remember_restore(result.items.iter().filter_map(|item| item.restored.clone()));
+        Ok(self.structured_result(msg, result))
+    }
+
+    #[rmcp::tool(description = "Restore every item trashed in the last N minutes or hours to its original location, e.g. to undo everything deleted in the last 10 minutes by any tool or session, reporting an outcome per item (Linux/Windows only)")]
+    pub async fn restore_recent(
+        &self,
+        Parameters(params): Parameters<RestoreRecentParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let scope = self.scope(params.profile.as_deref())?;
+
+        let dry_run = params.dry_run == Some(true);
+        if !dry_run {
+            if let Some(limited) = self.rate_limited("restore_recent") {
+                return Ok(limited);
+            }
+        }
+
+        let window = match (params.minutes, params.hours) {
+            (Some(minutes), None) if minutes > 0 => minutes.saturating_mul(60),
+            (None, Some(hours)) if hours > 0 => hours.saturating_mul(3_600),
+            (Some(_), Some(_)) => {
+                return Err(McpError::invalid_params("Give either `minutes` or `hours`, not both", None));
+            }
+            _ => return Err(McpError::invalid_params("Give a positive `minutes` or `hours`", None)),
+        };
+
+        if !self.backend.supports_listing() {
+            return Ok(self.unsupported("restore_recent"));
+        }
+
+        let items = match self.list_in_scope(&scope) {
+            Ok(items) => items,
+            Err(e) => {
+                return Ok(self.notice(
+                    Outcome::Failed,
+                    format!("Failed to list trash: {}", e)
+                ));
+            }
+        };
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs() as i64);
+        let since = now.saturating_sub(window.min(i64::MAX as u64) as i64);
+        let matches: Vec<_> = items.into_iter().filter(|item| item.time_deleted >= since).collect();
+        let span = format::format_duration(window);
+        if matches.is_empty() {
+            return Ok(self.notice(
+                Outcome::NothingToDo,
+                format!("Nothing was trashed in the last {}", span)
+            ));
+        }
+
+        let result = self.restore_planned(matches, params.on_conflict.unwrap_or_default(), dry_run, None);
+        if !dry_run && !result.aborted {
+            tracing::info!("Restored {} item(s) trashed in the last {}", result.restored, span);
+        }
+
+        let mut msg = if dry_run {
+            let free = result.items.iter().filter(|i| i.status == GlobRestoreStatus::WouldRestore).count();
+            format!(
+                "Dry run: {} of {} item(s) trashed in the last {} would restore in place",
+                free,
+                result.items.len(),
+                span
+            )
+        } else if result.aborted {
+            format!(
+                "Aborted: some of the {} item(s) trashed in the last {} would collide with existing files; nothing was restored",
+                result.items.len(),
+                span
+            )
+        } else {
+            format!(
+                "Restored {} of {} item(s) trashed in the last {}",
+                result.restored,
+                result.items.len(),
+                span
+            )
+        };
+        msg.push_str(&glob_restore_lines(&result));
+        self.remember_restore(result.items.iter().filter_map(|item| item.restored.clone()));
+        Ok(self.structured_result(msg, result))
+    }
+
+    #[rmcp::tool(description = "Restore every item trashed under an undo group (the `group` argument of trash_file, trash_files and trash_files_from_file) to its original location, reporting an outcome per item. Restored items leave the group (Linux/Windows only)")]
+    pub async fn restore_group(
+        &self,
+        Parameters(params): Parameters<RestoreGroupParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let dry_run = params.dry_run == Some(true);
+        if !dry_run {
+            if let Some(limited) = self.rate_limited("restore_group") {
+                return Ok(limited);
+            }
+        }
+        if !self.backend.supports_listing() {
+            return Ok(self.unsupported("restore_group"));
+        }
+
+        let (matches, missing) = match self.group_in_trash(&params.group) {
+            Ok(found) => found,
+            Err(result) => return Ok(result),
+        };
+        let missing: Vec<String> = missing.iter().map(|m| m.original.to_string_lossy().into_owned()).collect();
+        if matches.is_empty() {
+            return Ok(self.notice(
+                Outcome::NothingToDo,
+                format!(
+                    "None of the {} item(s) in undo group '{}' are in the trash any more",
+                    missing.len(),
+                    params.group
+                ),
+            ));
+        }
+
+        let result = self.restore_planned(matches, params.on_conflict.unwrap_or_default(), dry_run, None);
+        if !dry_run && !result.aborted {
+            tracing::info!("Restored {} item(s) of undo group {}", result.restored, params.group);
+            let restored: Vec<PathBuf> = result
+                .items
+                .iter()
+                .filter(|item| matches!(item.status, GlobRestoreStatus::Restored | GlobRestoreStatus::Renamed))
+                .map(|item| paths::fold_case(Path::new(&item.original_path)))
+                .collect();
+            self.undo_groups.remove(&params.group, |member| restored.contains(&paths::fold_case(&member.original)));
+            self.save_state();
+        }
+
+        let mut msg = if dry_run {
+            let free = result.items.iter().filter(|i| i.status == GlobRestoreStatus::WouldRestore).count();
+            format!(
+                "Dry run: {} of {} item(s) in undo group '{}' would restore in place",
+                free,
+                result.items.len(),
+                params.group
+            )
+        } else if result.aborted {
+            format!(
+                "Aborted: some of the {} item(s) in undo group '{}' would collide with existing files; nothing was restored",
+                result.items.len(),
+                params.group
+            )
+        } else {
+            format!(
+                "Restored {} of {} item(s) in undo group '{}'",
+                result.restored,
+                result.items.len(),
+                params.group
+            )
+        };
+        msg.push_str(&glob_restore_lines(&result));
+        for path in &missing {
+            msg.push_str(&format!("\n  [not in trash] {}", path));
+        }
+        self.remember_restore(result.items.iter().filter_map(|item| item.restored.clone()));
+        Ok(self.structured_result(
+            msg,
+            RestoreGroupResult {
+                group: params.group,
+                restore: result,
+                missing,
+            },
+        ))
+    }
+
+    #[rmcp::tool(description = "Copy trashed items matching a pattern into a directory for review, without restoring them. Each copy gets a `<name>.trashinfo.json` sidecar with its original path and deletion time. The trash entries stay unless purge_after is set (Linux/Windows only)")]
+    pub async fn export_from_trash(
+        &self,
+        Parameters(params): Parameters<ExportFromTrashParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let scope = self.scope(params.profile.as_deref())?;
+
+        let purge_after = params.purge_after == Some(true);
+        if purge_after {
+            if let Some(limited) = self.rate_limited("export_from_trash") {
+                return Ok(limited);
+            }
+        }
+
+        if !self.backend.supports_listing() {
+            return Ok(self.unsupported("export_from_trash"));
+        }
+
+        self.check_path_len(&params.destination)?;
+        let destination = scope.path(&params.destination);
+        if !destination.is_dir() {
+            return Ok(self.notice(
+                Outcome::NothingToDo,
+                format!("Destination is not an existing directory: {}", params.destination)
+            ));
+        }
+        if !scope.contains(&paths::resolve(&destination)) {
+            return Ok(self.notice(Outcome::Refused, format!(
+                "Destination {} is outside the base directory of profile '{}'",
+                destination.display(),
+                scope.name.unwrap_or_default()
+            )));
+        }
+
+        let items = match self.list_in_scope(&scope) {
+            Ok(items) => items,
+            Err(e) => {
+                return Ok(self.notice(
+                    Outcome::Failed,
+                    format!("Failed to list trash: {}", e)
+                ));
+            }
+        };
+        let pattern = pattern::Pattern::new(&params.pattern, params.match_kind.unwrap_or_default());
+        let matches: Vec<_> = items
+            .into_iter()
+            .filter(|item| {
+                pattern.matches(&item.name.to_string_lossy())
+                    || pattern.matches(&item.original_path().to_string_lossy())
+            })
+            .collect();
+        if matches.is_empty() {
+            return Ok(self.notice(
+                Outcome::NothingToDo,
+                format!("No items in trash matching '{}'", params.pattern)
+            ));
+        }
+
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs() as i64);
+        let mut result = ExportResult {
+            destination: destination.to_string_lossy().into_owned(),
+            exported: Vec::new(),
+            failed: Vec::new(),
+        };
+        for item in matches {
+            let original = item.original_path();
+            let Some(data) = self.backend.data_path(&item) else {
+                result.failed.push(format!(
+                    "{}: the trash location of its data is unknown on this platform",
+                    original.display()
+                ));
+                continue;
+            };
+            let mut target = destination.join(&item.name);
+            if target.symlink_metadata().is_ok() {
+                target = free_sibling(&target, "export");
+            }
+            let mut sidecar_name = target.file_name().unwrap_or_default().to_os_string();
+            sidecar_name.push(".trashinfo.json");
+            let sidecar_path = target.with_file_name(sidecar_name);
+
+            let entry = TrashEntry::from(&item);
+            let copied = copy_tree(&data, &target).and_then(|bytes| {
+                let sidecar = ExportSidecar { entry: &entry, exported_at: now };
+                let json = serde_json::to_vec_pretty(&sidecar).map_err(std::io::Error::other)?;
+                std::fs::write(&sidecar_path, json)?;
+                Ok(bytes)
+            });
+            let bytes = match copied {
+                Ok(bytes) => bytes,
+                Err(e) => {
+                    // Don't leave a partial copy that looks like a complete export
+                    let _ = std::fs::remove_dir_all(&target).or_else(|_| std::fs::remove_file(&target));
+                    tracing::warn!("Failed to export {}: {}", original.display(), e);
+                    result.failed.push(format!("{}: {}", original.display(), e));
+                    continue;
+                }
+            };
+
+            let purged = purge_after
+                && match self.backend.purge_all(vec![item]) {
+                    Ok(()) => {
+                        self.stats.record_purged(1);
+                        true
+                    }
+                    Err(e) => {
+                        self.stats.record_error(&e);
+                        result.failed.push(format!("{}: exported but not purged: {}", original.display(), e));
+                        false
+                    }
+                };
+            tracing::info!("Exported {} to {}", original.display(), target.display());
+            result.exported.push(ExportedItem {
+                original_path: original.to_string_lossy().into_owned(),
+                exported_path: target.to_string_lossy().into_owned(),
+                sidecar_path: sidecar_path.to_string_lossy().into_owned(),
+                bytes,
+                purged,
+            });
+        }
+
+        let purged = result.exported.iter().filter(|item| item.purged).count();
+        let mut msg = format!("Exported {} item(s) to {}", result.exported.len(), destination.display());
+        if purge_after {
+            msg.push_str(&format!("; purged {} from the trash", purged));
+        } else {
+            msg.push_str("; the trash entries were kept");
+        }
+        for item in &result.exported {
+            msg.push_str(&format!("\n  {} -> {} ({})", item.original_path, item.exported_path, format::format_bytes(item.bytes)));
+        }
+        if !result.failed.is_empty() {
+            msg.push_str(&format!("\nFailed:\n  {}", result.failed.join("\n  ")));
+        }
+        Ok(self.structured_result(msg, result))
+    }
+
+    #[rmcp::tool(description = "Restore every item in the trash to its original location (Linux/Windows only). Use dry_run to see which would collide with existing files first")]
+    pub async fn restore_all(
+        &self,
+        Parameters(params): Parameters<RestoreAllParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let scope = self.scope(params.profile.as_deref())?;
+
+        if params.dry_run != Some(true) {
+            if let Some(limited) = self.rate_limited("restore_all") {
+                return Ok(limited);
+            }
+        }
+
+        if !self.backend.supports_listing() {
+            return Ok(self.unsupported("restore_all"));
+        }
+
+        let mut items = match self.list_in_scope(&scope) {
+            Ok(items) => items,
+            Err(e) => {
+                return Ok(self.notice(
+                    Outcome::Failed,
+                    format!("Failed to list trash: {}", e)
+                ));
+            }
+        };
+        if items.is_empty() {
+            return Ok(self.notice(Outcome::NothingToDo, "Trash is empty"));
+        }
+
+        // Newest first, so the most recent copy of a path trashed twice wins
+        items.sort_by_key(|item| std::cmp::Reverse(item.time_deleted));
+        let mut seen = std::collections::HashSet::new();
+        let conflicting: Vec<bool> = items
+            .iter()
+            .map(|item| {
+                let original = item.original_path();
+                original.symlink_metadata().is_ok() || !seen.insert(paths::fold_case(&original))
+            })
+            .collect();
+
+        let on_conflict = params.on_conflict.unwrap_or_default();
+        let dry_run = params.dry_run == Some(true);
+        let mut result = RestoreAllResult {
+            dry_run,
+            ..Default::default()
+        };
+
+        if dry_run || (on_conflict == OnConflict::Abort && conflicting.contains(&true)) {
+            for (item, conflict) in items.iter().zip(&conflicting) {
+                let original = item.original_path();
+                if *conflict {
+                    result.conflicts.push(original.to_string_lossy().into_owned());
+                } else {
+                    result.restored.push(RestoredItem::new(&original, &original));
+                }
+            }
+            let mut msg = if dry_run {
+                format!(
+                    "Dry run: {} item(s) would restore, {} would collide",
+                    result.restored.len(),
+                    result.conflicts.len()
+                )
+            } else {
+                result.restored.clear();
+                format!(
+                    "Aborted: {} item(s) would collide with existing files; nothing was restored",
+                    result.conflicts.len()
+                )
+            };
+            if !result.conflicts.is_empty() {
+                msg.push_str(&format!("\nConflicts:\n  {}", result.conflicts.join("\n  ")));
+            }
+            return Ok(self.structured_result(msg, result));
+        }
+
+        for item in items {
+            let original = item.original_path();
+            let display = original.to_string_lossy().into_owned();
+            let expected = self.backend.recorded_mode(&item);
+            if original.symlink_metadata().is_ok() && on_conflict == OnConflict::Rename {
+                let target = free_sibling(&original, "restored");
+                let outcome = self.backend.restore_to(&item, &target);
+                match &outcome {
+                    Ok(()) => self.stats.record_restored(1),
+                    Err(e) => self.stats.record_error(e),
+                }
+                match outcome {
+                    Ok(()) => {
+                        result.mode_mismatches.extend(self.verify_mode(&target, expected));
+                        result.restored.push(RestoredItem::new(&original, &target));
+                    }
+                    Err(e) => result.failed.push(format!("{}: {}", display, e)),
+                }
+                continue;
+            }
+            let outcome = self.backend.restore_all(vec![item]);
+            match &outcome {
+                Ok(()) => self.stats.record_restored(1),
+                Err(e) => self.stats.record_error(e),
+            }
+            match outcome {
+                Ok(()) => {
+                    result.mode_mismatches.extend(self.verify_mode(&original, expected));
+                    result.restored.push(RestoredItem::new(&original, &original));
+                }
+                Err(trash::Error::RestoreCollision { .. }) => result.conflicts.push(display),
+                Err(e) => result.failed.push(format!("{}: {}", display, e)),
+            }
+        }
+
+        let renamed: Vec<&RestoredItem> = result.restored.iter().filter(|r| !r.in_place).collect();
+        tracing::info!(
+            "Restored {} item(s), renamed {}, skipped {}",
+            result.restored.len(),
+            renamed.len(),
+            result.conflicts.len()
+        );
+        let mut msg = format!("Restored {} item(s)", result.restored.len());
+        if !renamed.is_empty() {
+            msg.push_str("\nRenamed to avoid collisions:");
+            for restored in renamed {
+                msg.push_str(&format!("\n  {}", restored.describe()));
+            }
+        }
+        if !result.conflicts.is_empty() {
+            msg.push_str(&format!(
+                "\nCollisions (path already exists, left in trash):\n  {}",
+                result.conflicts.join("\n  ")
+            ));
+        }
+        if !result.failed.is_empty() {
+            msg.push_str(&format!("\nFailed:\n  {}", result.failed.join("\n  ")));
+        }
+        msg.push_str(&mode_report(&result.mode_mismatches));
+        self.remember_restore(result.restored.iter().map(|r| r.path.clone()));
+        Ok(self.structured_result(msg, result))
+    }
+
+    #[rmcp::tool(description = "Permanently delete all items in the trash, once confirmed with `confirm: true` or the server's confirmation phrase (Linux/Windows only). This cannot be undone!")]
+    pub async fn empty_trash(
+        &self,
+        Parameters(params): Parameters<EmptyTrashParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let scope = self.scope(params.profile.as_deref())?;
+
+        if let Some(phrase) = &self.config.empty_phrase {
+            if params.confirmation.as_deref().map(str::trim) != Some(phrase.as_str()) {
+                tracing::warn!("Refused empty_trash: confirmation phrase missing or wrong");
+                return Ok(self.notice(
+                    Outcome::Refused,
+                    "Refusing to empty the trash: this server requires `confirmation` to be the phrase its operator \
+                     configured (RMCP_TRASH_EMPTY_PHRASE). Ask the user to type it; it can't be looked up",
+                ));
+            }
+        } else if params.confirm != Some(true) {
+            return Ok(self.notice(
+                Outcome::Refused,
+                "empty_trash permanently deletes everything in the trash; pass confirm: true to proceed, or preview_empty_trash to see what it would delete"
+            ));
+        }
+
+        if let Some(limited) = self.rate_limited("empty_trash") {
+            return Ok(limited);
+        }
+
+        if !self.backend.supports_listing() {
+            return Ok(self.unsupported("empty_trash"));
+        }
+
+        match self.list_in_scope(&scope) {
+            Ok(items) => {
+                if items.is_empty() {
+                    return Ok(self.notice(
+                        Outcome::NothingToDo,
+                        "Trash is already empty"
+                    ));
+                }
+
+                // One at a time, so an item that can't be deleted doesn't keep
+                // the rest in the trash
+                let count = items.len();
+                let started = Instant::now();
+                let mut sizes = Vec::new();
+                let mut failed = Vec::new();
+                for item in items {
+                    let size = self.backend.metadata(&item).ok().and_then(|meta| meta.size.size());
+                    let original = item.original_path();
+                    match self.backend.purge_all(vec![item]) {
+                        Ok(()) => sizes.push(size),
+                        Err(e) => {
+                            tracing::warn!("Failed to purge {}: {}", original.display(), e);
+                            self.stats.record_error(&e);
+                            failed.push(format!("{}: {}", original.display(), e));
+                        }
+                    }
+                }
+                let purged = sizes.len();
+                let reclaimed_bytes = sizes.iter().flatten().sum();
+                let timing = BatchTiming::new(started.elapsed(), purged, known_total_size(sizes));
+                tracing::info!("Emptied trash ({} of {} items) in {} ms", purged, count, timing.elapsed_ms);
+                self.stats.record_purged(purged);
+
+                let mut msg = format!(
+                    "Permanently deleted {} item(s) from trash ({})",
+                    purged,
+                    format::format_bytes(reclaimed_bytes)
+                );
+                if !failed.is_empty() {
+                    msg.push_str(&format!(
+                        "\n{} item(s) could not be deleted and remain in the trash:\n  {}",
+                        failed.len(),
+                        failed.join("\n  ")
+                    ));
+                }
+                Ok(self.structured_result(
+                    msg,
+                    EmptyTrashResult {
+                        purged,
+                        reclaimed_bytes,
+                        failed,
+                        timing,
+                    },
+                ))
+            }
+            Err(e) => Ok(self.notice(
+                Outcome::Failed,
+                format!("Failed to list trash: {}", e)
+            )),
+        }
+    }
+
+    #[rmcp::tool(description = "Preview what empty_trash would permanently delete, grouped by original directory with counts and sizes (Linux/Windows only). Deletes nothing")]
+    pub async fn preview_empty_trash(
+        &self,
+        Parameters(params): Parameters<ProfileParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let scope = self.scope(params.profile.as_deref())?;
+
+        if !self.backend.supports_listing() {
+            return Ok(self.unsupported("preview_empty_trash"));
+        }
+
+        let items = match self.list_in_scope(&scope) {
+            Ok(items) => items,
+            Err(e) => {
+                return Ok(self.notice(
+                    Outcome::Failed,
+                    format!("Failed to list trash: {}", e)
+                ));
+            }
+        };
+        if items.is_empty() {
+            return Ok(self.notice(Outcome::NothingToDo, "Trash is already empty"));
+        }
+
+        let groups = self.group_by_dir(&items);
+        let bytes = groups.iter().map(|group| group.bytes).sum();
+        let mut msg = format!(
+            "empty_trash would permanently delete {} item(s) ({}):",
+            items.len(),
+            format::format_bytes(bytes)
+        );
+        for group in &groups {
+            msg.push_str(&format!(
+                "\n  {}: {} item(s), {}",
+                group.dir,
+                group.count,
+                format::format_bytes(group.bytes)
+            ));
+            if group.unsized_items > 0 {
+                msg.push_str(&format!(" (+{} not sized)", group.unsized_items));
+            }
+        }
+        Ok(self.structured_result(
+            msg,
+            EmptyTrashPreview {
+                count: items.len(),
+                bytes,
+                groups,
+            },
+        ))
+    }
+
+    #[rmcp::tool(description = "Summarize the trash by file extension: item count and total size per extension, largest first (Linux/Windows only)")]
+    pub async fn trash_summary_by_extension(
+        &self,
+        Parameters(params): Parameters<ProfileParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let scope = self.scope(params.profile.as_deref())?;
+
+        if !self.backend.supports_listing() {
+            return Ok(self.unsupported("trash_summary_by_extension"));
+        }
+
+        let items = match self.list_in_scope(&scope) {
+            Ok(items) => items,
+            Err(e) => {
+                return Ok(self.notice(
+                    Outcome::Failed,
+                    format!("Failed to list trash: {}", e)
+                ));
+            }
+        };
+        if items.is_empty() {
+            return Ok(self.notice(Outcome::NothingToDo, "Trash is empty"));
+        }
+
+        let groups: Vec<ExtensionGroup> = self
+            .tally_by(&items, |item| {
+                Path::new(&item.name)
+                    .extension()
+                    .map(|ext| ext.to_string_lossy().to_lowercase())
+            })
+            .into_iter()
+            .map(|(extension, tally)| ExtensionGroup {
+                extension: extension.unwrap_or_else(|| "(none)".into()),
+                count: tally.count,
+                bytes: tally.bytes,
+                unsized_items: tally.unsized_items,
+            })
+            .collect();
+        let bytes = groups.iter().map(|group| group.bytes).sum();
+        let mut msg = format!(
+            "Trash holds {} item(s) ({}) by extension:",
+            items.len(),
+            format::format_bytes(bytes)
+        );
+        for group in &groups {
+            msg.push_str(&format!(
+                "\n  {}: {} item(s), {}",
+                group.extension,
+                group.count,
+                format::format_bytes(group.bytes)
+            ));
+            if group.unsized_items > 0 {
+                msg.push_str(&format!(" (+{} not sized)", group.unsized_items));
+            }
+        }
+        Ok(self.structured_result(
+            msg,
+            ExtensionSummary {
+                count: items.len(),
+                bytes,
+                groups,
+            },
+        ))
+    }
+
+    #[rmcp::tool(description = "Count trash items and their total size by how long ago they were deleted, in day buckets (Linux/Windows only). Read-only")]
+    pub async fn trash_age_histogram(
+        &self,
+        Parameters(params): Parameters<TrashAgeHistogramParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let scope = self.scope(params.profile.as_deref())?;
+
+        let boundaries = params.boundaries_days.unwrap_or_else(|| vec![1, 7, 30, 90]);
+        if boundaries.is_empty() || boundaries[0] == 0 || boundaries.windows(2).any(|pair| pair[0] >= pair[1]) {
+            return Err(McpError::invalid_params(
+                "boundaries_days must be a non-empty list of increasing whole days, starting above 0",
+                None,
+            ));
+        }
+
+        if !self.backend.supports_listing() {
+            return Ok(self.unsupported("trash_age_histogram"));
+        }
+
+        let items = match self.list_in_scope(&scope) {
+            Ok(items) => items,
+            Err(e) => {
+                return Ok(self.notice(
+                    Outcome::Failed,
+                    format!("Failed to list trash: {}", e)
+                ));
+            }
+        };
+
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs() as i64);
+        let mut tallies: Vec<Option<SizeTally>> = (0..=boundaries.len()).map(|_| None).collect();
+        for (index, tally) in self.tally_by(&items, |item| {
+            let age_days = (now - item.time_deleted).max(0) as u64 / 86_400;
+            boundaries.iter().position(|&bound| age_days < bound).unwrap_or(boundaries.len())
+        }) {
+            tallies[index] = Some(tally);
+        }
+
+        let buckets: Vec<AgeBucket> = tallies
+            .into_iter()
+            .enumerate()
+            .map(|(index, tally)| {
+                let tally = tally.unwrap_or_default();
+                let min_days = index.checked_sub(1).map_or(0, |prev| boundaries[prev]);
+                let max_days = boundaries.get(index).copied();
+                let label = match (index, max_days) {
+                    (0, Some(max)) => format!("<{}d", max),
+                    (_, Some(max)) => format!("{}-{}d", min_days, max),
+                    (_, None) => format!(">={}d", min_days),
+                };
+                AgeBucket {
+                    label,
+                    min_days,
+                    max_days,
+                    count: tally.count,
+                    bytes: tally.bytes,
+                    unsized_items: tally.unsized_items,
+                }
+            })
+            .collect();
+        let bytes = buckets.iter().map(|bucket| bucket.bytes).sum();
+
+        let mut msg = format!(
+            "Trash holds {} item(s) ({}) by age since deletion:",
+            items.len(),
+            format::format_bytes(bytes)
+        );
+        for bucket in &buckets {
+            msg.push_str(&format!(
+                "\n  {:>8}: {} item(s), {}",
+                bucket.label,
+                bucket.count,
+                format::format_bytes(bucket.bytes)
+            ));
+            if bucket.unsized_items > 0 {
+                msg.push_str(&format!(" (+{} not sized)", bucket.unsized_items));
+            }
+        }
+        Ok(self.structured_result(
+            msg,
+            AgeHistogram {
+                count: items.len(),
+                bytes,
+                buckets,
+            },
+        ))
+    }
+
+    #[rmcp::tool(description = "Show how much free space purging the trash items matching some patterns would give: current free space, the bytes they occupy, and the free space afterwards, per trash volume (Linux/Windows only). Purges nothing")]
+    pub async fn purge_preview(
+        &self,
+        Parameters(params): Parameters<PurgePreviewParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let scope = self.scope(params.profile.as_deref())?;
+
+        if params.patterns.is_empty() {
+            return Err(McpError::invalid_params("No patterns provided: `patterns` is empty", None));
+        }
+
+        if !self.backend.supports_listing() {
+            return Ok(self.unsupported("purge_preview"));
+        }
+
+        let items = match self.list_in_scope(&scope) {
+            Ok(items) => items,
+            Err(e) => {
+                return Ok(self.notice(
+                    Outcome::Failed,
+                    format!("Failed to list trash: {}", e)
+                ));
+            }
+        };
+        let kind = params.match_kind.unwrap_or_default();
+        let patterns: Vec<pattern::Pattern> = params.patterns.iter().map(|p| pattern::Pattern::new(p, kind)).collect();
+        let matches: Vec<_> = items
+            .into_iter()
+            .filter(|item| {
+                let name = item.name.to_string_lossy();
+                let original = item.original_path().to_string_lossy().into_owned();
+                patterns.iter().any(|p| p.matches(&name) || p.matches(&original))
+            })
+            .collect();
+        if matches.is_empty() {
+            return Ok(self.notice(
+                Outcome::NothingToDo,
+                format!("No items in trash matching {}", params.patterns.join(", "))
+            ));
+        }
+
+        // Attribute each item to the trash directory holding its data, so
+        // per-volume trash directories are projected separately
+        let locations = self.backend.locations();
+        let data: Vec<PathBuf> = matches.iter().filter_map(|item| self.backend.data_path(item)).collect();
+        let max_depth = self.config.max_walk_depth;
+        let mut progress = Progress::new(&context);
+        let sized: Vec<(PathBuf, u64)> = crate::blocking(move || {
+            data.into_iter()
+                .map(|data| {
+                    let bytes = match data.symlink_metadata() {
+                        Ok(meta) if meta.is_dir() => {
+                            tree_size(&data, max_depth, &mut progress).map_or(0, |(_, bytes, _)| bytes)
+                        }
+                        Ok(meta) => meta.len(),
+                        Err(_) => 0,
+                    };
+                    (data, bytes)
+                })
+                .collect()
+        })
+        .await;
+        let mut volumes: std::collections::BTreeMap<PathBuf, (usize, u64)> = std::collections::BTreeMap::new();
+        for (data, bytes) in sized {
+            let location = locations
+                .iter()
+                .filter(|location| paths::starts_with(&data, location))
+                .max_by_key(|location| location.as_os_str().len())
+                .cloned()
+                .or_else(|| data.parent().map(Path::to_path_buf))
+                .unwrap_or_default();
+            let volume = volumes.entry(location).or_default();
+            volume.0 += 1;
+            volume.1 += bytes;
+        }
+
+        let volumes: Vec<VolumeProjection> = volumes
+            .into_iter()
+            .map(|(location, (count, purge_bytes))| {
+                let free_bytes = disk::disk_space(&location).ok().map(|space| space.free_bytes);
+                VolumeProjection {
+                    location: location.to_string_lossy().into_owned(),
+                    count,
+                    purge_bytes,
+                    free_bytes,
+                    projected_free_bytes: free_bytes.map(|free| free.saturating_add(purge_bytes)),
+                }
+            })
+            .collect();
+        let purge_bytes = volumes.iter().map(|volume| volume.purge_bytes).sum();
+
+        let mut msg = format!(
+            "Purging {} matching item(s) would free {}",
+            matches.len(),
+            format::format_bytes(purge_bytes)
+        );
+        for volume in &volumes {
+            msg.push_str(&format!(
+                "\n  {}: {} item(s), {}",
+                volume.location,
+                volume.count,
+                format::format_bytes(volume.purge_bytes)
+            ));
+            match (volume.free_bytes, volume.projected_free_bytes) {
+                (Some(free), Some(projected)) => msg.push_str(&format!(
+                    "; free {} -> {}",
+                    format::format_bytes(free),
+                    format::format_bytes(projected)
+                )),
+                _ => msg.push_str("; free space unknown"),
+            }
+        }
+        Ok(self.structured_result(
+            msg,
+            PurgePreview {
+                matched: matches.iter().map(TrashEntry::from).collect(),
+                purge_bytes,
+                volumes,
+            },
+        ))
+    }
+
+    #[rmcp::tool(description = "Find trashed files with identical content (SHA-256) and, in each group, mark all but the most recently trashed copy as purge candidates. Read-only: nothing is purged (Linux/Windows only)")]
+    pub async fn trash_duplicates(
+        &self,
+        Parameters(params): Parameters<TrashDuplicatesParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let scope = self.scope(params.profile.as_deref())?;
+
+        if !self.backend.supports_listing() {
+            return Ok(self.unsupported("trash_duplicates"));
+        }
+
+        let items = match self.list_in_scope(&scope) {
+            Ok(items) => items,
+            Err(e) => {
+                return Ok(self.notice(
+                    Outcome::Failed,
+                    format!("Failed to list trash: {}", e)
+                ));
+            }
+        };
+        if items.is_empty() {
+            return Ok(self.notice(Outcome::NothingToDo, "Trash is empty"));
+        }
+
+        // Only files that share a size with another can be duplicates, so
+        // everything else is never read
+        let min = params.min_size_bytes.unwrap_or(1);
+        let max = params.max_size_bytes.unwrap_or(u64::MAX);
+        let mut by_size: std::collections::BTreeMap<u64, Vec<(&trash::TrashItem, PathBuf)>> = std::collections::BTreeMap::new();
+        for item in &items {
+            let Some(data) = self.backend.data_path(item) else {
+                continue;
+            };
+            match data.symlink_metadata() {
+                Ok(meta) if meta.is_file() && (min..=max).contains(&meta.len()) => {
+                    by_size.entry(meta.len()).or_default().push((item, data));
+                }
+                _ => {}
+            }
+        }
+
+        let mut report = DuplicateReport {
+            hashed: 0,
+            groups: Vec::new(),
+            reclaimable_bytes: 0,
+            errors: Vec::new(),
+        };
+        for (size, files) in by_size.into_iter().rev().filter(|(_, files)| files.len() > 1) {
+            let mut by_hash: std::collections::BTreeMap<String, Vec<&trash::TrashItem>> = std::collections::BTreeMap::new();
+            for (item, data) in files {
+                match hash::sha256_file(&data) {
+                    Ok(sha256) => {
+                        report.hashed += 1;
+                        by_hash.entry(sha256).or_default().push(item);
+                    }
+                    Err(e) => report.errors.push(format!("{}: {}", item.original_path().display(), e)),
+                }
+            }
+            for (sha256, mut copies) in by_hash.into_iter().filter(|(_, copies)| copies.len() > 1) {
+                copies.sort_by_key(|item| std::cmp::Reverse(item.time_deleted));
+                let keep = TrashEntry::from(copies[0]);
+                let purge_candidates: Vec<TrashEntry> = copies[1..].iter().map(|item| TrashEntry::from(*item)).collect();
+                report.reclaimable_bytes += size * purge_candidates.len() as u64;
+                report.groups.push(DuplicateGroup {
+                    sha256,
+                    size_bytes: size,
+                    keep,
+                    purge_candidates,
+                });
+            }
+        }
+
+        let mut msg = if report.groups.is_empty() {
+            format!("No duplicate files in trash ({} file(s) hashed)", report.hashed)
+        } else {
+            format!(
+                "{} group(s) of identical files in trash; purging the older copies would free {}",
+                report.groups.len(),
+                format::format_bytes(report.reclaimable_bytes)
+            )
+        };
+        for group in &report.groups {
+            msg.push_str(&format!(
+                "\n  {} ({} each):\n    keep   {}",
+                &group.sha256[..12],
+                format::format_bytes(group.size_bytes),
+                group.keep.original_path
+            ));
+            for candidate in &group.purge_candidates {
+                msg.push_str(&format!("\n    purge  {}", candidate.original_path));
+            }
+        }
+        if !report.errors.is_empty() {
+            msg.push_str(&format!("\nCould not read:\n  {}", report.errors.join("\n  ")));
+        }
+        Ok(self.structured_result(msg, report))
+    }
+
+    #[rmcp::tool(description = "Report the scheduled cleanup configured with RMCP_TRASH_SCHEDULE: the age and interval, how many runs so far, what the last run purged and reclaimed, and when the next run is due")]
+    pub async fn scheduler_status(&self) -> Result<CallToolResult, McpError> {
+        let Some(scheduler) = &self.scheduler else {
+            return Ok(self.notice(
+                Outcome::NothingToDo,
+                "Scheduled cleanup is off: start the server with RMCP_TRASH_SCHEDULE (e.g. 30d/6h) to purge old items periodically"
+            ));
+        };
+        let status = scheduler.status();
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs() as i64);
+        let mut msg = format!(
+            "Scheduled cleanup: purges items deleted more than {} ago, every {}\n  Runs so far: {}",
+            format::format_duration(status.max_age_secs),
+            format::format_duration(status.every_secs),
+            status.runs
+        );
+        if let Some(run) = &status.last_run {
+            let ago = format::format_duration(now.saturating_sub(run.at).max(0) as u64);
+            match &run.error {
+                Some(e) => msg.push_str(&format!("\n  Last run: {} ago, failed: {}", ago, e)),
+                None => msg.push_str(&format!(
+                    "\n  Last run: {} ago, purged {} item(s) ({}); {} protected item(s) kept",
+                    ago,
+                    run.purged,
+                    format::format_bytes(run.bytes),
+                    run.skipped_protected
+                )),
+            }
+            if !run.failed.is_empty() {
+                msg.push_str(&format!(
+                    "\n  Could not purge {} item(s):\n    {}",
+                    run.failed.len(),
+                    run.failed.join("\n    ")
+                ));
+            }
+        }
+        msg.push_str(&format!(
+            "\n  Next run: in {}",
+            format::format_duration(status.next_run.saturating_sub(now).max(0) as u64)
+        ));
+        Ok(self.structured_result(msg, status))
+    }
+
+    #[rmcp::tool(description = "Scan the trash directories for metadata without a trashed file and trashed files without metadata, optionally removing them (Linux trash or RMCP_TRASH_DIR only). fix permanently deletes orphaned files")]
+    pub async fn trash_consistency_check(
+        &self,
+        Parameters(params): Parameters<ConsistencyCheckParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let fix = params.fix == Some(true);
+        if fix {
+            if let Some(limited) = self.rate_limited("trash_consistency_check") {
+                return Ok(limited);
+            }
+        }
+        let Some(dirs) = consistency::check(&self.backend, fix) else {
+            return Ok(self.unsupported("trash_consistency_check"));
+        };
+
+        let consistent = dirs.iter().all(consistency::DirCheck::is_consistent);
+        let mut msg = if consistent {
+            format!("Trash is consistent ({} trash location(s) checked)", dirs.len())
+        } else {
+            String::from("Trash has inconsistencies:")
+        };
+        for dir in dirs.iter().filter(|dir| !dir.is_consistent()) {
+            msg.push_str(&format!("\n{}:", dir.dir));
+            for (label, paths) in [
+                ("metadata without data", &dir.orphaned_metadata),
+                ("data without metadata", &dir.orphaned_data),
+                ("unreadable metadata", &dir.invalid_metadata),
+            ] {
+                for path in paths {
+                    msg.push_str(&format!("\n  {}: {}", label, path));
+                }
+            }
+            if let Some(e) = &dir.error {
+                msg.push_str(&format!("\n  scan stopped: {}", e));
+            }
+            if fix {
+                msg.push_str(&format!("\n  removed {} orphan(s)", dir.removed));
+            }
+        }
+        let removed: usize = dirs.iter().map(|dir| dir.removed).sum();
+        if removed > 0 {
+            tracing::info!("Removed {} orphaned trash entries", removed);
+        }
+        Ok(self.structured_result(msg, ConsistencyReport { consistent, dirs }))
+    }
+
+    #[rmcp::tool(description = "Permanently delete specific items from the trash by exact name, optionally overwriting their contents first (Linux/Windows only). This cannot be undone!")]
+    pub async fn permanently_delete(
+        &self,
+        Parameters(params): Parameters<PermanentlyDeleteParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let scope = self.scope(params.profile.as_deref())?;
+
+        if let Some(limited) = self.rate_limited("permanently_delete") {
+            return Ok(limited);
+        }
+        let query = NameQuery::from_params(params.name.as_deref(), params.name_encoded.as_deref())?;
+        let name = query.display();
+
+        if !self.backend.supports_listing() {
+            return Ok(self.unsupported("permanently_delete"));
+        }
+
+        let items = match self.list_in_scope(&scope) {
+            Ok(items) => items,
+            Err(e) => {
+                return Ok(self.notice(
+                    Outcome::Failed,
+                    format!("Failed to list trash: {}", e)
+                ));
+            }
+        };
+        let matches: Vec<_> = items
+            .into_iter()
+            .filter(|item| query.matches(&item.name, false))
+            .collect();
+
+        if matches.is_empty() {
+            return Ok(self.notice(
+                Outcome::NothingToDo,
+                format!("No items in trash named '{}'", name)
+            ));
+        }
+
+        let mut msg = String::new();
+        let mut not_wiped = Vec::new();
+        if params.secure == Some(true) {
+            for item in &matches {
+                let original = item.original_path();
+                let data = self.backend.data_path(item);
+                match data.as_deref().map(wipe::wipe) {
+                    Some(Ok(stats)) => {
+                        msg.push_str(&format!(
+                            "Wiped {} ({} file(s), {})\n",
+                            original.display(),
+                            stats.files,
+                            format::format_bytes(stats.bytes)
+                        ));
+                        // Named by where they were trashed from, not their place in the trash
+                        let data = data.unwrap_or_default();
+                        not_wiped.extend(stats.linked.iter().map(|file| {
+                            let file = match file.strip_prefix(&data) {
+                                Ok(rel) if rel.as_os_str().is_empty() => original.clone(),
+                                Ok(rel) => original.join(rel),
+                                Err(_) => file.clone(),
+                            };
+                            format!("{}: has other hard links", file.display())
+                        }));
+                    }
+                    Some(Err(e)) => {
+                        return Ok(self.notice(Outcome::Failed, format!(
+                            "{}Failed to wipe {}: {}; nothing further was deleted",
+                            msg,
+                            original.display(),
+                            e
+                        )));
+                    }
+                    None => {
+                        return Ok(self.notice(Outcome::Unsupported, format!(
+                            "Secure wipe is not available for {}: the trash location of its data is unknown on this platform",
+                            original.display()
+                        )));
+                    }
+                }
+            }
+        }
+
+        let count = matches.len();
+        let deleted = matches
+            .iter()
+            .map(|item| item.original_path().to_string_lossy().into_owned())
+            .collect();
+        match self.backend.purge_all(matches) {
+            Ok(()) => {
+                tracing::info!("Permanently deleted {} item(s) named {}", count, name);
+                self.stats.record_purged(count);
+                msg.push_str(&format!("Permanently deleted {} item(s) named '{}'", count, name));
+                if !not_wiped.is_empty() {
+                    msg.push_str(&format!("\nNot wiped, their data stays reachable through the other links:\n  {}", not_wiped.join("\n  ")));
+                }
+                if params.secure == Some(true) {
+                    msg.push_str("\nNote: secure wipe is best-effort; SSDs and copy-on-write filesystems may retain old data");
+                }
+                Ok(self.structured_result(
+                    msg,
+                    PermanentlyDeleteResult {
+                        deleted,
+                        wiped: params.secure == Some(true),
+                        not_wiped,
+                    },
+                ))
+            }
+            Err(e) => {
+                self.stats.record_error(&e);
+                Ok(self.notice(
+                    Outcome::Failed,
+                    format!("{}Failed to delete: {}", msg, e)
+                ))
+            }
+        }
+    }
+
+    #[rmcp::tool(description = "Permanently delete every item trashed under an undo group (the `group` argument of trash_file, trash_files and trash_files_from_file), finalizing it; deleted members are then forgotten, and any that could not be deleted stay in the group for another try. This cannot be undone (Linux/Windows only)")]
+    pub async fn purge_group(
+        &self,
+        Parameters(params): Parameters<PurgeGroupParams>,
+    ) -> Result<CallToolResult, McpError> {
+        if let Some(limited) = self.rate_limited("purge_group") {
+            return Ok(limited);
+        }
+        if !self.backend.supports_listing() {
+            return Ok(self.unsupported("purge_group"));
+        }
+
+        let (matches, missing) = match self.group_in_trash(&params.group) {
+            Ok(found) => found,
+            Err(result) => return Ok(result),
+        };
+        let missing: Vec<String> = missing.iter().map(|m| m.original.to_string_lossy().into_owned()).collect();
+        if matches.is_empty() {
+            self.undo_groups.remove(&params.group, |_| true);
+            self.save_state();
+            return Ok(self.notice(
+                Outcome::NothingToDo,
+                format!(
+                    "None of the {} item(s) in undo group '{}' are in the trash any more; the group was forgotten",
+                    missing.len(),
+                    params.group
+                ),
+            ));
+        }
+
+        // One at a time, as in empty_trash; members that fail stay in the group
+        let count = matches.len();
+        let mut deleted = Vec::new();
+        let mut failed = Vec::new();
+        let mut kept = Vec::new();
+        for item in matches {
+            let original = item.original_path();
+            match self.backend.purge_all(vec![item]) {
+                Ok(()) => deleted.push(original.to_string_lossy().into_owned()),
+                Err(e) => {
+                    tracing::warn!("Failed to purge {}: {}", original.display(), e);
+                    self.stats.record_error(&e);
+                    failed.push(format!("{}: {}", original.display(), e));
+                    kept.push(paths::fold_case(&original));
+                }
+            }
+        }
+        tracing::info!("Permanently deleted {} of {} item(s) of undo group {}", deleted.len(), count, params.group);
+        self.stats.record_purged(deleted.len());
+        self.undo_groups.remove(&params.group, |member| !kept.contains(&paths::fold_case(&member.original)));
+        self.save_state();
+
+        let mut msg = format!("Permanently deleted {} of {} item(s) in undo group '{}'", deleted.len(), count, params.group);
+        for path in &deleted {
+            msg.push_str(&format!("\n  {}", path));
+        }
+        if !failed.is_empty() {
+            msg.push_str(&format!(
+                "\n{} item(s) could not be deleted and stay in the trash and the group:\n  {}",
+                failed.len(),
+                failed.join("\n  ")
+            ));
+        }
+        if !missing.is_empty() {
+            msg.push_str(&format!("\nNo longer in the trash:\n  {}", missing.join("\n  ")));
+        }
+        Ok(self.structured_result(
+            msg,
+            PurgeGroupResult {
+                group: params.group,
+                deleted,
+                failed,
+                missing,
+            },
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn under_root_rebuilds_the_original_below_root() {
+        assert_eq!(
+            under_root(Path::new("/restore"), Path::new("/home/me/notes.txt")),
+            Some(PathBuf::from("/restore/home/me/notes.txt"))
+        );
+        assert_eq!(
+            under_root(Path::new("/restore"), Path::new("/home/./me/notes.txt")),
+            Some(PathBuf::from("/restore/home/me/notes.txt"))
+        );
+    }
+
+    #[test]
+    fn under_root_refuses_parent_components() {
+        assert_eq!(under_root(Path::new("/restore"), Path::new("/home/../../etc/passwd")), None);
+        assert_eq!(under_root(Path::new("/restore"), Path::new("/home/me/../notes.txt")), None);
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn under_root_turns_the_drive_into_a_directory() {
+        assert_eq!(
+            under_root(Path::new(r"D:\restore"), Path::new(r"C:\Users\me\notes.txt")),
+            Some(PathBuf::from(r"D:\restore\C\Users\me\notes.txt"))
+        );
+    }
+}
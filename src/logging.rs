@@ -0,0 +1,142 @@
+//! Forwarding of this crate's `tracing` events to the MCP client.
+//!
+//! Nothing is sent until the client calls `logging/setLevel`; after that,
+//! events at or above the requested level go out as `notifications/message`.
+//! Only events from this crate are bridged, so the transport's own tracing
+//! can't feed back into itself.
+
+use rmcp::model::{LoggingLevel, LoggingMessageNotificationParam};
+use rmcp::{Peer, RoleServer};
+use std::fmt::Write as _;
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+
+#[derive(Debug)]
+pub struct ClientLogSink {
+    level: Mutex<Option<LoggingLevel>>,
+    tx: mpsc::UnboundedSender<LoggingMessageNotificationParam>,
+    rx: Mutex<Option<mpsc::UnboundedReceiver<LoggingMessageNotificationParam>>>,
+}
+
+impl Default for ClientLogSink {
+    fn default() -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+        Self {
+            level: Mutex::new(None),
+            tx,
+            rx: Mutex::new(Some(rx)),
+        }
+    }
+}
+
+impl ClientLogSink {
+    /// Set the client's minimum level, starting the forwarder on first use.
+    pub fn set_level(&self, level: LoggingLevel, peer: Peer<RoleServer>) {
+        *self.level.lock().unwrap() = Some(level);
+
+        if let Some(mut rx) = self.rx.lock().unwrap().take() {
+            tokio::spawn(async move {
+                while let Some(message) = rx.recv().await {
+                    if peer.notify_logging_message(message).await.is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+    }
+
+    fn enabled(&self, level: LoggingLevel) -> bool {
+        match *self.level.lock().unwrap() {
+            Some(min) => severity(level) >= severity(min),
+            None => false,
+        }
+    }
+
+    pub fn layer(self: &Arc<Self>) -> ClientLogLayer {
+        ClientLogLayer { sink: self.clone() }
+    }
+}
+
+pub struct ClientLogLayer {
+    sink: Arc<ClientLogSink>,
+}
+
+impl<S: Subscriber> Layer<S> for ClientLogLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        if !metadata.target().starts_with(env!("CARGO_CRATE_NAME")) {
+            return;
+        }
+        let level = mcp_level(*metadata.level());
+        if !self.sink.enabled(level) {
+            return;
+        }
+
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+        let _ = self.sink.tx.send(LoggingMessageNotificationParam {
+            level,
+            logger: Some(metadata.target().to_string()),
+            data: serde_json::Value::String(visitor.finish()),
+        });
+    }
+}
+
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+    fields: String,
+}
+
+impl MessageVisitor {
+    fn finish(self) -> String {
+        if self.fields.is_empty() {
+            self.message
+        } else {
+            format!("{}{}", self.message, self.fields)
+        }
+    }
+}
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{:?}", value);
+        } else {
+            let _ = write!(self.fields, " {}={:?}", field.name(), value);
+        }
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message.push_str(value);
+        } else {
+            let _ = write!(self.fields, " {}={}", field.name(), value);
+        }
+    }
+}
+
+fn mcp_level(level: Level) -> LoggingLevel {
+    match level {
+        Level::ERROR => LoggingLevel::Error,
+        Level::WARN => LoggingLevel::Warning,
+        Level::INFO => LoggingLevel::Info,
+        _ => LoggingLevel::Debug,
+    }
+}
+
+fn severity(level: LoggingLevel) -> u8 {
+    match level {
+        LoggingLevel::Debug => 0,
+        LoggingLevel::Info => 1,
+        LoggingLevel::Notice => 2,
+        LoggingLevel::Warning => 3,
+        LoggingLevel::Error => 4,
+        LoggingLevel::Critical => 5,
+        LoggingLevel::Alert => 6,
+        LoggingLevel::Emergency => 7,
+    }
+}
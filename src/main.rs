@@ -2,270 +2,3630 @@
 //!
 //! Move files to trash safely. Cross-platform via trash crate.
 
+mod backend;
+mod config;
+#[cfg(feature = "full")]
+mod consistency;
+mod disk;
+mod errors;
+mod format;
+mod git;
+mod groups;
+mod guard;
+mod hash;
+mod help;
+#[cfg(feature = "full")]
+mod listing;
+mod logging;
+#[cfg(feature = "full")]
+mod mime;
+mod paths;
+mod pattern;
+mod profiles;
+mod progress;
+mod ratelimit;
+mod schedule;
+#[cfg(feature = "full")]
+mod script;
+mod selftest;
+mod staging;
+mod state;
+mod stats;
+#[cfg(test)]
+mod testutil;
+mod walk;
+#[cfg(feature = "full")]
+mod wipe;
+
+use backend::{Backend, CustomTrash};
+use config::Config;
+use logging::ClientLogSink;
+use profiles::{Profile, Scope};
 use rmcp::{
     handler::server::{router::tool::ToolRouter, wrapper::Parameters, ServerHandler},
     model::*,
+    service::RequestContext,
     ErrorData as McpError,
+    RoleServer,
     ServiceExt,
 };
 use schemars::JsonSchema;
-use serde::Deserialize;
-use std::path::PathBuf;
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer};
+
+// === Parameter Types ===
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct TrashFileParams {
+    #[schemars(description = "Path to the file or directory to move to trash")]
+    pub path: String,
+    #[schemars(description = "The same path again, exactly as in `path`; required when the server enforces path confirmation")]
+    pub confirm_path: Option<String>,
+    #[schemars(description = "Override safety refusals (e.g. mount points). Only honoured when the server runs with --allow-force")]
+    pub force: Option<bool>,
+    #[schemars(description = "After trashing, poll the trash listing briefly until the item appears, confirming it was really trashed and not deleted outright")]
+    pub confirm_in_trash: Option<bool>,
+    #[schemars(description = "Refuse if the path is a directory (a symlink to one is still trashed as a link)")]
+    pub require_file: Option<bool>,
+    #[schemars(description = "Return the file's SHA-256, computed just before trashing, as a record of the content removed. Not computed for directories")]
+    pub checksum: Option<bool>,
+    #[schemars(description = "What to do if the path does not exist: report it (default), return an error result, or ignore it as already gone")]
+    pub on_missing: Option<OnMissing>,
+    #[schemars(description = "Also return a one-line summary ready to display, e.g. \"Moved 3 items (2.1 MiB) to trash; 1 skipped\", in `summary`")]
+    pub summary: Option<bool>,
+    #[schemars(description = "Record the trashed items in this undo group, e.g. \"cleanup-2024-06\", so restore_group or purge_group can undo or finalize them together")]
+    pub group: Option<String>,
+    #[schemars(description = "Profile whose base directory and protected rules apply")]
+    pub profile: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct TrashIfOlderParams {
+    #[schemars(description = "Path to the file or directory to move to trash")]
+    pub path: String,
+    #[schemars(description = "Unix time in seconds; the path is trashed only if it was last modified at or before this time (for a directory, its own modification time)")]
+    pub modified_before: i64,
+    #[schemars(description = "The same path again, exactly as in `path`; required when the server enforces path confirmation")]
+    pub confirm_path: Option<String>,
+    #[schemars(description = "Override safety refusals (e.g. mount points). Only honoured when the server runs with --allow-force")]
+    pub force: Option<bool>,
+    #[schemars(description = "What to do if the path does not exist: report it (default), return an error result, or ignore it as already gone")]
+    pub on_missing: Option<OnMissing>,
+    #[schemars(description = "Profile whose base directory and protected rules apply")]
+    pub profile: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ReplaceFileParams {
+    #[schemars(description = "File to replace; created if it doesn't exist yet")]
+    pub path: String,
+    #[schemars(description = "The new content, base64-encoded")]
+    pub new_content_base64: String,
+    #[schemars(description = "Override safety refusals (e.g. mount points). Only honoured when the server runs with --allow-force")]
+    pub force: Option<bool>,
+    #[schemars(description = "Profile whose base directory and protected rules apply")]
+    pub profile: Option<String>,
+}
+
+/// How `trash_file` answers for a path that doesn't exist.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum OnMissing {
+    /// Say it does not exist, without failing the call.
+    #[default]
+    Report,
+    /// Fail the call (`isError`).
+    Error,
+    /// Treat it as trashed already.
+    Ignore,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct TrashFilesParams {
+    #[schemars(description = "List of paths to move to trash. Each entry is a path string or an object `{ path, recursive }`")]
+    pub paths: Vec<PathEntry>,
+    #[schemars(description = "Override safety refusals (e.g. mount points). Only honoured when the server runs with --allow-force")]
+    pub force: Option<bool>,
+    #[schemars(description = "Skip files another process has locked (Windows: open without sharing; Unix: holds an flock) and report them as in use")]
+    pub skip_locked: Option<bool>,
+    #[schemars(description = "List paths that were not found in the message (default true). They are always in the structured result's `missing`")]
+    pub report_missing: Option<bool>,
+    #[schemars(description = "Write a script to this path that puts the trashed items back without this server: a shell script on Unix, PowerShell on Windows. The file must not exist yet")]
+    pub emit_restore_script: Option<String>,
+    #[schemars(description = "For a symlink to a directory: trash the link itself (`link`, default, as trash_file does) or leave it alone (`skip`). The link is never followed, so nothing outside the listed paths is trashed")]
+    pub symlink_dirs: Option<SymlinkDirs>,
+    #[schemars(description = "Also return a one-line summary ready to display, e.g. \"Moved 3 items (2.1 MiB) to trash; 1 skipped\", in `summary`")]
+    pub summary: Option<bool>,
+    #[schemars(description = "Record the trashed items in this undo group, e.g. \"cleanup-2024-06\", so restore_group or purge_group can undo or finalize them together")]
+    pub group: Option<String>,
+    #[schemars(description = "Profile whose base directory and protected rules apply")]
+    pub profile: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct TrashFilesFromFileParams {
+    #[schemars(description = "File containing a JSON array of trash_files entries (path strings or `{ path, recursive }` objects)")]
+    pub file: String,
+    #[schemars(description = "How many items to trash at once (default 4, at most 32)")]
+    pub concurrency: Option<usize>,
+    #[schemars(description = "Override safety refusals (e.g. mount points). Only honoured when the server runs with --allow-force")]
+    pub force: Option<bool>,
+    #[schemars(description = "Skip files another process has locked (Windows: open without sharing; Unix: holds an flock) and report them as in use")]
+    pub skip_locked: Option<bool>,
+    #[schemars(description = "For a symlink to a directory: trash the link itself (`link`, default, as trash_file does) or leave it alone (`skip`). The link is never followed, so nothing outside the listed paths is trashed")]
+    pub symlink_dirs: Option<SymlinkDirs>,
+    #[schemars(description = "Also return a one-line summary ready to display, e.g. \"Moved 3 items (2.1 MiB) to trash; 1 skipped\", in `summary`")]
+    pub summary: Option<bool>,
+    #[schemars(description = "Record the trashed items in this undo group, e.g. \"cleanup-2024-06\", so restore_group or purge_group can undo or finalize them together")]
+    pub group: Option<String>,
+    #[schemars(description = "Profile whose base directory and protected rules apply")]
+    pub profile: Option<String>,
+}
+
+/// How batch trashing treats a symlink to a directory. Neither mode follows
+/// the link: a `recursive` entry that is such a link isn't expanded, and the
+/// directory it points to is never touched.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SymlinkDirs {
+    /// Trash the link, leaving its target in place.
+    #[default]
+    Link,
+    /// Leave the link alone and report it as skipped.
+    Skip,
+}
+
+/// Whether nothing is at `path` any more, not even a dangling symlink.
+fn vanished(path: &Path) -> bool {
+    path.symlink_metadata().is_err_and(|e| e.kind() == std::io::ErrorKind::NotFound)
+}
+
+/// Whether `path` is a symlink whose target is a directory.
+fn is_dir_link(path: &Path) -> bool {
+    path.symlink_metadata().is_ok_and(|meta| meta.file_type().is_symlink()) && path.is_dir()
+}
+
+/// A `trash_files` entry: a bare path, or a path with per-entry options.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum PathEntry {
+    Path(String),
+    Options {
+        #[schemars(description = "Path to the file or directory")]
+        path: String,
+        #[schemars(description = "For a directory, trash each of its entries individually instead of the directory itself. A symlink to a directory is not expanded")]
+        #[serde(default)]
+        recursive: bool,
+    },
+}
+
+impl PathEntry {
+    pub fn path(&self) -> &str {
+        match self {
+            Self::Path(path) | Self::Options { path, .. } => path,
+        }
+    }
+
+    pub fn recursive(&self) -> bool {
+        matches!(self, Self::Options { recursive: true, .. })
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeBasis {
+    /// Last modification time.
+    #[default]
+    Modified,
+    /// Last access time (unreliable on filesystems mounted with noatime).
+    Accessed,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct TrashByPolicyParams {
+    #[schemars(description = "Directory whose files are checked against the policy")]
+    pub dir: String,
+    #[schemars(description = "Only match files at least this many bytes large")]
+    pub min_size_bytes: Option<u64>,
+    #[schemars(description = "Only match files not touched for at least this many days")]
+    pub older_than_days: Option<u64>,
+    #[schemars(description = "Which timestamp older_than_days checks: modified (default) or accessed")]
+    pub time_basis: Option<TimeBasis>,
+    #[schemars(description = "Also check files in subdirectories (symlinks are not followed)")]
+    pub recursive: Option<bool>,
+    #[schemars(description = "Only report what matches; trash nothing")]
+    pub dry_run: Option<bool>,
+    #[schemars(description = "Also return a one-line summary ready to display, e.g. \"Moved 3 items (2.1 MiB) to trash; 1 skipped\", in `summary`")]
+    pub summary: Option<bool>,
+    #[schemars(description = "Profile whose base directory and protected rules apply")]
+    pub profile: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct FindAndTrashParams {
+    #[schemars(description = "Directory to search")]
+    pub dir: String,
+    #[schemars(description = "Only match files whose name matches this pattern (case-insensitive)")]
+    pub pattern: Option<String>,
+    #[schemars(description = "How pattern is compared: substring (default), exact, or glob")]
+    pub match_kind: Option<pattern::MatchKind>,
+    #[schemars(description = "Only match files at least this many bytes large")]
+    pub min_size_bytes: Option<u64>,
+    #[schemars(description = "Only match files not touched for at least this many days")]
+    pub older_than_days: Option<u64>,
+    #[schemars(description = "Which timestamp older_than_days checks: modified (default) or accessed")]
+    pub time_basis: Option<TimeBasis>,
+    #[schemars(description = "Also search subdirectories (symlinks are not followed)")]
+    pub recursive: Option<bool>,
+    #[schemars(description = "Only report what matches; trash nothing")]
+    pub dry_run: Option<bool>,
+    #[schemars(description = "Also return a one-line summary ready to display, e.g. \"Moved 3 items (2.1 MiB) to trash; 1 skipped\", in `summary`")]
+    pub summary: Option<bool>,
+    #[schemars(description = "Profile whose base directory and protected rules apply")]
+    pub profile: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct TrashFilteredParams {
+    #[schemars(description = "Directory to search")]
+    pub dir: String,
+    #[schemars(description = "A file is trashed if it matches at least one of these, e.g. [\"*.log\"]. Patterns containing `/` are matched against the path relative to dir, others against the file name")]
+    pub include_patterns: Vec<String>,
+    #[schemars(description = "A file matching any of these is kept even if it matches an include pattern, e.g. [\"important.log\"]")]
+    pub exclude_patterns: Option<Vec<String>>,
+    #[schemars(description = "How patterns are compared (case-insensitive): glob (default), substring, or exact")]
+    pub match_kind: Option<pattern::MatchKind>,
+    #[schemars(description = "Also search subdirectories (default true; symlinks are not followed)")]
+    pub recursive: Option<bool>,
+    #[schemars(description = "Only report what matches; trash nothing")]
+    pub dry_run: Option<bool>,
+    #[schemars(description = "Also return a one-line summary ready to display, e.g. \"Moved 3 items (2.1 MiB) to trash; 1 skipped\", in `summary`")]
+    pub summary: Option<bool>,
+    #[schemars(description = "Profile whose base directory and protected rules apply")]
+    pub profile: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct TrashCommonJunkParams {
+    #[schemars(description = "Directory to clean up")]
+    pub dir: String,
+    #[schemars(description = "Glob patterns for junk names, replacing the built-in set (.DS_Store, Thumbs.db, ehthumbs.db, *.tmp, *.pyc, __pycache__, .pytest_cache)")]
+    pub patterns: Option<Vec<String>>,
+    #[schemars(description = "Also trash node_modules directories (default false). Without it they are never matched, even when listed in patterns")]
+    pub include_node_modules: Option<bool>,
+    #[schemars(description = "Also look in subdirectories (default true; symlinks are not followed, .git is skipped)")]
+    pub recursive: Option<bool>,
+    #[schemars(description = "Only report what matches; trash nothing")]
+    pub dry_run: Option<bool>,
+    #[schemars(description = "Also return a one-line summary ready to display, e.g. \"Moved 3 items (2.1 MiB) to trash; 1 skipped\", in `summary`")]
+    pub summary: Option<bool>,
+    #[schemars(description = "Profile whose base directory and protected rules apply")]
+    pub profile: Option<String>,
+}
+
+/// Which files a cleanup plan picks first.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum CleanupOrder {
+    #[default]
+    Largest,
+    Oldest,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct CleanupPlanParams {
+    #[schemars(description = "Directory to pick files from")]
+    pub dir: String,
+    #[schemars(description = "Free space wanted on the directory's filesystem, in bytes")]
+    pub target_free_bytes: u64,
+    #[schemars(description = "Pick the largest (default) or the oldest files first")]
+    pub prefer: Option<CleanupOrder>,
+    #[schemars(description = "Which timestamp `prefer: oldest` uses: modified (default) or accessed")]
+    pub time_basis: Option<TimeBasis>,
+    #[schemars(description = "Also pick files in subdirectories (default true; symlinks are not followed)")]
+    pub recursive: Option<bool>,
+    #[schemars(description = "Profile whose base directory and protected rules apply")]
+    pub profile: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct IsProtectedParams {
+    #[schemars(description = "Path to check; `~` and environment variables are expanded")]
+    pub path: String,
+    #[schemars(description = "Profile whose base directory and protected rules apply")]
+    pub profile: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct HelpParams {
+    #[schemars(description = "Only describe this tool (default: every available tool)")]
+    pub tool: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SnapshotUsageParams {
+    #[schemars(description = "Directory whose recursive size to record")]
+    pub dir: String,
+    #[schemars(description = "Name to record it under, e.g. \"before-cleanup\"; an existing snapshot with this label is replaced")]
+    pub label: String,
+    #[schemars(description = "Profile whose base directory applies")]
+    pub profile: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct DiffUsageParams {
+    #[schemars(description = "Label of a snapshot taken with snapshot_usage")]
+    pub label: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct DiskSpaceParams {
+    #[schemars(description = "Path on the filesystem to inspect (defaults to the trash location)")]
+    pub path: Option<String>,
+}
+
+// === Structured Output ===
+
+/// Version of the structured payloads, sent as `schema_version` in every one.
+///
+/// Adding a field to a payload does not change the version, so clients should
+/// ignore fields they don't know. Renaming, removing or changing the type or
+/// meaning of an existing field bumps it.
+const SCHEMA_VERSION: u32 = 2;
+
+/// Why a response carries only a message, or why a batch changed nothing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Outcome {
+    /// Nothing matched, or there was nothing to change.
+    NothingToDo,
+    Refused,
+    Failed,
+    /// The tool can't run with this backend or configuration.
+    Unsupported,
+    RateLimited,
+}
+
+/// Structured payload of a plain-message response.
+#[derive(Debug, Serialize)]
+struct Notice {
+    outcome: Outcome,
+    message: String,
+}
+
+#[derive(Debug, Serialize)]
+struct TrashFileResult {
+    /// `path` as given, possibly relative.
+    input_path: String,
+    /// Absolute path that was trashed.
+    resolved_path: String,
+    bytes: u64,
+    /// SHA-256 of the content, when `checksum` was requested for a file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sha256: Option<String>,
+    /// Held in the staging area rather than in the trash yet.
+    staged: bool,
+    git_tracked: bool,
+    /// Other hard links to the file, which keep its data on disk (Unix only).
+    other_links: u64,
+    /// Outcome of `confirm_in_trash`, when requested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    confirmation: Option<String>,
+    /// One-line outcome for display, when `summary` was requested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    summary: Option<String>,
+    /// Undo group the items were recorded in, when `group` was given.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    group: Option<String>,
+}
+
+/// `trash_if_older` payload for a path modified after the cutoff.
+#[derive(Debug, Serialize)]
+struct TooNewResult {
+    path: String,
+    status: &'static str,
+    /// Unix time the path was last modified.
+    modified: i64,
+    modified_before: i64,
+}
+
+#[derive(Debug, Serialize)]
+struct ReplaceFileResult {
+    path: String,
+    resolved_path: String,
+    /// Size of the version moved to the trash; absent when the file was new.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    previous_bytes: Option<u64>,
+    bytes_written: u64,
+    /// The previous version is held in the staging area rather than in the trash yet.
+    staged: bool,
+}
+
+/// Wall-clock cost of a multi-item operation.
+#[derive(Debug, Serialize)]
+struct BatchTiming {
+    elapsed_ms: u64,
+    items_per_sec: f64,
+    /// Total size, present only when every item's size was known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bytes: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bytes_per_sec: Option<f64>,
+}
+
+impl BatchTiming {
+    fn new(elapsed: std::time::Duration, items: usize, bytes: Option<u64>) -> Self {
+        let secs = elapsed.as_secs_f64().max(f64::EPSILON);
+        Self {
+            elapsed_ms: elapsed.as_millis() as u64,
+            items_per_sec: items as f64 / secs,
+            bytes,
+            bytes_per_sec: bytes.map(|b| b as f64 / secs),
+        }
+    }
+}
+
+/// Why a `trash_files` entry was left alone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum SkipReason {
+    NotFound,
+    /// Same file as an earlier entry, under another spelling.
+    Duplicate,
+    OutsideProfile,
+    MountPoint,
+    WorkingDirectory,
+    /// Matched a built-in, file or profile protection rule.
+    Protected,
+    /// Locked by another process (`skip_locked`).
+    InUse,
+    /// Replaced or moved between the safety checks and the move to the trash.
+    Changed,
+    /// A symlink to a directory, with `symlink_dirs: skip`.
+    SymlinkDir,
+}
+
+#[derive(Debug, Serialize)]
+struct SkippedEntry {
+    path: String,
+    reason: SkipReason,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct Capabilities<'a> {
+    build: &'static str,
+    backend: &'static str,
+    /// Listing, restore and purge are available.
+    listing: bool,
+    staging: bool,
+    /// `None` when the trash couldn't be probed without leaving a file in it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    startup_probe: Option<&'a selftest::SelfTestReport>,
+}
+
+#[derive(Debug, Serialize)]
+struct TrashFilesResult {
+    trashed: Vec<String>,
+    missing: Vec<String>,
+    refused: Vec<String>,
+    /// Skipped because another process had them locked (`skip_locked`).
+    in_use: Vec<String>,
+    /// Trashed paths that git tracks (only checked with `RMCP_TRASH_GIT_WARNINGS`).
+    git_tracked: Vec<String>,
+    /// Trashed files with other hard links, whose data stays on disk (Unix only).
+    hard_linked: Vec<String>,
+    /// Every entry not trashed, with the reason.
+    skipped: Vec<SkippedEntry>,
+    /// Paths of a batch that failed to trash, with the `error`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    failed: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    /// Why nothing was trashed, when nothing was.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    outcome: Option<Outcome>,
+    timing: BatchTiming,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    restore_script: Option<RestoreScriptReport>,
+    /// One-line outcome for display, when `summary` was requested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    summary: Option<String>,
+    /// Undo group the items were recorded in, when `group` was given.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    group: Option<String>,
+}
+
+/// Outcome of `emit_restore_script`.
+#[derive(Debug, Serialize)]
+struct RestoreScriptReport {
+    path: String,
+    /// Items the script restores.
+    items: usize,
+    /// Trashed paths the script leaves out because their trash entry wasn't found.
+    unmatched: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum EntryStatus {
+    Trashed,
+    Missing,
+    Refused,
+    InUse,
+    /// A symlink to a directory, with `symlink_dirs: skip`.
+    SymlinkDir,
+    Failed,
+}
+
+impl EntryStatus {
+    /// How the status reads in a tool's text answer.
+    fn label(self) -> &'static str {
+        match self {
+            Self::Trashed => "trashed",
+            Self::Missing => "not found",
+            Self::Refused => "refused",
+            Self::InUse => "in use",
+            Self::SymlinkDir => "symlink to a directory",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct EntryResult {
+    path: String,
+    status: EntryStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct TrashFilesFromFileResult {
+    file: String,
+    items: Vec<EntryResult>,
+    trashed: usize,
+    timing: BatchTiming,
+    /// One-line outcome for display, when `summary` was requested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    summary: Option<String>,
+    /// Undo group the items were recorded in, when `group` was given.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    group: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct PolicyMatch {
+    path: String,
+    size_bytes: u64,
+    age_days: u64,
+}
+
+#[derive(Debug, Serialize)]
+struct PolicyResult {
+    dry_run: bool,
+    matched: Vec<PolicyMatch>,
+    total_bytes: u64,
+    trashed: usize,
+    refused: Vec<String>,
+    /// One-line outcome for display, when `summary` was requested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    summary: Option<String>,
+    /// Where the directory walk stopped short, if it did.
+    #[serde(skip_serializing_if = "walk::WalkLimits::is_empty")]
+    walk: walk::WalkLimits,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum FoundStatus {
+    Trashed,
+    WouldTrash,
+    Refused,
+    Failed,
+}
+
+impl FoundStatus {
+    /// How the status reads in a tool's text answer.
+    fn label(self) -> &'static str {
+        match self {
+            Self::Trashed => "trashed",
+            Self::WouldTrash => "would trash",
+            Self::Refused => "refused",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+/// The `summary` line for found items, given each one's status and size
+/// (where known).
+fn found_summary(items: impl IntoIterator<Item = (FoundStatus, Option<u64>)>, dry_run: bool) -> String {
+    let (mut moved, mut skipped) = (Vec::new(), 0);
+    for (status, size) in items {
+        match status {
+            FoundStatus::Trashed | FoundStatus::WouldTrash => moved.push(size),
+            FoundStatus::Refused | FoundStatus::Failed => skipped += 1,
+        }
+    }
+    format::trash_summary(moved.len(), known_total_size(moved.iter().copied()), skipped, dry_run)
+}
+
+#[derive(Debug, Serialize)]
+struct FoundItem {
+    path: String,
+    size_bytes: u64,
+    age_days: u64,
+    status: FoundStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct FindAndTrashResult {
+    dry_run: bool,
+    items: Vec<FoundItem>,
+    matched: usize,
+    trashed: usize,
+    trashed_bytes: u64,
+    /// One-line outcome for display, when `summary` was requested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    summary: Option<String>,
+    /// Where the directory walk stopped short, if it did.
+    #[serde(skip_serializing_if = "walk::WalkLimits::is_empty")]
+    walk: walk::WalkLimits,
+}
+
+#[derive(Debug, Serialize)]
+struct FilteredItem {
+    path: String,
+    size_bytes: u64,
+    status: FoundStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct TrashFilteredResult {
+    dry_run: bool,
+    /// Files matching an include pattern and no exclude pattern.
+    matched: usize,
+    /// Files matching an include pattern that an exclude pattern kept.
+    excluded: usize,
+    trashed: usize,
+    trashed_bytes: u64,
+    items: Vec<FilteredItem>,
+    /// One-line outcome for display, when `summary` was requested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    summary: Option<String>,
+    /// Where the directory walk stopped short, if it did.
+    #[serde(skip_serializing_if = "walk::WalkLimits::is_empty")]
+    walk: walk::WalkLimits,
+}
+
+#[derive(Debug, Serialize)]
+struct JunkItem {
+    path: String,
+    is_dir: bool,
+    /// Size of a matched file; directories aren't measured.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    size_bytes: Option<u64>,
+    status: FoundStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct JunkResult {
+    dry_run: bool,
+    patterns: Vec<String>,
+    items: Vec<JunkItem>,
+    trashed: usize,
+    /// Bytes of the trashed files, not counting directories.
+    trashed_bytes: u64,
+    /// One-line outcome for display, when `summary` was requested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    summary: Option<String>,
+    /// Where the directory walk stopped short, if it did.
+    #[serde(skip_serializing_if = "walk::WalkLimits::is_empty")]
+    walk: walk::WalkLimits,
+}
+
+#[derive(Debug, Serialize)]
+struct PlannedFile {
+    path: String,
+    size_bytes: u64,
+    age_days: u64,
+}
+
+#[derive(Debug, Serialize)]
+struct CleanupPlan {
+    dir: String,
+    free_bytes: u64,
+    target_free_bytes: u64,
+    /// How much has to be freed to reach the target.
+    needed_bytes: u64,
+    files: Vec<PlannedFile>,
+    planned_bytes: u64,
+    /// Whether trashing (and then emptying) `files` would reach the target.
+    goal_met: bool,
+    /// Protected files left out of the plan.
+    protected_skipped: usize,
+    /// Where the directory walk stopped short, if it did.
+    #[serde(skip_serializing_if = "walk::WalkLimits::is_empty")]
+    walk: walk::WalkLimits,
+}
+
+#[derive(Debug, Serialize)]
+struct ProtectionReport {
+    path: String,
+    resolved_path: String,
+    protected: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    matched: Option<guard::ProtectedMatch>,
+}
+
+#[derive(Debug, Serialize)]
+struct TrashLocation {
+    path: String,
+    exists: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct TrashLocations {
+    /// `system` for the OS trash, `custom` for `RMCP_TRASH_DIR`.
+    backend: &'static str,
+    locations: Vec<TrashLocation>,
+}
+
+/// A directory's size as recorded by `snapshot_usage`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageSnapshot {
+    label: String,
+    dir: String,
+    files: usize,
+    bytes: u64,
+    /// Unix time the snapshot was taken.
+    taken_at: i64,
+    /// Where the directory walk stopped short, if it did.
+    #[serde(default, skip_serializing_if = "walk::WalkLimits::is_empty")]
+    walk: walk::WalkLimits,
+}
+
+#[derive(Debug, Serialize)]
+struct UsageDiff {
+    label: String,
+    dir: String,
+    taken_at: i64,
+    before_files: usize,
+    before_bytes: u64,
+    after_files: usize,
+    after_bytes: u64,
+    /// Bytes freed since the snapshot; negative if the directory grew.
+    freed_bytes: i64,
+    /// The directory no longer exists, so everything in it counts as freed.
+    dir_missing: bool,
+    /// Where the directory walk stopped short, if it did.
+    #[serde(skip_serializing_if = "walk::WalkLimits::is_empty")]
+    walk: walk::WalkLimits,
+}
+
+#[derive(Debug, Serialize)]
+struct DiskSpaceReport {
+    path: String,
+    #[serde(flatten)]
+    space: disk::DiskSpace,
+}
+
+// === Helpers ===
+
+/// Cut `text` down to at most `limit` characters, appending a continuation hint.
+fn truncate_output(text: String, limit: Option<usize>, has_structured: bool) -> String {
+    let Some(limit) = limit else {
+        return text;
+    };
+    let Some((cut, _)) = text.char_indices().nth(limit) else {
+        return text;
+    };
+
+    let mut truncated = text[..cut].to_string();
+    if has_structured {
+        truncated.push_str("\n...(truncated, use structured output for full results)");
+    } else {
+        truncated.push_str(&format!("\n...(truncated at {} characters)", limit));
+    }
+    truncated
+}
+
+/// Names `trash_common_junk` matches unless the caller gives its own patterns.
+const JUNK_PATTERNS: &[&str] = &[".DS_Store", "Thumbs.db", "ehthumbs.db", "*.tmp", "*.pyc", "__pycache__", ".pytest_cache"];
+
+/// Entries in `dir` (and below it, if `recursive`) whose name matches one of
+/// `patterns`, without following symlinks. Matched directories are taken
+/// whole; `.git` and `node_modules` are never entered, and `node_modules`
+/// only matches with `node_modules`.
+fn junk_in(
+    dir: &Path,
+    patterns: &[pattern::Pattern],
+    node_modules: bool,
+    recursive: bool,
+    max_depth: usize,
+    progress: &mut progress::Progress,
+) -> std::io::Result<(Vec<(PathBuf, std::fs::Metadata)>, walk::WalkLimits)> {
+    let mut out = Vec::new();
+    let limits = walk::walk(dir, max_depth, |path, meta| {
+        if meta.is_file() {
+            progress.file_scanned();
+        }
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        if meta.is_dir() && name.eq_ignore_ascii_case("node_modules") {
+            if node_modules {
+                out.push((path.to_path_buf(), meta.clone()));
+            }
+        } else if patterns.iter().any(|p| p.matches(&name)) {
+            out.push((path.to_path_buf(), meta.clone()));
+        } else if meta.is_dir() && recursive && name != ".git" {
+            return walk::Visit::Descend;
+        }
+        walk::Visit::Skip
+    })?;
+    Ok((out, limits))
+}
+
+/// Regular files in `dir` (and below it, if `recursive`), without following symlinks.
+fn files_in(
+    dir: &Path,
+    recursive: bool,
+    max_depth: usize,
+    progress: &mut progress::Progress,
+) -> std::io::Result<(Vec<(PathBuf, std::fs::Metadata)>, walk::WalkLimits)> {
+    let mut out = Vec::new();
+    let limits = walk::walk(dir, max_depth, |path, meta| {
+        if meta.is_file() {
+            progress.file_scanned();
+            out.push((path.to_path_buf(), meta.clone()));
+        }
+        if recursive {
+            walk::Visit::Descend
+        } else {
+            walk::Visit::Skip
+        }
+    })?;
+    Ok((out, limits))
+}
+
+/// The number of regular files below `dir` and their total size.
+///
+/// Symlinks are not followed.
+fn tree_size(
+    dir: &Path,
+    max_depth: usize,
+    progress: &mut progress::Progress,
+) -> std::io::Result<(usize, u64, walk::WalkLimits)> {
+    let (mut files, mut bytes) = (0, 0);
+    let limits = walk::walk(dir, max_depth, |_, meta| {
+        if meta.is_file() {
+            progress.file_scanned();
+            files += 1;
+            bytes += meta.len();
+        }
+        walk::Visit::Descend
+    })?;
+    Ok((files, bytes, limits))
+}
+
+/// Trash `path` in `backend`, or stage it in `staging` if there is one.
+///
+/// A file that can't be staged (e.g. it's on another filesystem) is
+/// trashed directly.
+fn send_to_trash(backend: &Backend, staging: Option<&staging::Staging>, path: &Path) -> Result<(), trash::Error> {
+    if let Some(staging) = staging {
+        match staging.stage(path) {
+            Ok(()) => return Ok(()),
+            Err(e) => tracing::warn!("Could not stage {}, trashing directly: {}", path.display(), e),
+        }
+    }
+    backend.delete(path)
+}
+
+/// Run blocking filesystem work, such as a walk, on tokio's blocking pool.
+///
+/// Unlike `block_in_place` this works on any runtime, including the
+/// single-threaded one, but `work` has to own what it uses.
+async fn blocking<T: Send + 'static>(work: impl FnOnce() -> T + Send + 'static) -> T {
+    match tokio::task::spawn_blocking(work).await {
+        Ok(value) => value,
+        Err(e) => std::panic::resume_unwind(e.into_panic()),
+    }
+}
+
+/// Whole days since the `basis` timestamp in `meta`, or 0 if it's unavailable.
+fn age_days(meta: &std::fs::Metadata, basis: TimeBasis, now: std::time::SystemTime) -> u64 {
+    let stamp = match basis {
+        TimeBasis::Modified => meta.modified(),
+        TimeBasis::Accessed => meta.accessed(),
+    };
+    stamp
+        .ok()
+        .and_then(|t| now.duration_since(t).ok())
+        .map(|age| age.as_secs() / 86_400)
+        .unwrap_or(0)
+}
+
+/// Sum of sizes, or `None` if any of them is unknown (e.g. a directory).
+fn known_total_size(sizes: impl IntoIterator<Item = Option<u64>>) -> Option<u64> {
+    sizes.into_iter().sum()
+}
+
+// === Server ===
+
+/// `router` with every tool renamed to `prefix` followed by its name.
+fn prefixed(router: ToolRouter<TrashServer>, prefix: &str) -> ToolRouter<TrashServer> {
+    let mut renamed = ToolRouter::new();
+    for mut route in router {
+        route.attr.name = format!("{}{}", prefix, route.attr.name).into();
+        renamed.add_route(route);
+    }
+    renamed
+}
+
+#[derive(Debug)]
+pub struct TrashServer {
+    pub tool_router: ToolRouter<Self>,
+    pub config: Config,
+    pub backend: Backend,
+    pub protected: guard::ProtectedPaths,
+    pub profiles: HashMap<String, Profile>,
+    pub log_sink: Arc<ClientLogSink>,
+    pub stats: stats::Stats,
+    pub staging: Option<Arc<staging::Staging>>,
+    /// Calls left to mutating tools under `RMCP_TRASH_RATE_LIMIT`.
+    pub rate_limiter: Option<std::sync::Mutex<ratelimit::TokenBucket>>,
+    /// Tools removed from the router by `RMCP_TRASH_ENABLED_TOOLS` and
+    /// `RMCP_TRASH_DISABLED_TOOLS`, answered with a notice instead. Named
+    /// without `RMCP_TRASH_TOOL_PREFIX`.
+    pub disabled_tools: std::collections::HashSet<String>,
+    /// Paths put back by the latest restore that restored anything, for
+    /// `redo_last_restore`.
+    pub last_restore: std::sync::Mutex<Vec<PathBuf>>,
+    /// Trashed paths by undo group, for `restore_group` and `purge_group`.
+    pub undo_groups: groups::UndoGroups,
+    /// Directory sizes recorded by `snapshot_usage`, by label.
+    pub usage_snapshots: std::sync::Mutex<HashMap<String, UsageSnapshot>>,
+    /// Where the three fields above are saved (`RMCP_TRASH_STATE_FILE`).
+    pub state_file: Option<state::StateFile>,
+    /// Self-test run at startup, when the trash could be probed without
+    /// leaving anything behind.
+    pub startup_probe: Option<selftest::SelfTestReport>,
+    /// Periodic purge of old items (`RMCP_TRASH_SCHEDULE`), when running.
+    #[cfg(feature = "full")]
+    pub scheduler: Option<Arc<schedule::Scheduler>>,
+    /// Run by `trash_file` between its checks and the trash call, so tests
+    /// can change the filesystem inside that window.
+    #[cfg(test)]
+    pub before_trash: Option<fn(&Path)>,
+}
+
+impl Default for TrashServer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TrashServer {
+    pub fn new() -> Self {
+        Self::with_config(Config::default(), Backend::System)
+    }
+
+    pub fn with_config(config: Config, backend: Backend) -> Self {
+        let mut tool_router = Self::tool_router();
+        #[cfg(feature = "full")]
+        {
+            tool_router += Self::listing_router();
+        }
+        let known: Vec<String> = tool_router.list_all().into_iter().map(|tool| tool.name.into_owned()).collect();
+        for name in config.enabled_tools.iter().flatten().chain(&config.disabled_tools) {
+            if !known.contains(name) {
+                tracing::warn!("Ignoring unknown tool {} in the enabled/disabled tool lists", name);
+            }
+        }
+        let disabled_tools: std::collections::HashSet<String> =
+            known.into_iter().filter(|name| !config.tool_enabled(name)).collect();
+        for name in &disabled_tools {
+            tool_router.remove_route(name);
+        }
+        if let Some(prefix) = &config.tool_prefix {
+            tool_router = prefixed(tool_router, prefix);
+        }
+        let rate_limiter = config
+            .rate_limit
+            .map(|limit| std::sync::Mutex::new(ratelimit::TokenBucket::new(limit)));
+        Self {
+            tool_router,
+            rate_limiter,
+            disabled_tools,
+            last_restore: Default::default(),
+            usage_snapshots: Default::default(),
+            undo_groups: Default::default(),
+            state_file: None,
+            startup_probe: None,
+            #[cfg(feature = "full")]
+            scheduler: None,
+            #[cfg(test)]
+            before_trash: None,
+            config,
+            backend,
+            protected: guard::ProtectedPaths::builtin(),
+            profiles: HashMap::new(),
+            log_sink: Arc::default(),
+            stats: stats::Stats::default(),
+            staging: None,
+        }
+    }
+
+    pub fn with_protected_paths(mut self, protected: guard::ProtectedPaths) -> Self {
+        self.protected = protected;
+        self
+    }
+
+    pub fn with_profiles(mut self, profiles: HashMap<String, Profile>) -> Self {
+        self.profiles = profiles;
+        self
+    }
+
+    /// Share a log sink whose layer is installed in the tracing subscriber.
+    pub fn with_log_sink(mut self, log_sink: Arc<ClientLogSink>) -> Self {
+        self.log_sink = log_sink;
+        self
+    }
+
+    /// Record the self-test run at startup, reported by `capabilities`.
+    pub fn with_startup_probe(mut self, report: selftest::SelfTestReport) -> Self {
+        self.startup_probe = Some(report);
+        self
+    }
+
+    /// Report on a scheduled cleanup started with `schedule::spawn`.
+    #[cfg(feature = "full")]
+    pub fn with_scheduler(mut self, scheduler: Arc<schedule::Scheduler>) -> Self {
+        self.scheduler = Some(scheduler);
+        self
+    }
+
+    /// Carry the session state over from `file` and save it there after
+    /// every change (`RMCP_TRASH_STATE_FILE`).
+    pub fn with_state_file(mut self, file: state::StateFile) -> Self {
+        let state = file.load();
+        tracing::info!(
+            "Session state from {}: {} undo group(s), {} usage snapshot(s), {} path(s) to redo",
+            file.path().display(),
+            state.undo_groups.len(),
+            state.usage_snapshots.len(),
+            state.last_restore.len()
+        );
+        self.last_restore = std::sync::Mutex::new(state.last_restore);
+        self.undo_groups = groups::UndoGroups::from_map(state.undo_groups);
+        self.usage_snapshots = std::sync::Mutex::new(state.usage_snapshots);
+        self.state_file = Some(file);
+        self
+    }
+
+    /// Route trashed files through a staging area (`RMCP_TRASH_STAGING_DIR`).
+    pub fn with_staging(mut self, staging: Arc<staging::Staging>) -> Self {
+        self.staging = Some(staging);
+        self
+    }
+
+    /// Result with truncated text plus the complete structured payload,
+    /// stamped with [`SCHEMA_VERSION`].
+    ///
+    /// When the two together exceed `RMCP_TRASH_MAX_RESPONSE_BYTES`, the text
+    /// is replaced by a note and the payload marked `text_omitted`.
+    fn structured_result(&self, text: impl Into<String>, data: impl Serialize) -> CallToolResult {
+        let mut text = truncate_output(text.into(), self.config.max_output_chars, true);
+        let mut structured = serde_json::to_value(data).ok().map(|value| match value {
+            serde_json::Value::Object(mut fields) => {
+                fields.insert("schema_version".into(), SCHEMA_VERSION.into());
+                serde_json::Value::Object(fields)
+            }
+            other => serde_json::json!({ "schema_version": SCHEMA_VERSION, "data": other }),
+        });
+        if let (Some(limit), Some(serde_json::Value::Object(fields))) = (self.config.max_response_bytes, &mut structured) {
+            let size = text.len() + serde_json::to_string(&fields).map_or(0, |json| json.len());
+            if size > limit {
+                tracing::debug!("Response of {} bytes over the {} byte limit, omitting its text", size, limit);
+                text = format!(
+                    "Response too large ({} bytes, limit {} set by RMCP_TRASH_MAX_RESPONSE_BYTES): \
+                     text omitted, the full results are in the structured content",
+                    size, limit
+                );
+                fields.insert("text_omitted".into(), true.into());
+            }
+        }
+        let mut result = CallToolResult::success(vec![Content::text(text)]);
+        result.structured_content = structured;
+        result
+    }
+
+    /// A plain-message result whose structured payload is the message and
+    /// its [`Outcome`].
+    pub(crate) fn notice(&self, outcome: Outcome, text: impl Into<String>) -> CallToolResult {
+        let message = text.into();
+        self.structured_result(message.clone(), Notice { outcome, message })
+    }
+
+    /// The scope for a call: the named profile, or the server-wide rules.
+    fn scope(&self, profile: Option<&str>) -> Result<Scope<'_>, McpError> {
+        match profile {
+            None => Ok(Scope {
+                name: None,
+                base_dir: None,
+                protected: &self.protected,
+            }),
+            Some(name) => match self.profiles.get_key_value(name) {
+                Some((name, profile)) => Ok(Scope {
+                    name: Some(name),
+                    base_dir: Some(&profile.base_dir),
+                    protected: &profile.protected,
+                }),
+                None => {
+                    let mut known: Vec<&str> = self.profiles.keys().map(String::as_str).collect();
+                    known.sort_unstable();
+                    Err(McpError::invalid_params(
+                        format!("Unknown profile '{}' (known: {})", name, known.join(", ")),
+                        None,
+                    ))
+                }
+            },
+        }
+    }
+
+    #[cfg(not(feature = "full"))]
+    async fn confirm_in_trash(&self, _original: &Path, _since: std::time::SystemTime) -> String {
+        "Not confirmed: this build can't list the trash".into()
+    }
+
+    #[cfg(not(feature = "full"))]
+    fn put_back_replaced(&self, _original: &Path, _since: std::time::SystemTime) -> Result<(), String> {
+        Err("this build can't restore from the trash".into())
+    }
+
+    #[cfg(not(feature = "full"))]
+    fn write_restore_script(
+        &self,
+        _script: &Path,
+        _originals: &[PathBuf],
+        _since: std::time::SystemTime,
+    ) -> Result<(usize, Vec<PathBuf>), String> {
+        Err("this build can't list the trash".into())
+    }
+
+    /// Trash each of `found` (a path and its size, where known), or on a dry
+    /// run only check it against the protected paths, for the tools that
+    /// search a directory. Returns the status and detail of each, in order,
+    /// with how many were trashed and their total size.
+    fn trash_found<'a>(
+        &self,
+        scope: &Scope,
+        found: impl IntoIterator<Item = (&'a Path, Option<u64>)>,
+        dry_run: bool,
+    ) -> (Vec<(FoundStatus, Option<String>)>, usize, u64) {
+        let mut outcomes = Vec::new();
+        let (mut trashed, mut bytes) = (0, 0);
+        for (path, size) in found {
+            let outcome = if let Some(reason) = self.refusal(scope, path, None) {
+                if !dry_run {
+                    self.stats.record_refused(1);
+                }
+                (FoundStatus::Refused, Some(reason))
+            } else if dry_run {
+                (FoundStatus::WouldTrash, None)
+            } else {
+                match self.send_to_trash(path) {
+                    Ok(()) => {
+                        trashed += 1;
+                        bytes += size.unwrap_or(0);
+                        (FoundStatus::Trashed, None)
+                    }
+                    Err(e) => {
+                        tracing::warn!("Failed to trash {}: {}", path.display(), e);
+                        self.stats.record_error(&e);
+                        (FoundStatus::Failed, Some(errors::trash_failure_message(Some(path), &e)))
+                    }
+                }
+            };
+            outcomes.push(outcome);
+        }
+        if trashed > 0 {
+            self.stats.record_batch();
+            self.stats.record_trashed(trashed, bytes);
+        }
+        (outcomes, trashed, bytes)
+    }
+
+    /// Trash `path`, or stage it when staging is enabled.
+    fn send_to_trash(&self, path: &Path) -> Result<(), trash::Error> {
+        send_to_trash(&self.backend, self.staging.as_deref(), path)
+    }
+
+    /// The `trash_file` answer for a path that isn't there, per `on_missing`.
+    /// `vanished` is for a path that existed when checked but was gone by
+    /// the time it was trashed.
+    fn missing(&self, on_missing: OnMissing, path: &str, vanished: bool) -> CallToolResult {
+        let message = match (on_missing, vanished) {
+            (OnMissing::Ignore, false) => format!("Already gone: {}", path),
+            (OnMissing::Ignore, true) => {
+                format!("Already gone: {} was removed by another process before it could be trashed", path)
+            }
+            (_, false) => format!("Path does not exist: {}", path),
+            (_, true) => format!(
+                "Path disappeared before it could be trashed: {} existed when checked but was removed or moved by another process",
+                path
+            ),
+        };
+        if on_missing == OnMissing::Error {
+            let mut result = self.notice(Outcome::Failed, message);
+            result.is_error = Some(true);
+            result
+        } else {
+            self.notice(Outcome::NothingToDo, message)
+        }
+    }
+
+    /// Write the session state to `RMCP_TRASH_STATE_FILE`, if one is set.
+    pub(crate) fn save_state(&self) {
+        if let Some(file) = &self.state_file {
+            file.save(|| state::SessionState {
+                last_restore: self.last_restore.lock().unwrap().clone(),
+                undo_groups: self.undo_groups.to_map(),
+                usage_snapshots: self.usage_snapshots.lock().unwrap().clone(),
+            });
+        }
+    }
+
+    /// Record `originals` in the undo `group`, if there is one, and return
+    /// the text line saying so.
+    fn record_group(&self, group: Option<&str>, originals: Vec<PathBuf>) -> String {
+        let Some(group) = group.filter(|_| !originals.is_empty()) else {
+            return String::new();
+        };
+        let count = originals.len();
+        self.undo_groups.record(group, originals);
+        self.save_state();
+        format!(
+            "\nRecorded {} item(s) in undo group '{}'; restore_group or purge_group act on the whole group",
+            count, group
+        )
+    }
+
+    /// [`Self::send_to_trash`] for several paths, as one trash call when not staging.
+    fn send_all_to_trash<T: AsRef<Path>>(&self, paths: &[T]) -> Result<(), trash::Error> {
+        if self.staging.is_none() {
+            return self.backend.delete_all(paths);
+        }
+        paths.iter().try_for_each(|path| self.send_to_trash(path.as_ref()))
+    }
+
+    /// The response for a mutating call over `RMCP_TRASH_RATE_LIMIT`, or
+    /// `None` (taking a token) if it may go ahead.
+    fn rate_limited(&self, tool: &str) -> Option<CallToolResult> {
+        let mut bucket = self.rate_limiter.as_ref()?.lock().unwrap();
+        let wait = bucket.try_take().err()?;
+        tracing::warn!("Rate limited {} call", tool);
+        Some(self.notice(
+            Outcome::RateLimited,
+            format!(
+                "Rate limited, slow down: {} was not run. Calls that change files are limited to {} \
+                 (RMCP_TRASH_RATE_LIMIT); retry in {} s",
+                tool,
+                bucket.limit(),
+                wait.as_secs_f64().ceil()
+            ),
+        ))
+    }
+
+    /// Reject path arguments longer than the configured limit before they
+    /// reach the filesystem.
+    fn check_path_len(&self, input: &str) -> Result<(), McpError> {
+        if input.len() > self.config.max_path_len {
+            return Err(McpError::invalid_params(
+                format!(
+                    "Path argument is {} bytes long; the limit is {} (RMCP_TRASH_MAX_PATH_LEN)",
+                    input.len(),
+                    self.config.max_path_len
+                ),
+                None,
+            ));
+        }
+        Ok(())
+    }
+
+    /// Under `RMCP_TRASH_REQUIRE_ABSOLUTE`, reject a relative path unless the
+    /// scope has a base directory to anchor it.
+    fn check_absolute(&self, scope: &Scope, input: &str) -> Result<(), McpError> {
+        if self.config.require_absolute && scope.base_dir.is_none() && paths::expand(input).is_relative() {
+            return Err(McpError::invalid_params(
+                format!(
+                    "Relative path '{}' rejected: this server requires absolute paths (RMCP_TRASH_REQUIRE_ABSOLUTE). \
+                     Pass the full path, or use a profile with a base directory",
+                    input
+                ),
+                None,
+            ));
+        }
+        Ok(())
+    }
+
+    /// Why `path` must not be trashed, unless the caller forced it and the
+    /// server was started with `--allow-force`. Leaving the profile's base
+    /// directory can't be forced.
+    ///
+    /// Only a check: callers that refuse a change because of it count the
+    /// refusal with `stats.record_refused`, previews don't.
+    fn refusal(&self, scope: &Scope, path: &Path, force: Option<bool>) -> Option<String> {
+        self.refusal_reason(scope, path, force).map(|(_, message)| message)
+    }
+
+    /// [`Self::refusal`], with the kind of rule that applied.
+    fn refusal_reason(&self, scope: &Scope, path: &Path, force: Option<bool>) -> Option<(SkipReason, String)> {
+        let resolved = paths::resolve(path);
+        if !scope.contains(&resolved) {
+            return Some((
+                SkipReason::OutsideProfile,
+                format!(
+                    "{} is outside the base directory of profile '{}'",
+                    resolved.display(),
+                    scope.name.unwrap_or_default()
+                ),
+            ));
+        }
+
+        if self.config.allow_force && force == Some(true) {
+            return None;
+        }
+        let hint = if self.config.allow_force {
+            "pass force: true to override"
+        } else {
+            "overriding requires starting the server with --allow-force and passing force: true"
+        };
+
+        let matched = scope.protected.check(&resolved)?;
+        Some(match matched.source {
+            guard::RuleSource::MountPoint => (SkipReason::MountPoint, format!(
+                "{} is a mount point. Trashing the root of a mounted filesystem would try to \
+                 move the whole volume and is almost always a mistake; trash the files inside \
+                 it instead ({})",
+                path.display(),
+                hint
+            )),
+            guard::RuleSource::WorkingDirectory => (SkipReason::WorkingDirectory, format!(
+                "{} contains the server's working directory {}. Trashing it would break \
+                 resolution of relative paths for the rest of the session ({})",
+                path.display(),
+                matched.rule,
+                hint
+            )),
+            source => (SkipReason::Protected, format!(
+                "{} is protected by {} '{}' ({})",
+                path.display(),
+                source.label(),
+                matched.rule,
+                hint
+            )),
+        })
+    }
+
+}
+
+#[rmcp::tool_router]
+impl TrashServer {
+    #[rmcp::tool(description = "Move a file or directory to the system trash/recycle bin")]
+    pub async fn trash_file(
+        &self,
+        Parameters(params): Parameters<TrashFileParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let scope = self.scope(params.profile.as_deref())?;
+        let group = groups::validate(params.group.as_deref()).map_err(|e| McpError::invalid_params(e, None))?;
+
+        if let Some(limited) = self.rate_limited("trash_file") {
+            return Ok(limited);
+        }
+
+        if self.config.require_path_confirm {
+            match params.confirm_path.as_deref() {
+                None => {
+                    return Ok(self.notice(
+                        Outcome::Refused,
+                        "Refusing to trash: this server requires `confirm_path` to repeat `path` exactly"
+                    ));
+                }
+                Some(confirm) if confirm != params.path => {
+                    return Ok(self.notice(Outcome::Refused, format!(
+                        "Refusing to trash: confirm_path '{}' does not match path '{}'",
+                        confirm, params.path
+                    )));
+                }
+                Some(_) => {}
+            }
+        }
+
+        self.check_path_len(&params.path)?;
+        self.check_absolute(&scope, &params.path)?;
+        let path = scope.path(&params.path);
+
+        let on_missing = params.on_missing.unwrap_or_default();
+        if !path.exists() {
+            return Ok(self.missing(on_missing, &params.path, false));
+        }
+
+        if params.require_file == Some(true) && path.symlink_metadata().is_ok_and(|meta| meta.is_dir()) {
+            return Ok(self.notice(Outcome::Refused, format!(
+                "Refusing to trash: {} is a directory and require_file is set. \
+                 Call again without require_file to trash the whole directory, or use trash_files with recursive: true for its contents",
+                params.path
+            )));
+        }
+
+        let checked = guard::Identity::of(&path);
+        if let Some(reason) = self.refusal(&scope, &path, params.force) {
+            self.stats.record_refused(1);
+            return Ok(self.notice(
+                Outcome::Refused,
+                format!("Refusing to trash: {}", reason)
+            ));
+        }
+
+        let resolved = paths::resolve(&path);
+        let other_links = guard::other_links(&path);
+        let git_tracked = self.config.git_warnings && git::is_tracked(&resolved);
+        let started = std::time::SystemTime::now();
+        let is_file = std::fs::symlink_metadata(&path).ok().filter(|meta| meta.is_file());
+        let bytes = is_file.as_ref().map_or(0, |meta| meta.len());
+        let sha256 = match (params.checksum == Some(true), &is_file) {
+            (true, Some(_)) => match hash::sha256_file(&path) {
+                Ok(sha256) => Some(sha256),
+                Err(e) => {
+                    return Ok(self.notice(
+                        Outcome::Failed,
+                        format!("Not trashed: could not read {} to compute its checksum: {}", params.path, e),
+                    ));
+                }
+            },
+            _ => None,
+        };
+        if !guard::Identity::unchanged(checked, &path) {
+            if vanished(&path) {
+                return Ok(self.missing(on_missing, &params.path, true));
+            }
+            return Ok(self.notice(Outcome::Refused, format!(
+                "Refusing to trash: {} was replaced or moved after it was checked; call again to trash what is there now",
+                params.path
+            )));
+        }
+        #[cfg(test)]
+        if let Some(hook) = self.before_trash {
+            hook(&path);
+        }
+        match self.send_to_trash(&path) {
+            Ok(()) => {
+                tracing::info!("Trashed {}", path.display());
+                self.stats.record_trashed(1, bytes);
+                let mut msg = format!("Moved to trash: {}", params.path);
+                match &sha256 {
+                    Some(sha256) => msg.push_str(&format!("\nSHA-256: {}", sha256)),
+                    None if params.checksum == Some(true) => {
+                        msg.push_str("\nNo checksum: not a regular file (directories and symlinks aren't hashed)")
+                    }
+                    None => {}
+                }
+                if git_tracked {
+                    msg.push('\n');
+                    msg.push_str(&git::tracked_note(&resolved));
+                }
+                if other_links > 0 {
+                    msg.push('\n');
+                    msg.push_str(&guard::hard_link_note(&path, other_links));
+                }
+                msg.push_str(&self.record_group(group, vec![resolved.clone()]));
+                let mut confirmation = None;
+                if let Some(staging) = &self.staging {
+                    msg.push_str(&format!(
+                        "\nStaged for {} s before it reaches the trash; flush_staging sends it now",
+                        staging.delay().as_secs()
+                    ));
+                } else if params.confirm_in_trash == Some(true) {
+                    let confirmed = self.confirm_in_trash(&resolved, started).await;
+                    msg.push('\n');
+                    msg.push_str(&confirmed);
+                    confirmation = Some(confirmed);
+                }
+                Ok(self.structured_result(
+                    msg,
+                    TrashFileResult {
+                        input_path: params.path,
+                        resolved_path: resolved.to_string_lossy().into_owned(),
+                        bytes,
+                        sha256,
+                        staged: self.staging.is_some(),
+                        git_tracked,
+                        other_links,
+                        confirmation,
+                        summary: (params.summary == Some(true))
+                            .then(|| format::trash_summary(1, is_file.as_ref().map(|_| bytes), 0, false)),
+                        group: group.map(str::to_owned),
+                    },
+                ))
+            }
+            Err(_) if vanished(&path) => {
+                tracing::info!("{} disappeared before it could be trashed", path.display());
+                Ok(self.missing(on_missing, &params.path, true))
+            }
+            Err(e) => {
+                tracing::warn!("Failed to trash {}: {}", path.display(), e);
+                self.stats.record_error(&e);
+                Ok(self.notice(
+                    Outcome::Failed,
+                    errors::trash_failure_message(Some(&path), &e)
+                ))
+            }
+        }
+    }
+
+    #[rmcp::tool(description = "Move a file or directory to the trash only if it hasn't been modified since a given time, e.g. a temp file nobody touched after it was created; otherwise it is left in place and reported as too new")]
+    pub async fn trash_if_older(
+        &self,
+        Parameters(params): Parameters<TrashIfOlderParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let scope = self.scope(params.profile.as_deref())?;
+        self.check_path_len(&params.path)?;
+
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs() as i64);
+        if params.modified_before < 0 {
+            return Err(McpError::invalid_params("`modified_before` must be a Unix time in seconds, not negative", None));
+        }
+        if params.modified_before > now {
+            return Err(McpError::invalid_params(
+                format!(
+                    "`modified_before` ({}) is in the future (now is {}); it is in seconds, not milliseconds",
+                    params.modified_before, now
+                ),
+                None,
+            ));
+        }
+
+        // Missing paths fall through to trash_file, which applies on_missing
+        let modified = scope
+            .path(&params.path)
+            .symlink_metadata()
+            .and_then(|meta| meta.modified())
+            .ok()
+            .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs() as i64);
+        if let Some(modified) = modified.filter(|&modified| modified > params.modified_before) {
+            return Ok(self.structured_result(
+                format!(
+                    "Not trashed (too new): {} was modified {} after the given time",
+                    params.path,
+                    format::format_duration((modified - params.modified_before) as u64)
+                ),
+                TooNewResult {
+                    path: params.path,
+                    status: "skipped_too_new",
+                    modified,
+                    modified_before: params.modified_before,
+                },
+            ));
+        }
+
+        self.trash_file(Parameters(TrashFileParams {
+            path: params.path,
+            confirm_path: params.confirm_path,
+            force: params.force,
+            confirm_in_trash: None,
+            require_file: None,
+            checksum: None,
+            on_missing: params.on_missing,
+            summary: None,
+            group: None,
+            profile: params.profile,
+        }))
+        .await
+    }
+
+    #[rmcp::tool(description = "Replace a file's content, keeping the old version recoverable: the new content is written to a temporary file next to it first, the old file is moved to the trash, and the new one is renamed into place. If any step fails the old file is left (or put back) where it was. Creates the file if it doesn't exist")]
+    pub async fn replace_file(
+        &self,
+        Parameters(params): Parameters<ReplaceFileParams>,
+    ) -> Result<CallToolResult, McpError> {
+        use base64::Engine;
+        use std::io::Write;
+
+        let scope = self.scope(params.profile.as_deref())?;
+        if let Some(limited) = self.rate_limited("replace_file") {
+            return Ok(limited);
+        }
+        self.check_path_len(&params.path)?;
+        self.check_absolute(&scope, &params.path)?;
+        let content = base64::engine::general_purpose::STANDARD
+            .decode(params.new_content_base64.trim())
+            .map_err(|e| McpError::invalid_params(format!("`new_content_base64` is not valid base64: {}", e), None))?;
+        let path = scope.path(&params.path);
+
+        let previous = std::fs::symlink_metadata(&path).ok();
+        if previous.as_ref().is_some_and(|meta| meta.is_dir()) {
+            return Ok(self.notice(Outcome::Refused, format!(
+                "Refusing to replace: {} is a directory; replace_file only replaces files",
+                params.path
+            )));
+        }
+        if let Some(reason) = self.refusal(&scope, &path, params.force) {
+            self.stats.record_refused(1);
+            return Ok(self.notice(Outcome::Refused, format!("Refusing to replace: {}", reason)));
+        }
+        let Some(name) = path.file_name() else {
+            return Err(McpError::invalid_params(format!("{} does not name a file", params.path), None));
+        };
+        let checked = guard::Identity::of(&path);
+
+        // Same directory, so the final rename can't cross filesystems
+        let temp = path.with_file_name(format!(
+            ".{}.rmcp-trash-{}.tmp",
+            name.to_string_lossy(),
+            std::process::id()
+        ));
+        let written = std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&temp)
+            .and_then(|mut file| {
+                file.write_all(&content)?;
+                if let Some(meta) = previous.as_ref().filter(|meta| meta.is_file()) {
+                    file.set_permissions(meta.permissions())?;
+                }
+                file.sync_all()
+            });
+        if let Err(e) = written {
+            let _ = std::fs::remove_file(&temp);
+            return Ok(self.notice(
+                Outcome::Failed,
+                format!("Not replaced: could not write the new content next to {}: {}", params.path, e),
+            ));
+        }
+
+        let resolved = paths::resolve(&path);
+        let started = std::time::SystemTime::now();
+        let previous_bytes = previous.as_ref().map(|meta| meta.len());
+        if previous.is_some() {
+            if !guard::Identity::unchanged(checked, &path) {
+                let _ = std::fs::remove_file(&temp);
+                return Ok(self.notice(Outcome::Refused, format!(
+                    "Refusing to replace: {} was replaced or moved after it was checked; call again to replace what is there now",
+                    params.path
+                )));
+            }
+            if let Err(e) = self.send_to_trash(&path) {
+                tracing::warn!("Failed to trash {} for replacement: {}", path.display(), e);
+                self.stats.record_error(&e);
+                let _ = std::fs::remove_file(&temp);
+                return Ok(self.notice(Outcome::Failed, format!(
+                    "Not replaced, the old file is untouched: {}",
+                    errors::trash_failure_message(Some(&path), &e)
+                )));
+            }
+            self.stats.record_trashed(1, previous_bytes.unwrap_or(0));
+        }
+
+        if let Err(e) = std::fs::rename(&temp, &path) {
+            tracing::warn!("Failed to move the replacement into {}: {}", path.display(), e);
+            let _ = std::fs::remove_file(&temp);
+            let rollback = match previous {
+                None => String::new(),
+                Some(_) => match self.put_back_replaced(&resolved, started) {
+                    Ok(()) => "; the old file was restored from the trash".into(),
+                    Err(reason) => {
+                        // Only point at a tool this build and platform actually offer
+                        let (place, how) = if self.staging.is_some() {
+                            ("the staging area", "move it back from there")
+                        } else if self.backend.supports_listing() {
+                            ("the trash", "restore it with restore_from_trash")
+                        } else {
+                            ("the trash", "restore it from the trash with the system's file manager")
+                        };
+                        format!("; the old file could not be put back ({}) and is in {}, {}", reason, place, how)
+                    }
+                },
+            };
+            return Ok(self.notice(
+                Outcome::Failed,
+                format!("Not replaced: could not move the new content into {}: {}{}", params.path, e, rollback),
+            ));
+        }
+
+        tracing::info!("Replaced {}", path.display());
+        let staged = previous.is_some() && self.staging.is_some();
+        let msg = match previous_bytes {
+            Some(bytes) => format!(
+                "Replaced {}: wrote {}, the previous version ({}) is {}",
+                params.path,
+                format::format_bytes(content.len() as u64),
+                format::format_bytes(bytes),
+                if staged { "staged for the trash" } else { "in the trash" }
+            ),
+            None => format!(
+                "Created {}: wrote {} (there was no previous version)",
+                params.path,
+                format::format_bytes(content.len() as u64)
+            ),
+        };
+        Ok(self.structured_result(
+            msg,
+            ReplaceFileResult {
+                path: params.path,
+                resolved_path: resolved.to_string_lossy().into_owned(),
+                previous_bytes,
+                bytes_written: content.len() as u64,
+                staged,
+            },
+        ))
+    }
+
+    #[rmcp::tool(description = "Move multiple files or directories to the system trash/recycle bin")]
+    pub async fn trash_files(
+        &self,
+        Parameters(params): Parameters<TrashFilesParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let scope = self.scope(params.profile.as_deref())?;
+        let group = groups::validate(params.group.as_deref()).map_err(|e| McpError::invalid_params(e, None))?;
+
+        if let Some(limited) = self.rate_limited("trash_files") {
+            return Ok(limited);
+        }
+
+        if params.paths.is_empty() {
+            return Err(McpError::invalid_params("No paths provided: `paths` is empty", None));
+        }
+
+        for entry in &params.paths {
+            self.check_path_len(entry.path())?;
+            self.check_absolute(&scope, entry.path())?;
+        }
+        if let Some(script) = &params.emit_restore_script {
+            self.check_path_len(script)?;
+            self.check_absolute(&scope, script)?;
+        }
+
+        // Check which paths exist, expanding recursive directory entries
+        let mut missing: Vec<&str> = Vec::new();
+        let mut skipped: Vec<SkippedEntry> = Vec::new();
+        let mut candidates: Vec<PathBuf> = Vec::new();
+        let skip = |path: &Path, reason, detail| SkippedEntry {
+            path: path.to_string_lossy().into_owned(),
+            reason,
+            detail,
+        };
+
+        for entry in &params.paths {
+            let path = scope.path(entry.path());
+            if !path.exists() {
+                missing.push(entry.path());
+                skipped.push(skip(Path::new(entry.path()), SkipReason::NotFound, None));
+            } else if entry.recursive() && path.symlink_metadata().is_ok_and(|meta| meta.is_dir()) {
+                match std::fs::read_dir(&path) {
+                    Ok(children) => candidates.extend(children.flatten().map(|c| c.path())),
+                    Err(e) => {
+                        return Ok(self.notice(
+                            Outcome::Failed,
+                            format!("Failed to read directory {}: {}", entry.path(), e)
+                        ));
+                    }
+                }
+            } else {
+                candidates.push(path);
+            }
+        }
+
+        // Different spellings of the same path would otherwise be trashed twice
+        let mut seen = std::collections::HashSet::new();
+        candidates.retain(|path| {
+            let first = seen.insert(paths::fold_case(&paths::resolve(path)));
+            if !first {
+                skipped.push(skip(path, SkipReason::Duplicate, None));
+            }
+            first
+        });
+
+        let mut refused: Vec<String> = Vec::new();
+        let mut in_use: Vec<String> = Vec::new();
+        let mut to_trash: Vec<PathBuf> = Vec::new();
+        let mut checked: Vec<Option<guard::Identity>> = Vec::new();
+        let skip_dir_links = params.symlink_dirs == Some(SymlinkDirs::Skip);
+        for path in candidates {
+            if skip_dir_links && is_dir_link(&path) {
+                skipped.push(skip(&path, SkipReason::SymlinkDir, None));
+                continue;
+            }
+            let identity = guard::Identity::of(&path);
+            match self.refusal_reason(&scope, &path, params.force) {
+                Some((reason, message)) => {
+                    self.stats.record_refused(1);
+                    skipped.push(skip(&path, reason, Some(message.clone())));
+                    refused.push(message);
+                }
+                None if params.skip_locked == Some(true) && guard::in_use(&path) => {
+                    skipped.push(skip(&path, SkipReason::InUse, None));
+                    in_use.push(path.to_string_lossy().into_owned());
+                }
+                None => {
+                    checked.push(identity);
+                    to_trash.push(path);
+                }
+            }
+        }
+
+        // Last look before acting: a path swapped since its checks is left alone
+        let mut kept = checked.into_iter();
+        to_trash.retain(|path| {
+            let unchanged = guard::Identity::unchanged(kept.next().flatten(), path);
+            if !unchanged {
+                let message = format!("{}: replaced or moved after it was checked", path.display());
+                skipped.push(skip(path, SkipReason::Changed, Some(message.clone())));
+                refused.push(message);
+            }
+            unchanged
+        });
+
+        // What was left out, reported whatever the outcome; with nothing to
+        // trash, the first reason heads the message instead
+        let refused_first = to_trash.is_empty() && !refused.is_empty();
+        let in_use_first = to_trash.is_empty() && refused.is_empty() && !in_use.is_empty();
+        let mut notes = String::new();
+        if !missing.is_empty() && params.report_missing != Some(false) {
+            notes.push_str(&format!("\nSkipped (not found): {}", missing.join(", ")));
+        }
+        if !refused.is_empty() && !refused_first {
+            notes.push_str(&format!("\nRefused:\n  {}", refused.join("\n  ")));
+        }
+        if !in_use.is_empty() && !in_use_first {
+            notes.push_str(&format!("\nSkipped (in use):\n  {}", in_use.join("\n  ")));
+        }
+        let dir_links: Vec<&str> = skipped
+            .iter()
+            .filter(|entry| entry.reason == SkipReason::SymlinkDir)
+            .map(|entry| entry.path.as_str())
+            .collect();
+        if !dir_links.is_empty() {
+            notes.push_str(&format!("\nSkipped (symlink to a directory):\n  {}", dir_links.join("\n  ")));
+        }
+
+        let summary = |moved, bytes| {
+            (params.summary == Some(true)).then(|| format::trash_summary(moved, bytes, skipped.len(), false))
+        };
+        let mut result = TrashFilesResult {
+            trashed: Vec::new(),
+            missing: missing.iter().map(|p| p.to_string()).collect(),
+            refused: Vec::new(),
+            in_use: Vec::new(),
+            git_tracked: Vec::new(),
+            hard_linked: Vec::new(),
+            summary: summary(0, None),
+            skipped: Vec::new(),
+            failed: Vec::new(),
+            error: None,
+            outcome: None,
+            timing: BatchTiming::new(std::time::Duration::ZERO, 0, None),
+            restore_script: None,
+            group: None,
+        };
+
+        if to_trash.is_empty() {
+            let (outcome, mut msg) = if refused_first {
+                (Outcome::Refused, format!("Refusing to trash:\n  {}", refused.join("\n  ")))
+            } else if in_use_first {
+                (Outcome::NothingToDo, format!("Nothing trashed; in use by another process:\n  {}", in_use.join("\n  ")))
+            } else {
+                (Outcome::NothingToDo, "No valid paths to trash".to_string())
+            };
+            msg.push_str(&notes);
+            result.outcome = Some(outcome);
+            result.refused = refused;
+            result.in_use = in_use;
+            result.skipped = skipped;
+            return Ok(self.structured_result(msg, result));
+        }
+
+        let bytes = known_total_size(to_trash.iter().map(|path| {
+            std::fs::symlink_metadata(path)
+                .ok()
+                .filter(|meta| !meta.is_dir())
+                .map(|meta| meta.len())
+        }));
+        let git_tracked: Vec<PathBuf> = if self.config.git_warnings {
+            to_trash.iter().map(|path| paths::resolve(path)).filter(|path| git::is_tracked(path)).collect()
+        } else {
+            Vec::new()
+        };
+        let hard_linked: Vec<(&PathBuf, u64)> = to_trash
+            .iter()
+            .map(|path| (path, guard::other_links(path)))
+            .filter(|&(_, links)| links > 0)
+            .collect();
+        let started = Instant::now();
+        let started_at = std::time::SystemTime::now();
+        match self.send_all_to_trash(&to_trash) {
+            Ok(()) => {
+                let timing = BatchTiming::new(started.elapsed(), to_trash.len(), bytes);
+                tracing::info!("Trashed {} items in {} ms", to_trash.len(), timing.elapsed_ms);
+                self.stats.record_batch();
+                self.stats.record_trashed(to_trash.len(), bytes.unwrap_or(0));
+                let mut msg = format!("Moved {} items to trash", to_trash.len());
+                msg.push_str(&notes);
+                for path in &git_tracked {
+                    msg.push('\n');
+                    msg.push_str(&git::tracked_note(path));
+                }
+                for &(path, links) in &hard_linked {
+                    msg.push('\n');
+                    msg.push_str(&guard::hard_link_note(path, links));
+                }
+                msg.push_str(&self.record_group(group, to_trash.iter().map(|path| paths::resolve(path)).collect()));
+                result.restore_script = params.emit_restore_script.as_deref().map(|script| {
+                    let script = scope.path(script);
+                    let originals: Vec<PathBuf> = to_trash.iter().map(|path| paths::resolve(path)).collect();
+                    let mut report = RestoreScriptReport {
+                        path: script.to_string_lossy().into_owned(),
+                        items: 0,
+                        unmatched: Vec::new(),
+                        error: None,
+                    };
+                    match self.write_restore_script(&script, &originals, started_at) {
+                        Ok((items, unmatched)) => {
+                            msg.push_str(&format!("\nRestore script for {} item(s) written to {}", items, script.display()));
+                            if !unmatched.is_empty() {
+                                msg.push_str(&format!(
+                                    "\nNot in the script (trash entry not found): {}",
+                                    unmatched.iter().map(|p| p.display().to_string()).collect::<Vec<_>>().join(", ")
+                                ));
+                            }
+                            report.items = items;
+                            report.unmatched = unmatched.iter().map(|p| p.to_string_lossy().into_owned()).collect();
+                        }
+                        Err(e) => {
+                            tracing::warn!("Could not write restore script {}: {}", script.display(), e);
+                            msg.push_str(&format!("\nNo restore script written: {}", e));
+                            report.error = Some(e);
+                        }
+                    }
+                    report
+                });
+                result.trashed = to_trash.iter().map(|p| p.to_string_lossy().into_owned()).collect();
+                result.git_tracked = git_tracked.iter().map(|p| p.to_string_lossy().into_owned()).collect();
+                result.hard_linked = hard_linked.iter().map(|(p, _)| p.to_string_lossy().into_owned()).collect();
+                result.summary = summary(to_trash.len(), bytes);
+                result.timing = timing;
+                result.group = group.map(str::to_owned);
+                result.refused = refused;
+                result.in_use = in_use;
+                result.skipped = skipped;
+                Ok(self.structured_result(msg, result))
+            }
+            Err(e) => {
+                tracing::warn!("Failed to trash batch of {} items: {}", to_trash.len(), e);
+                self.stats.record_error(&e);
+                let target = match to_trash.as_slice() {
+                    [only] => Some(only.as_path()),
+                    _ => None,
+                };
+                let mut msg = errors::trash_failure_message(target, &e);
+                msg.push_str(&notes);
+                result.outcome = Some(Outcome::Failed);
+                result.failed = to_trash.iter().map(|p| p.to_string_lossy().into_owned()).collect();
+                result.error = Some(e.to_string());
+                result.timing = BatchTiming::new(started.elapsed(), 0, None);
+                result.refused = refused;
+                result.in_use = in_use;
+                result.skipped = skipped;
+                Ok(self.structured_result(msg, result))
+            }
+        }
+    }
+
+    #[rmcp::tool(description = "Trash every path listed in a JSON file, for lists too large to pass inline. The file holds an array of trash_files entries; each is trashed separately and reported with its own status")]
+    pub async fn trash_files_from_file(
+        &self,
+        Parameters(params): Parameters<TrashFilesFromFileParams>,
+    ) -> Result<CallToolResult, McpError> {
+        const DEFAULT_CONCURRENCY: usize = 4;
+        const MAX_CONCURRENCY: usize = 32;
+
+        let scope = self.scope(params.profile.as_deref())?;
+        let group = groups::validate(params.group.as_deref()).map_err(|e| McpError::invalid_params(e, None))?;
+
+        if let Some(limited) = self.rate_limited("trash_files_from_file") {
+            return Ok(limited);
+        }
+
+        self.check_path_len(&params.file)?;
+        self.check_absolute(&scope, &params.file)?;
+        let file = scope.path(&params.file);
+        if let Some(reason) = self.refusal(&scope, &file, None) {
+            self.stats.record_refused(1);
+            return Ok(self.notice(
+                Outcome::Refused,
+                format!("Refusing to read path list: {}", reason)
+            ));
+        }
+        let contents = std::fs::read_to_string(&file).map_err(|e| {
+            McpError::invalid_params(format!("Failed to read path list {}: {}", file.display(), e), None)
+        })?;
+        let entries: Vec<PathEntry> = serde_json::from_str(&contents).map_err(|e| {
+            McpError::invalid_params(
+                format!("Path list {} is not a JSON array of paths: {}", file.display(), e),
+                None,
+            )
+        })?;
+        if entries.is_empty() {
+            return Err(McpError::invalid_params(
+                format!("No paths provided: {} is an empty list", file.display()),
+                None,
+            ));
+        }
+
+        // Resolve every entry up front; only the trashing itself runs in parallel
+        let mut items: Vec<EntryResult> = Vec::new();
+        let mut pending: Vec<(usize, PathBuf, Option<guard::Identity>)> = Vec::new();
+        let mut seen = std::collections::HashSet::new();
+        for entry in &entries {
+            let mut result = |path: String, status, detail| {
+                items.push(EntryResult { path, status, detail });
+                items.len() - 1
+            };
+            if let Err(e) = self.check_path_len(entry.path()).and_then(|()| self.check_absolute(&scope, entry.path())) {
+                result(entry.path().to_string(), EntryStatus::Failed, Some(e.message.to_string()));
+                continue;
+            }
+            let path = scope.path(entry.path());
+            if !path.exists() {
+                result(entry.path().to_string(), EntryStatus::Missing, None);
+                continue;
+            }
+            let targets = if entry.recursive() && path.symlink_metadata().is_ok_and(|meta| meta.is_dir()) {
+                match std::fs::read_dir(&path) {
+                    Ok(children) => children.flatten().map(|c| c.path()).collect(),
+                    Err(e) => {
+                        result(entry.path().to_string(), EntryStatus::Failed, Some(e.to_string()));
+                        continue;
+                    }
+                }
+            } else {
+                vec![path]
+            };
+            for target in targets {
+                if !seen.insert(paths::fold_case(&paths::resolve(&target))) {
+                    continue;
+                }
+                let display = target.to_string_lossy().into_owned();
+                if params.symlink_dirs == Some(SymlinkDirs::Skip) && is_dir_link(&target) {
+                    result(display, EntryStatus::SymlinkDir, None);
+                    continue;
+                }
+                let identity = guard::Identity::of(&target);
+                match self.refusal(&scope, &target, params.force) {
+                    Some(reason) => {
+                        self.stats.record_refused(1);
+                        result(display, EntryStatus::Refused, Some(reason));
+                    }
+                    None if params.skip_locked == Some(true) && guard::in_use(&target) => {
+                        result(display, EntryStatus::InUse, None);
+                    }
+                    None => {
+                        let index = result(display, EntryStatus::Trashed, None);
+                        pending.push((index, target, identity));
+                    }
+                }
+            }
+        }
+
+        // Last look before acting: a path swapped since its checks is left alone
+        pending.retain(|(index, path, identity)| {
+            let unchanged = guard::Identity::unchanged(*identity, path);
+            if !unchanged {
+                items[*index].status = EntryStatus::Refused;
+                items[*index].detail = Some("replaced or moved after it was checked".to_string());
+            }
+            unchanged
+        });
+        let pending: Vec<(usize, PathBuf)> = pending.into_iter().map(|(index, path, _)| (index, path)).collect();
+
+        let workers = params
+            .concurrency
+            .unwrap_or(DEFAULT_CONCURRENCY)
+            .clamp(1, MAX_CONCURRENCY)
+            .min(pending.len().max(1));
+        let sizes: Vec<Option<u64>> = pending
+            .iter()
+            .map(|(_, path)| {
+                std::fs::symlink_metadata(path)
+                    .ok()
+                    .filter(|meta| !meta.is_dir())
+                    .map(|meta| meta.len())
+            })
+            .collect();
+        let started = Instant::now();
+        let (backend, staging) = (self.backend.clone(), self.staging.clone());
+        // The workers block, so keep them off the async executor's threads
+        let (pending, failures): (_, Vec<(usize, trash::Error)>) = blocking(move || {
+            let next = std::sync::atomic::AtomicUsize::new(0);
+            let failures = std::thread::scope(|s| {
+                let handles: Vec<_> = (0..workers)
+                    .map(|_| {
+                        s.spawn(|| {
+                            let mut failures = Vec::new();
+                            loop {
+                                let i = next.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                                let Some((index, path)) = pending.get(i) else {
+                                    break;
+                                };
+                                if let Err(e) = send_to_trash(&backend, staging.as_deref(), path) {
+                                    failures.push((*index, e));
+                                }
+                            }
+                            failures
+                        })
+                    })
+                    .collect();
+                handles.into_iter().flat_map(|h| h.join().unwrap_or_default()).collect()
+            });
+            (pending, failures)
+        })
+        .await;
+        for (index, e) in failures {
+            tracing::warn!("Failed to trash {}: {}", items[index].path, e);
+            self.stats.record_error(&e);
+            let path = PathBuf::from(&items[index].path);
+            items[index].status = EntryStatus::Failed;
+            items[index].detail = Some(errors::trash_failure_message(Some(&path), &e));
+        }
+
+        let trashed = items.iter().filter(|item| item.status == EntryStatus::Trashed).count();
+        let bytes = known_total_size(
+            pending
+                .iter()
+                .zip(&sizes)
+                .filter(|((index, _), _)| items[*index].status == EntryStatus::Trashed)
+                .map(|(_, size)| *size),
+        );
+        let timing = BatchTiming::new(started.elapsed(), trashed, bytes);
+        if trashed > 0 {
+            tracing::info!("Trashed {} items from {} in {} ms", trashed, file.display(), timing.elapsed_ms);
+            self.stats.record_batch();
+            self.stats.record_trashed(trashed, bytes.unwrap_or(0));
+        }
 
-// === Parameter Types ===
+        let mut msg = format!("Moved {} of {} item(s) listed in {} to trash", trashed, items.len(), file.display());
+        for item in items.iter().filter(|item| item.status != EntryStatus::Trashed) {
+            msg.push_str(&format!("\n  [{}] {}", item.status.label(), item.path));
+            if let Some(detail) = &item.detail {
+                msg.push_str(&format!(": {}", detail));
+            }
+        }
+        msg.push_str(&self.record_group(
+            group,
+            items
+                .iter()
+                .filter(|item| item.status == EntryStatus::Trashed)
+                .map(|item| paths::resolve(Path::new(&item.path)))
+                .collect(),
+        ));
+        let result = TrashFilesFromFileResult {
+            file: file.to_string_lossy().into_owned(),
+            group: group.map(str::to_owned),
+            summary: (params.summary == Some(true))
+                .then(|| format::trash_summary(trashed, bytes, items.len() - trashed, false)),
+            items,
+            trashed,
+            timing,
+        };
+        Ok(self.structured_result(msg, result))
+    }
 
-#[derive(Debug, Deserialize, JsonSchema)]
-pub struct TrashFileParams {
-    #[schemars(description = "Path to the file or directory to move to trash")]
-    pub path: String,
-}
+    #[rmcp::tool(description = "Trash files in a directory that match every given condition: at least min_size_bytes large and/or untouched for older_than_days. Protected paths are skipped; use dry_run to preview")]
+    pub async fn trash_by_policy(
+        &self,
+        Parameters(params): Parameters<TrashByPolicyParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let scope = self.scope(params.profile.as_deref())?;
 
-#[derive(Debug, Deserialize, JsonSchema)]
-pub struct TrashFilesParams {
-    #[schemars(description = "List of paths to move to trash")]
-    pub paths: Vec<String>,
-}
+        if params.dry_run != Some(true) {
+            if let Some(limited) = self.rate_limited("trash_by_policy") {
+                return Ok(limited);
+            }
+        }
 
-#[derive(Debug, Deserialize, JsonSchema)]
-pub struct RestoreParams {
-    #[schemars(description = "Name of the file to restore from trash (partial match supported)")]
-    pub name: String,
-}
+        if params.min_size_bytes.is_none() && params.older_than_days.is_none() {
+            return Ok(self.notice(
+                Outcome::Refused,
+                "Refusing to trash: give min_size_bytes and/or older_than_days so the policy doesn't match every file"
+            ));
+        }
 
-// === Server ===
+        self.check_path_len(&params.dir)?;
+        let dir = scope.path(&params.dir);
+        if !dir.is_dir() {
+            return Ok(self.notice(
+                Outcome::NothingToDo,
+                format!("Not a directory: {}", params.dir)
+            ));
+        }
 
-#[derive(Debug)]
-pub struct TrashServer {
-    pub tool_router: ToolRouter<Self>,
-}
+        let (walk_dir, recursive, max_depth) = (dir.clone(), params.recursive == Some(true), self.config.max_walk_depth);
+        let mut progress = progress::Progress::new(&context);
+        let walked = blocking(move || files_in(&walk_dir, recursive, max_depth, &mut progress)).await;
+        let (files, limits) = match walked {
+            Ok(walked) => walked,
+            Err(e) => {
+                return Ok(self.notice(
+                    Outcome::Failed,
+                    format!("Failed to read directory {}: {}", params.dir, e)
+                ));
+            }
+        };
 
-impl Default for TrashServer {
-    fn default() -> Self {
-        Self::new()
+        let basis = params.time_basis.unwrap_or_default();
+        let now = std::time::SystemTime::now();
+        let mut matched = Vec::new();
+        let mut refused = Vec::new();
+        for (path, meta) in files {
+            let age_days = age_days(&meta, basis, now);
+            if params.min_size_bytes.is_some_and(|min| meta.len() < min)
+                || params.older_than_days.is_some_and(|days| age_days < days)
+            {
+                continue;
+            }
+            if let Some(reason) = self.refusal(&scope, &path, None) {
+                refused.push(reason);
+                continue;
+            }
+            matched.push((path, meta.len(), age_days));
+        }
+
+        let dry_run = params.dry_run == Some(true);
+        if !dry_run {
+            self.stats.record_refused(refused.len());
+        }
+        let total_bytes = matched.iter().map(|(_, size, _)| size).sum();
+        let mut trashed = 0;
+        if !dry_run && !matched.is_empty() {
+            let paths: Vec<&PathBuf> = matched.iter().map(|(path, _, _)| path).collect();
+            if let Err(e) = self.send_all_to_trash(&paths) {
+                tracing::warn!("Failed to trash policy matches in {}: {}", dir.display(), e);
+                self.stats.record_error(&e);
+                return Ok(self.notice(
+                    Outcome::Failed,
+                    errors::trash_failure_message(None, &e)
+                ));
+            }
+            trashed = paths.len();
+            tracing::info!("Trashed {} file(s) by policy in {}", trashed, dir.display());
+            self.stats.record_batch();
+            self.stats.record_trashed(trashed, total_bytes);
+        }
+
+        let matched_count = matched.len();
+        let mut msg = format!(
+            "{} {} file(s) ({}) in {}",
+            if dry_run { "Would trash" } else { "Trashed" },
+            matched.len(),
+            format::format_bytes(total_bytes),
+            dir.display()
+        );
+        for (path, size, age_days) in &matched {
+            msg.push_str(&format!(
+                "\n  {} ({}, {} days)",
+                path.display(),
+                format::format_bytes(*size),
+                age_days
+            ));
+        }
+        if !refused.is_empty() {
+            msg.push_str(&format!("\nRefused:\n  {}", refused.join("\n  ")));
+        }
+        if let Some(note) = limits.note() {
+            msg.push_str(&format!("\n{}", note));
+        }
+
+        let result = PolicyResult {
+            dry_run,
+            matched: matched
+                .into_iter()
+                .map(|(path, size_bytes, age_days)| PolicyMatch {
+                    path: path.to_string_lossy().into_owned(),
+                    size_bytes,
+                    age_days,
+                })
+                .collect(),
+            total_bytes,
+            trashed,
+            summary: (params.summary == Some(true))
+                .then(|| format::trash_summary(matched_count, Some(total_bytes), refused.len(), dry_run)),
+            refused,
+            walk: limits,
+        };
+        Ok(self.structured_result(msg, result))
     }
-}
 
-impl TrashServer {
-    pub fn new() -> Self {
-        Self {
-            tool_router: Self::tool_router(),
+    #[rmcp::tool(description = "Search a directory for files matching a name pattern and/or size and age conditions and trash each match, reporting a result per file. Protected paths are skipped; use dry_run to preview")]
+    pub async fn find_and_trash(
+        &self,
+        Parameters(params): Parameters<FindAndTrashParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let scope = self.scope(params.profile.as_deref())?;
+
+        if params.dry_run != Some(true) {
+            if let Some(limited) = self.rate_limited("find_and_trash") {
+                return Ok(limited);
+            }
+        }
+
+        if params.pattern.is_none() && params.min_size_bytes.is_none() && params.older_than_days.is_none() {
+            return Ok(self.notice(
+                Outcome::Refused,
+                "Refusing to trash: give pattern, min_size_bytes and/or older_than_days so the search doesn't match every file"
+            ));
+        }
+
+        self.check_path_len(&params.dir)?;
+        let dir = scope.path(&params.dir);
+        if !dir.is_dir() {
+            return Ok(self.notice(
+                Outcome::NothingToDo,
+                format!("Not a directory: {}", params.dir)
+            ));
+        }
+
+        let (walk_dir, recursive, max_depth) = (dir.clone(), params.recursive == Some(true), self.config.max_walk_depth);
+        let mut progress = progress::Progress::new(&context);
+        let walked = blocking(move || files_in(&walk_dir, recursive, max_depth, &mut progress)).await;
+        let (files, limits) = match walked {
+            Ok(walked) => walked,
+            Err(e) => {
+                return Ok(self.notice(
+                    Outcome::Failed,
+                    format!("Failed to read directory {}: {}", params.dir, e)
+                ));
+            }
+        };
+
+        let pattern = params
+            .pattern
+            .as_deref()
+            .map(|p| pattern::Pattern::new(p, params.match_kind.unwrap_or_default()));
+        let basis = params.time_basis.unwrap_or_default();
+        let now = std::time::SystemTime::now();
+        let dry_run = params.dry_run == Some(true);
+        let mut matched = Vec::new();
+        for (path, meta) in files {
+            let age_days = age_days(&meta, basis, now);
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            if pattern.as_ref().is_some_and(|p| !p.matches(&name))
+                || params.min_size_bytes.is_some_and(|min| meta.len() < min)
+                || params.older_than_days.is_some_and(|days| age_days < days)
+            {
+                continue;
+            }
+            matched.push((path, meta.len(), age_days));
+        }
+
+        let (outcomes, trashed, trashed_bytes) =
+            self.trash_found(&scope, matched.iter().map(|(path, size, _)| (path.as_path(), Some(*size))), dry_run);
+        let mut result = FindAndTrashResult {
+            dry_run,
+            matched: matched.len(),
+            items: matched
+                .into_iter()
+                .zip(outcomes)
+                .map(|((path, size_bytes, age_days), (status, detail))| FoundItem {
+                    path: path.to_string_lossy().into_owned(),
+                    size_bytes,
+                    age_days,
+                    status,
+                    detail,
+                })
+                .collect(),
+            trashed,
+            trashed_bytes,
+            summary: None,
+            walk: limits,
+        };
+        if result.trashed > 0 {
+            tracing::info!("Trashed {} found file(s) in {}", result.trashed, dir.display());
+        }
+
+        let mut msg = if dry_run {
+            format!("Would trash {} file(s) found in {}", result.matched, dir.display())
+        } else {
+            format!(
+                "Trashed {} of {} file(s) found in {} ({})",
+                result.trashed,
+                result.matched,
+                dir.display(),
+                format::format_bytes(result.trashed_bytes)
+            )
+        };
+        for item in &result.items {
+            msg.push_str(&format!("\n  [{}] {}", item.status.label(), item.path));
+            if let Some(detail) = &item.detail {
+                msg.push_str(&format!(": {}", detail));
+            }
+        }
+        if params.summary == Some(true) {
+            let items = result.items.iter().map(|item| (item.status, Some(item.size_bytes)));
+            result.summary = Some(found_summary(items, dry_run));
         }
+        if let Some(note) = result.walk.note() {
+            msg.push_str(&format!("\n{}", note));
+        }
+        Ok(self.structured_result(msg, result))
     }
-}
 
-#[rmcp::tool_router]
-impl TrashServer {
-    #[rmcp::tool(description = "Move a file or directory to the system trash/recycle bin")]
-    pub async fn trash_file(
+    #[rmcp::tool(description = "Trash files in a directory tree that match at least one include pattern and no exclude pattern, e.g. include [\"*.log\"] and exclude [\"important.log\"]. Protected paths are skipped; use dry_run to preview")]
+    pub async fn trash_filtered(
         &self,
-        Parameters(params): Parameters<TrashFileParams>,
+        Parameters(params): Parameters<TrashFilteredParams>,
+        context: RequestContext<RoleServer>,
     ) -> Result<CallToolResult, McpError> {
-        let path = PathBuf::from(&params.path);
+        let scope = self.scope(params.profile.as_deref())?;
 
-        if !path.exists() {
-            return Ok(CallToolResult::success(vec![Content::text(
-                format!("Path does not exist: {}", params.path)
-            )]));
+        let dry_run = params.dry_run == Some(true);
+        if !dry_run {
+            if let Some(limited) = self.rate_limited("trash_filtered") {
+                return Ok(limited);
+            }
+        }
+
+        if params.include_patterns.is_empty() {
+            return Err(McpError::invalid_params("No patterns provided: `include_patterns` is empty", None));
+        }
+
+        self.check_path_len(&params.dir)?;
+        let dir = scope.path(&params.dir);
+        if !dir.is_dir() {
+            return Ok(self.notice(
+                Outcome::NothingToDo,
+                format!("Not a directory: {}", params.dir)
+            ));
+        }
+
+        let (walk_dir, recursive, max_depth) = (dir.clone(), params.recursive != Some(false), self.config.max_walk_depth);
+        let mut progress = progress::Progress::new(&context);
+        let walked = blocking(move || files_in(&walk_dir, recursive, max_depth, &mut progress)).await;
+        let (mut files, limits) = match walked {
+            Ok(walked) => walked,
+            Err(e) => {
+                return Ok(self.notice(
+                    Outcome::Failed,
+                    format!("Failed to read directory {}: {}", params.dir, e)
+                ));
+            }
+        };
+        files.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        let kind = params.match_kind.unwrap_or(pattern::MatchKind::Glob);
+        let compile = |patterns: &[String]| -> Vec<(bool, pattern::Pattern)> {
+            patterns
+                .iter()
+                .map(|p| (p.contains('/'), pattern::Pattern::new(p, kind)))
+                .collect()
+        };
+        let include = compile(&params.include_patterns);
+        let exclude = compile(params.exclude_patterns.as_deref().unwrap_or_default());
+        let matches_any = |patterns: &[(bool, pattern::Pattern)], path: &Path| {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            let relative = path.strip_prefix(&dir).unwrap_or(path).to_string_lossy().replace('\\', "/");
+            patterns
+                .iter()
+                .any(|(by_path, p)| p.matches(if *by_path { &relative } else { &name }))
+        };
+
+        let mut matched = Vec::new();
+        let mut excluded = 0;
+        for (path, meta) in files {
+            if !matches_any(&include, &path) {
+                continue;
+            }
+            if matches_any(&exclude, &path) {
+                excluded += 1;
+                continue;
+            }
+            matched.push((path, meta.len()));
         }
 
-        match trash::delete(&path) {
-            Ok(()) => Ok(CallToolResult::success(vec![Content::text(
-                format!("Moved to trash: {}", params.path)
-            )])),
-            Err(e) => Ok(CallToolResult::success(vec![Content::text(
-                format!("Failed to trash: {}", e)
-            )])),
+        let (outcomes, trashed, trashed_bytes) =
+            self.trash_found(&scope, matched.iter().map(|(path, size)| (path.as_path(), Some(*size))), dry_run);
+        let mut result = TrashFilteredResult {
+            dry_run,
+            matched: matched.len(),
+            excluded,
+            trashed,
+            trashed_bytes,
+            items: matched
+                .into_iter()
+                .zip(outcomes)
+                .map(|((path, size_bytes), (status, detail))| FilteredItem {
+                    path: path.to_string_lossy().into_owned(),
+                    size_bytes,
+                    status,
+                    detail,
+                })
+                .collect(),
+            summary: None,
+            walk: limits,
+        };
+        if result.trashed > 0 {
+            tracing::info!("Trashed {} filtered file(s) in {}", result.trashed, dir.display());
+        }
+
+        let mut msg = if dry_run {
+            format!("Would trash {} file(s) in {}", result.matched, dir.display())
+        } else {
+            format!(
+                "Trashed {} of {} matching file(s) in {} ({})",
+                result.trashed,
+                result.matched,
+                dir.display(),
+                format::format_bytes(result.trashed_bytes)
+            )
+        };
+        if result.excluded > 0 {
+            msg.push_str(&format!("; {} kept by exclude_patterns", result.excluded));
+        }
+        for item in &result.items {
+            msg.push_str(&format!("\n  [{}] {}", item.status.label(), item.path));
+            if let Some(detail) = &item.detail {
+                msg.push_str(&format!(": {}", detail));
+            }
         }
+        if params.summary == Some(true) {
+            let items = result.items.iter().map(|item| (item.status, Some(item.size_bytes)));
+            result.summary = Some(found_summary(items, dry_run));
+        }
+        if let Some(note) = result.walk.note() {
+            msg.push_str(&format!("\n{}", note));
+        }
+        Ok(self.structured_result(msg, result))
     }
 
-    #[rmcp::tool(description = "Move multiple files or directories to the system trash/recycle bin")]
-    pub async fn trash_files(
+    #[rmcp::tool(description = "Trash well-known junk in a directory by name: .DS_Store, Thumbs.db, *.tmp, *.pyc, __pycache__ and the like, or your own patterns. node_modules only when include_node_modules is set. Protected paths are skipped; use dry_run to preview")]
+    pub async fn trash_common_junk(
         &self,
-        Parameters(params): Parameters<TrashFilesParams>,
+        Parameters(params): Parameters<TrashCommonJunkParams>,
+        context: RequestContext<RoleServer>,
     ) -> Result<CallToolResult, McpError> {
-        let paths: Vec<PathBuf> = params.paths.iter().map(PathBuf::from).collect();
+        let scope = self.scope(params.profile.as_deref())?;
 
-        // Check which paths exist
-        let mut missing: Vec<&str> = Vec::new();
-        let mut to_trash: Vec<&PathBuf> = Vec::new();
+        let dry_run = params.dry_run == Some(true);
+        if !dry_run {
+            if let Some(limited) = self.rate_limited("trash_common_junk") {
+                return Ok(limited);
+            }
+        }
 
-        for (i, path) in paths.iter().enumerate() {
-            if path.exists() {
-                to_trash.push(path);
-            } else {
-                missing.push(&params.paths[i]);
+        let names: Vec<String> = match params.patterns {
+            Some(patterns) if patterns.is_empty() => {
+                return Err(McpError::invalid_params(
+                    "No patterns provided: `patterns` is empty; omit it for the built-in set",
+                    None,
+                ));
             }
+            Some(patterns) => patterns,
+            None => JUNK_PATTERNS.iter().map(|p| p.to_string()).collect(),
+        };
+        let patterns: Vec<pattern::Pattern> =
+            names.iter().map(|p| pattern::Pattern::new(p, pattern::MatchKind::Glob)).collect();
+
+        self.check_path_len(&params.dir)?;
+        let dir = scope.path(&params.dir);
+        if !dir.is_dir() {
+            return Ok(self.notice(
+                Outcome::NothingToDo,
+                format!("Not a directory: {}", params.dir)
+            ));
         }
 
-        if to_trash.is_empty() {
-            return Ok(CallToolResult::success(vec![Content::text(
-                "No valid paths to trash"
-            )]));
+        let node_modules = params.include_node_modules == Some(true);
+        let recursive = params.recursive != Some(false);
+        let (walk_dir, max_depth) = (dir.clone(), self.config.max_walk_depth);
+        let mut progress = progress::Progress::new(&context);
+        let walked =
+            blocking(move || junk_in(&walk_dir, &patterns, node_modules, recursive, max_depth, &mut progress)).await;
+        let (found, limits) = match walked {
+            Ok(walked) => walked,
+            Err(e) => {
+                return Ok(self.notice(
+                    Outcome::Failed,
+                    format!("Failed to read directory {}: {}", params.dir, e)
+                ));
+            }
+        };
+
+        let found: Vec<(PathBuf, bool, Option<u64>)> = found
+            .into_iter()
+            .map(|(path, meta)| (path, meta.is_dir(), (!meta.is_dir()).then_some(meta.len())))
+            .collect();
+        let (outcomes, trashed, trashed_bytes) =
+            self.trash_found(&scope, found.iter().map(|(path, _, size)| (path.as_path(), *size)), dry_run);
+        let mut result = JunkResult {
+            dry_run,
+            patterns: names,
+            items: found
+                .into_iter()
+                .zip(outcomes)
+                .map(|((path, is_dir, size_bytes), (status, detail))| JunkItem {
+                    path: path.to_string_lossy().into_owned(),
+                    is_dir,
+                    size_bytes,
+                    status,
+                    detail,
+                })
+                .collect(),
+            trashed,
+            trashed_bytes,
+            summary: None,
+            walk: limits,
+        };
+
+        if result.items.is_empty() {
+            return Ok(self.structured_result(format!("No junk found in {}", dir.display()), result));
+        }
+        if result.trashed > 0 {
+            tracing::info!("Trashed {} junk item(s) in {}", result.trashed, dir.display());
         }
 
-        match trash::delete_all(&to_trash) {
-            Ok(()) => {
-                let mut msg = format!("Moved {} items to trash", to_trash.len());
-                if !missing.is_empty() {
-                    msg.push_str(&format!("\nSkipped (not found): {}", missing.join(", ")));
-                }
-                Ok(CallToolResult::success(vec![Content::text(msg)]))
+        let mut msg = if dry_run {
+            format!("Would trash {} junk item(s) found in {}", result.items.len(), dir.display())
+        } else {
+            format!(
+                "Trashed {} of {} junk item(s) found in {}",
+                result.trashed,
+                result.items.len(),
+                dir.display()
+            )
+        };
+        for item in &result.items {
+            msg.push_str(&format!("\n  [{}] {}", item.status.label(), item.path));
+            if item.is_dir {
+                msg.push('/');
             }
-            Err(e) => Ok(CallToolResult::success(vec![Content::text(
-                format!("Failed to trash: {}", e)
-            )])),
+            if let Some(detail) = &item.detail {
+                msg.push_str(&format!(": {}", detail));
+            }
+        }
+        if params.summary == Some(true) {
+            let items = result.items.iter().map(|item| (item.status, item.size_bytes));
+            result.summary = Some(found_summary(items, dry_run));
         }
+        if let Some(note) = result.walk.note() {
+            msg.push_str(&format!("\n{}", note));
+        }
+        Ok(self.structured_result(msg, result))
     }
 
-    #[rmcp::tool(description = "List items currently in the system trash (Linux/Windows only)")]
-    pub async fn list_trash(&self) -> Result<CallToolResult, McpError> {
-        #[cfg(any(target_os = "linux", target_os = "windows"))]
-        {
-            match trash::os_limited::list() {
-                Ok(items) => {
-                    if items.is_empty() {
-                        Ok(CallToolResult::success(vec![Content::text("Trash is empty")]))
-                    } else {
-                        let list: Vec<String> = items
-                            .iter()
-                            .map(|item| item.name.to_string_lossy().into_owned())
-                            .collect();
-                        Ok(CallToolResult::success(vec![Content::text(
-                            format!("Trash contents ({} items):\n{}", items.len(), list.join("\n"))
-                        )]))
-                    }
+    #[rmcp::tool(description = "Plan how to reach a free-space target: pick the largest or oldest files in a directory until trashing them would free enough. Nothing is trashed; pass the returned paths to trash_files to carry out the plan")]
+    pub async fn cleanup_plan(
+        &self,
+        Parameters(params): Parameters<CleanupPlanParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let scope = self.scope(params.profile.as_deref())?;
+        self.check_path_len(&params.dir)?;
+        let dir = scope.path(&params.dir);
+        if !dir.is_dir() {
+            return Ok(self.notice(
+                Outcome::NothingToDo,
+                format!("Not a directory: {}", params.dir)
+            ));
+        }
+
+        let free_bytes = match disk::disk_space(&dir) {
+            Ok(space) => space.free_bytes,
+            Err(e) => {
+                return Ok(self.notice(
+                    Outcome::Failed,
+                    format!("Failed to get disk space for {}: {}", params.dir, e)
+                ));
+            }
+        };
+        let needed_bytes = params.target_free_bytes.saturating_sub(free_bytes);
+
+        let mut files = Vec::new();
+        let mut limits = walk::WalkLimits::default();
+        if needed_bytes > 0 {
+            let (walk_dir, recursive, max_depth) = (dir.clone(), params.recursive != Some(false), self.config.max_walk_depth);
+            let mut progress = progress::Progress::new(&context);
+            let walked = blocking(move || files_in(&walk_dir, recursive, max_depth, &mut progress)).await;
+            (files, limits) = match walked {
+                Ok(walked) => walked,
+                Err(e) => {
+                    return Ok(self.notice(
+                        Outcome::Failed,
+                        format!("Failed to read directory {}: {}", params.dir, e)
+                    ));
                 }
-                Err(e) => Ok(CallToolResult::success(vec![Content::text(
-                    format!("Failed to list trash: {}", e)
-                )])),
+            };
+        }
+
+        let basis = params.time_basis.unwrap_or_default();
+        let now = std::time::SystemTime::now();
+        let mut candidates: Vec<PlannedFile> = Vec::new();
+        let mut protected_skipped = 0;
+        for (path, meta) in files {
+            if self.refusal(&scope, &path, None).is_some() {
+                protected_skipped += 1;
+                continue;
             }
+            candidates.push(PlannedFile {
+                path: path.to_string_lossy().into_owned(),
+                size_bytes: meta.len(),
+                age_days: age_days(&meta, basis, now),
+            });
+        }
+        match params.prefer.unwrap_or_default() {
+            CleanupOrder::Largest => candidates.sort_by_key(|file| std::cmp::Reverse(file.size_bytes)),
+            CleanupOrder::Oldest => candidates.sort_by_key(|file| std::cmp::Reverse((file.age_days, file.size_bytes))),
         }
 
-        #[cfg(not(any(target_os = "linux", target_os = "windows")))]
-        {
-            Ok(CallToolResult::success(vec![Content::text(
-                "list_trash is not supported on this platform (Linux/Windows only)"
-            )]))
+        let mut planned_bytes = 0u64;
+        let files: Vec<PlannedFile> = candidates
+            .into_iter()
+            .take_while(|file| {
+                let take = planned_bytes < needed_bytes;
+                planned_bytes += if take { file.size_bytes } else { 0 };
+                take
+            })
+            .collect();
+        let goal_met = planned_bytes >= needed_bytes;
+
+        let mut msg = if needed_bytes == 0 {
+            format!(
+                "{} already has {} free, at least the target of {}; nothing to trash",
+                dir.display(),
+                format::format_bytes(free_bytes),
+                format::format_bytes(params.target_free_bytes)
+            )
+        } else {
+            format!(
+                "Plan: trash {} file(s) from {} totalling {} (need {} more free to reach {}){}",
+                files.len(),
+                dir.display(),
+                format::format_bytes(planned_bytes),
+                format::format_bytes(needed_bytes),
+                format::format_bytes(params.target_free_bytes),
+                if goal_met { "" } else { "; not enough unprotected files to reach the target" }
+            )
+        };
+        for file in &files {
+            msg.push_str(&format!(
+                "\n  {} ({}, {} days old)",
+                file.path,
+                format::format_bytes(file.size_bytes),
+                file.age_days
+            ));
+        }
+        if protected_skipped > 0 {
+            msg.push_str(&format!("\nLeft out {} protected file(s)", protected_skipped));
+        }
+        if !files.is_empty() {
+            msg.push_str(
+                "\nNote: trashed files still take up space until the trash is emptied when the trash is on the same filesystem",
+            );
         }
+        if let Some(note) = limits.note() {
+            msg.push_str(&format!("\n{}", note));
+        }
+
+        let plan = CleanupPlan {
+            dir: dir.to_string_lossy().into_owned(),
+            free_bytes,
+            target_free_bytes: params.target_free_bytes,
+            needed_bytes,
+            files,
+            planned_bytes,
+            goal_met,
+            protected_skipped,
+            walk: limits,
+        };
+        Ok(self.structured_result(msg, plan))
     }
 
-    #[rmcp::tool(description = "Restore a file from trash to its original location (Linux/Windows only)")]
-    pub async fn restore_from_trash(
+    #[rmcp::tool(description = "Check whether a path is protected from trashing, and by which rule")]
+    pub async fn is_protected(
         &self,
-        Parameters(params): Parameters<RestoreParams>,
+        Parameters(params): Parameters<IsProtectedParams>,
     ) -> Result<CallToolResult, McpError> {
-        #[cfg(any(target_os = "linux", target_os = "windows"))]
-        {
-            match trash::os_limited::list() {
-                Ok(items) => {
-                    let search = params.name.to_lowercase();
-                    let matches: Vec<_> = items
-                        .into_iter()
-                        .filter(|item| {
-                            item.name.to_string_lossy().to_lowercase().contains(&search)
-                        })
-                        .collect();
+        let scope = self.scope(params.profile.as_deref())?;
+        self.check_path_len(&params.path)?;
+        let resolved = paths::resolve(&scope.path(&params.path));
+        let matched = scope.protected.check(&resolved);
 
-                    if matches.is_empty() {
-                        return Ok(CallToolResult::success(vec![Content::text(
-                            format!("No items in trash matching '{}'", params.name)
-                        )]));
-                    }
+        let msg = match &matched {
+            Some(m) => format!(
+                "{} is protected ({}: {})",
+                resolved.display(),
+                m.source.label(),
+                m.rule
+            ),
+            None => format!("{} is not protected", resolved.display()),
+        };
+        let report = ProtectionReport {
+            path: params.path,
+            resolved_path: resolved.to_string_lossy().into_owned(),
+            protected: matched.is_some(),
+            matched,
+        };
+        Ok(self.structured_result(msg, report))
+    }
 
-                    let count = matches.len();
-                    let names: Vec<String> = matches
-                        .iter()
-                        .map(|item| item.name.to_string_lossy().into_owned())
-                        .collect();
-
-                    match trash::os_limited::restore_all(matches) {
-                        Ok(()) => Ok(CallToolResult::success(vec![Content::text(
-                            format!("Restored {} item(s): {}", count, names.join(", "))
-                        )])),
-                        Err(e) => Ok(CallToolResult::success(vec![Content::text(
-                            format!("Failed to restore: {}", e)
-                        )])),
-                    }
+    #[rmcp::tool(description = "Record the recursive size of a directory under a label, to compare against later with diff_usage, e.g. before a cleanup to show how much it freed. Snapshots are kept in memory until the server restarts, unless RMCP_TRASH_STATE_FILE saves them across restarts")]
+    pub async fn snapshot_usage(
+        &self,
+        Parameters(params): Parameters<SnapshotUsageParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let scope = self.scope(params.profile.as_deref())?;
+        self.check_path_len(&params.dir)?;
+        let dir = scope.path(&params.dir);
+        if !dir.is_dir() {
+            return Ok(self.notice(Outcome::NothingToDo, format!("Not a directory: {}", params.dir)));
+        }
+
+        let (walk_dir, max_depth) = (dir.clone(), self.config.max_walk_depth);
+        let mut progress = progress::Progress::new(&context);
+        let walked = blocking(move || tree_size(&walk_dir, max_depth, &mut progress)).await;
+        let (files, bytes, limits) = match walked {
+            Ok(walked) => walked,
+            Err(e) => {
+                return Ok(self.notice(
+                    Outcome::Failed,
+                    format!("Failed to measure {}: {}", params.dir, e),
+                ));
+            }
+        };
+
+        let snapshot = UsageSnapshot {
+            label: params.label.clone(),
+            dir: paths::resolve(&dir).to_string_lossy().into_owned(),
+            files,
+            bytes,
+            taken_at: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs() as i64),
+            walk: limits,
+        };
+        let replaced = self
+            .usage_snapshots
+            .lock()
+            .unwrap()
+            .insert(params.label.clone(), snapshot.clone())
+            .is_some();
+        self.save_state();
+        let mut msg = format!(
+            "Snapshot '{}'{}: {} holds {} in {} file(s); call diff_usage with this label to compare",
+            params.label,
+            if replaced { " (replacing the previous one)" } else { "" },
+            snapshot.dir,
+            format::format_bytes(bytes),
+            files
+        );
+        if let Some(note) = snapshot.walk.note() {
+            msg.push_str(&format!("\n{}", note));
+        }
+        Ok(self.structured_result(msg, snapshot))
+    }
+
+    #[rmcp::tool(description = "Compare a directory's current recursive size with a snapshot taken by snapshot_usage, reporting how many bytes and files were freed since")]
+    pub async fn diff_usage(
+        &self,
+        Parameters(params): Parameters<DiffUsageParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let snapshot = {
+            let snapshots = self.usage_snapshots.lock().unwrap();
+            match snapshots.get(&params.label) {
+                Some(snapshot) => snapshot.clone(),
+                None => {
+                    let mut known: Vec<&str> = snapshots.keys().map(String::as_str).collect();
+                    known.sort_unstable();
+                    return Err(McpError::invalid_params(
+                        format!(
+                            "No snapshot labelled '{}' (known: {}); take one with snapshot_usage first",
+                            params.label,
+                            if known.is_empty() { "none".into() } else { known.join(", ") }
+                        ),
+                        None,
+                    ));
                 }
-                Err(e) => Ok(CallToolResult::success(vec![Content::text(
-                    format!("Failed to list trash: {}", e)
-                )])),
             }
+        };
+
+        let dir = PathBuf::from(&snapshot.dir);
+        let dir_missing = !dir.is_dir();
+        let (mut files, mut bytes) = (0, 0);
+        let mut limits = walk::WalkLimits::default();
+        if !dir_missing {
+            let (walk_dir, max_depth) = (dir.clone(), self.config.max_walk_depth);
+            let mut progress = progress::Progress::new(&context);
+            let walked = blocking(move || tree_size(&walk_dir, max_depth, &mut progress)).await;
+            (files, bytes, limits) = match walked {
+                Ok(walked) => walked,
+                Err(e) => {
+                    return Ok(self.notice(
+                        Outcome::Failed,
+                        format!("Failed to measure {}: {}", snapshot.dir, e),
+                    ));
+                }
+            };
         }
 
-        #[cfg(not(any(target_os = "linux", target_os = "windows")))]
-        {
-            Ok(CallToolResult::success(vec![Content::text(
-                "restore_from_trash is not supported on this platform (Linux/Windows only)"
-            )]))
+        let freed = snapshot.bytes as i64 - bytes as i64;
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs() as i64);
+        let mut msg = format!(
+            "Since snapshot '{}' of {} ({} ago):\n  Before: {} in {} file(s)\n  Now:    {} in {} file(s)\n  ",
+            snapshot.label,
+            snapshot.dir,
+            format::format_duration(now.saturating_sub(snapshot.taken_at).max(0) as u64),
+            format::format_bytes(snapshot.bytes),
+            snapshot.files,
+            format::format_bytes(bytes),
+            files
+        );
+        if freed >= 0 {
+            msg.push_str(&format!("Freed:  {}", format::format_bytes(freed as u64)));
+        } else {
+            msg.push_str(&format!("Grew by {}", format::format_bytes(freed.unsigned_abs())));
+        }
+        if dir_missing {
+            msg.push_str("\n  The directory no longer exists");
+        }
+        if let Some(note) = limits.note() {
+            msg.push_str(&format!("\n{}", note));
         }
+
+        let diff = UsageDiff {
+            label: snapshot.label,
+            dir: snapshot.dir,
+            taken_at: snapshot.taken_at,
+            before_files: snapshot.files,
+            before_bytes: snapshot.bytes,
+            after_files: files,
+            after_bytes: bytes,
+            freed_bytes: freed,
+            dir_missing,
+            walk: limits,
+        };
+        Ok(self.structured_result(msg, diff))
     }
 
-    #[rmcp::tool(description = "Permanently delete all items in the trash (Linux/Windows only). This cannot be undone!")]
-    pub async fn empty_trash(&self) -> Result<CallToolResult, McpError> {
-        #[cfg(any(target_os = "linux", target_os = "windows"))]
-        {
-            match trash::os_limited::list() {
-                Ok(items) => {
-                    if items.is_empty() {
-                        return Ok(CallToolResult::success(vec![Content::text(
-                            "Trash is already empty"
-                        )]));
-                    }
+    #[rmcp::tool(description = "Report total, used and free bytes of a filesystem (defaults to the trash volume)")]
+    pub async fn disk_space(
+        &self,
+        Parameters(params): Parameters<DiskSpaceParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let path = match params.path {
+            Some(path) => {
+                self.check_path_len(&path)?;
+                PathBuf::from(path)
+            }
+            None => match self.backend.location() {
+                Some(path) => path,
+                None => {
+                    return Ok(self.notice(
+                        Outcome::Failed,
+                        "Could not determine the trash location; pass a path explicitly"
+                    ));
+                }
+            },
+        };
 
-                    let count = items.len();
-                    match trash::os_limited::purge_all(items) {
-                        Ok(()) => Ok(CallToolResult::success(vec![Content::text(
-                            format!("Permanently deleted {} item(s) from trash", count)
-                        )])),
-                        Err(e) => Ok(CallToolResult::success(vec![Content::text(
-                            format!("Failed to empty trash: {}", e)
-                        )])),
-                    }
+        match disk::disk_space(&path) {
+            Ok(space) => {
+                let msg = format!(
+                    "Disk space for {}:\n  Total: {}\n  Used:  {}\n  Free:  {}",
+                    path.display(),
+                    format::format_bytes(space.total_bytes),
+                    format::format_bytes(space.used_bytes),
+                    format::format_bytes(space.free_bytes),
+                );
+                let report = DiskSpaceReport {
+                    path: path.to_string_lossy().into_owned(),
+                    space,
+                };
+                Ok(self.structured_result(msg, report))
+            }
+            Err(e) => Ok(self.notice(
+                Outcome::Failed,
+                format!("Failed to get disk space for {}: {}", path.display(), e)
+            )),
+        }
+    }
+
+    #[rmcp::tool(description = "Report where trashed files are physically stored: the home trash and any per-volume trash directories")]
+    pub async fn trash_locations(&self) -> Result<CallToolResult, McpError> {
+        let backend = match self.backend {
+            Backend::System => "system",
+            Backend::Custom(_) => "custom",
+        };
+        let locations: Vec<TrashLocation> = self
+            .backend
+            .locations()
+            .into_iter()
+            .map(|path| TrashLocation {
+                exists: path.is_dir(),
+                path: path.to_string_lossy().into_owned(),
+            })
+            .collect();
+
+        if locations.is_empty() {
+            return Ok(self.notice(
+                Outcome::Failed,
+                "Could not determine any trash location on this host"
+            ));
+        }
+
+        let mut msg = format!("Trash locations ({} backend):", backend);
+        for location in &locations {
+            msg.push_str(&format!("\n  {}", location.path));
+            if !location.exists {
+                msg.push_str(" (not created yet)");
+            }
+        }
+        Ok(self.structured_result(msg, TrashLocations { backend, locations }))
+    }
+
+    #[rmcp::tool(description = "Describe the available tools: what each does, its parameters with their types, which are required and the accepted values, and an example call. Pass `tool` for just one")]
+    pub async fn help(&self, Parameters(params): Parameters<HelpParams>) -> Result<CallToolResult, McpError> {
+        let mut tools = self.tool_router.list_all();
+        tools.sort_by(|a, b| a.name.cmp(&b.name));
+        if let Some(name) = &params.tool {
+            let prefixed = format!("{}{}", self.config.tool_prefix.as_deref().unwrap_or_default(), name);
+            tools.retain(|tool| tool.name == name.as_str() || tool.name == prefixed);
+            if tools.is_empty() {
+                return Err(McpError::invalid_params(
+                    format!("Unknown tool {}; call help without `tool` to list them", name),
+                    None,
+                ));
+            }
+        }
+
+        let result = help::HelpResult {
+            tools: tools.iter().map(help::describe).collect(),
+        };
+        let mut msg = if params.tool.is_some() {
+            String::new()
+        } else {
+            format!("{} tool(s) available:\n\n", result.tools.len())
+        };
+        msg.push_str(&result.tools.iter().map(help::render).collect::<Vec<_>>().join("\n\n"));
+        Ok(self.structured_result(msg, result))
+    }
+
+    #[rmcp::tool(description = "Report what this server can do here: build, backend, whether listing and restore work, and the result of the trash probe run at startup, which shows whether trashed files are really recoverable")]
+    pub async fn capabilities(&self) -> Result<CallToolResult, McpError> {
+        let capabilities = Capabilities {
+            build: if cfg!(feature = "full") { "full" } else { "minimal" },
+            backend: match self.backend {
+                Backend::System => "system",
+                Backend::Custom(_) => "custom",
+            },
+            listing: self.backend.supports_listing(),
+            staging: self.staging.is_some(),
+            startup_probe: self.startup_probe.as_ref(),
+        };
+
+        let mut msg = format!(
+            "{} build, {} backend\n  Listing, restore and purge: {}\n  Staging: {}",
+            capabilities.build,
+            capabilities.backend,
+            if capabilities.listing { "available" } else { "not available" },
+            if capabilities.staging { "on" } else { "off" }
+        );
+        match &self.startup_probe {
+            Some(report) if report.passed => {
+                msg.push_str("\n  Startup probe: passed, trashed files are recoverable")
+            }
+            Some(report) => {
+                msg.push_str("\n  Startup probe: FAILED, trashed files may not be recoverable");
+                for step in report.steps.iter().filter(|step| step.status == selftest::StepStatus::Failed) {
+                    msg.push_str(&format!("\n    {}: {}", step.name, step.detail));
                 }
-                Err(e) => Ok(CallToolResult::success(vec![Content::text(
-                    format!("Failed to list trash: {}", e)
-                )])),
             }
+            None => msg.push_str(
+                "\n  Startup probe: not run, since without listing a probe file would be left in the trash; self_test runs it on demand"
+            ),
+        }
+        Ok(self.structured_result(msg, capabilities))
+    }
+
+    #[rmcp::tool(description = "Send every file waiting in the staging area (RMCP_TRASH_STAGING_DIR) to the trash now instead of after the staging delay")]
+    pub async fn flush_staging(&self) -> Result<CallToolResult, McpError> {
+        if let Some(limited) = self.rate_limited("flush_staging") {
+            return Ok(limited);
         }
+        let Some(staging) = &self.staging else {
+            return Ok(self.notice(
+                Outcome::Unsupported,
+                "Staging is not enabled (set RMCP_TRASH_STAGING_DIR)"
+            ));
+        };
 
-        #[cfg(not(any(target_os = "linux", target_os = "windows")))]
-        {
-            Ok(CallToolResult::success(vec![Content::text(
-                "empty_trash is not supported on this platform (Linux/Windows only)"
-            )]))
+        let report = staging.flush(&self.backend);
+        let mut msg = format!("Moved {} staged item(s) to trash", report.promoted.len());
+        for path in &report.promoted {
+            msg.push_str(&format!("\n  {}", path));
+        }
+        if !report.failed.is_empty() {
+            msg.push_str(&format!("\nStill staged:\n  {}", report.failed.join("\n  ")));
+        }
+        Ok(self.structured_result(msg, report))
+    }
+
+    #[rmcp::tool(description = "Report activity counters since the server started: files and bytes trashed, batches, restores, purges, and errors by category. Counters reset only when the server restarts")]
+    pub async fn stats(&self) -> Result<CallToolResult, McpError> {
+        let snapshot = self.stats.snapshot();
+        let msg = format!(
+            "Since start ({} s ago):\n  Trashed: {} file(s), {}\n  Batches: {}\n  Restored: {}\n  Purged: {}\n  \
+             Errors: {} refused, {} permission denied, {} collisions, {} other",
+            snapshot.uptime_secs,
+            snapshot.files_trashed,
+            format::format_bytes(snapshot.bytes_trashed),
+            snapshot.batches,
+            snapshot.restores,
+            snapshot.purges,
+            snapshot.errors.refused,
+            snapshot.errors.permission_denied,
+            snapshot.errors.collisions,
+            snapshot.errors.other,
+        );
+        Ok(self.structured_result(msg, snapshot))
+    }
+
+    #[rmcp::tool(description = "Show the most recent trash, restore or purge failure in full: the `trash` crate error variant, raw OS error code and error kind, and the path involved. For bug reports and for branching on specific OS errors")]
+    pub async fn last_error_detail(&self) -> Result<CallToolResult, McpError> {
+        let Some(detail) = self.stats.last_error() else {
+            return Ok(self.notice(Outcome::NothingToDo, "No errors recorded since the server started"));
+        };
+        let mut msg = format!("Last error ({}): {}", detail.variant, detail.message);
+        if let Some(code) = detail.os_code {
+            msg.push_str(&format!("\n  OS error code: {}", code));
+        }
+        if let Some(kind) = &detail.io_kind {
+            msg.push_str(&format!("\n  Kind: {}", kind));
         }
+        if let Some(path) = &detail.path {
+            msg.push_str(&format!("\n  Path: {}", path));
+        }
+        Ok(self.structured_result(msg, detail))
+    }
+
+    #[rmcp::tool(description = "Check that trashing works on this host: trashes a temporary file, verifies it is listed, restores it and cleans up")]
+    pub async fn self_test(&self) -> Result<CallToolResult, McpError> {
+        let report = selftest::run(&self.backend);
+        Ok(self.structured_result(report.to_text(), report))
     }
 }
 
-#[rmcp::tool_handler]
 impl ServerHandler for TrashServer {
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let name = match &self.config.tool_prefix {
+            Some(prefix) => request.name.strip_prefix(prefix.as_str()),
+            None => Some(request.name.as_ref()),
+        };
+        if name.is_some_and(|name| self.disabled_tools.contains(name)) {
+            return Ok(self.notice(
+                Outcome::Unsupported,
+                format!("{} is not available on this server", request.name),
+            ));
+        }
+        let context = rmcp::handler::server::tool::ToolCallContext::new(self, request, context);
+        self.tool_router.call(context).await
+    }
+
+    async fn list_tools(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, McpError> {
+        Ok(ListToolsResult {
+            tools: self.tool_router.list_all(),
+            meta: None,
+            next_cursor: None,
+        })
+    }
+
     fn get_info(&self) -> ServerInfo {
         ServerInfo {
             protocol_version: ProtocolVersion::V_2024_11_05,
-            capabilities: ServerCapabilities::builder().enable_tools().build(),
+            capabilities: ServerCapabilities::builder()
+                .enable_tools()
+                .enable_logging()
+                .build(),
             server_info: Implementation::from_build_env(),
-            instructions: Some(
-                "Cross-platform trash/recycle bin operations. Safely delete files with recovery option.".into(),
-            ),
+            instructions: Some(if cfg!(feature = "full") {
+                "Cross-platform trash/recycle bin operations. Safely delete files with recovery option.".into()
+            } else {
+                "Cross-platform trash/recycle bin operations. This build only moves files to the trash; \
+                 listing, restore and purge are not available."
+                    .into()
+            }),
         }
     }
+
+    async fn set_level(
+        &self,
+        request: SetLevelRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<(), McpError> {
+        self.log_sink.set_level(request.level, context.peer);
+        tracing::debug!("Client log level set to {:?}", request.level);
+        Ok(())
+    }
 }
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
+    let log_sink = Arc::new(ClientLogSink::default());
     tracing_subscriber::registry()
-        .with(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")))
-        .with(tracing_subscriber::fmt::layer().with_writer(std::io::stderr))
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_writer(std::io::stderr)
+                .with_filter(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"))),
+        )
+        .with(log_sink.layer())
         .init();
 
     tracing::info!("Starting rmcp-trash server");
 
-    let server = TrashServer::new();
+    let mut config = Config::from_env()?;
+    config.allow_force = std::env::args().skip(1).any(|arg| arg == "--allow-force");
+    config.validate()?;
+    config.log_effective();
+    let backend = match &config.trash_dir {
+        Some(dir) => {
+            let custom = CustomTrash::open(dir)?;
+            tracing::info!("Using custom trash directory {}", custom.root().display());
+            Backend::Custom(custom)
+        }
+        None => Backend::System,
+    };
+
+    let mut protected = guard::ProtectedPaths::builtin();
+    if let Some(file) = &config.protected_file {
+        let count = protected.load_file(file).map_err(|e| {
+            anyhow::anyhow!("Failed to read protected paths file {}: {}", file.display(), e)
+        })?;
+        tracing::info!("Loaded {} protected path(s) from {}", count, file.display());
+    }
+
+    let profiles = match &config.profiles_file {
+        Some(file) => {
+            let profiles = profiles::load(file, &protected).map_err(|e| {
+                anyhow::anyhow!("Failed to load profiles from {}: {}", file.display(), e)
+            })?;
+            tracing::info!("Loaded {} profile(s) from {}", profiles.len(), file.display());
+            profiles
+        }
+        None => HashMap::new(),
+    };
+
+    let staging = match &config.staging_dir {
+        Some(dir) => {
+            let staging = staging::Staging::open(dir, config.staging_delay).map_err(|e| {
+                anyhow::anyhow!("Failed to open staging directory {}: {}", dir.display(), e)
+            })?;
+            tracing::info!(
+                "Staging trashed files in {} for {} s",
+                staging.dir().display(),
+                staging.delay().as_secs()
+            );
+            Some(Arc::new(staging))
+        }
+        None => None,
+    };
+
+    // Probe only where the probe can be found and removed again
+    let probe = backend.supports_listing().then(|| selftest::run(&backend));
+    match &probe {
+        Some(report) if report.passed => tracing::info!("Startup trash probe passed"),
+        Some(report) => tracing::warn!(
+            "Startup trash probe failed; trashed files may not be recoverable on this host:\n{}",
+            report.to_text()
+        ),
+        None => tracing::info!("Startup trash probe skipped: the trash can't be listed here"),
+    }
+
+    // Purging on a timer is for what the purge tools may do, so it stays
+    // off when the server withholds them
+    let schedule = config.schedule.filter(|_| {
+        let allowed = ["empty_trash", "permanently_delete"].iter().all(|name| config.tool_enabled(name));
+        if !allowed {
+            tracing::warn!("RMCP_TRASH_SCHEDULE ignored: empty_trash or permanently_delete is disabled");
+        }
+        allowed
+    });
+    #[cfg(not(feature = "full"))]
+    if schedule.is_some() {
+        tracing::warn!("RMCP_TRASH_SCHEDULE ignored: this build can't list or purge the trash");
+    }
+    #[cfg(feature = "full")]
+    let scheduler = schedule.map(|schedule| {
+        tracing::info!(
+            "Purging trash items older than {} every {}",
+            format::format_duration(schedule.max_age.as_secs()),
+            format::format_duration(schedule.every.as_secs())
+        );
+        let scheduler = Arc::new(schedule::Scheduler::new(schedule));
+        schedule::spawn(scheduler.clone(), backend.clone(), protected.clone());
+        scheduler
+    });
+
+    let state_file = config.state_file.clone();
+    let mut server = TrashServer::with_config(config, backend.clone())
+        .with_protected_paths(protected)
+        .with_profiles(profiles)
+        .with_log_sink(log_sink);
+    if let Some(report) = probe {
+        server = server.with_startup_probe(report);
+    }
+    #[cfg(feature = "full")]
+    if let Some(scheduler) = scheduler {
+        server = server.with_scheduler(scheduler);
+    }
+    if let Some(staging) = staging {
+        staging::spawn_promoter(staging.clone(), backend);
+        server = server.with_staging(staging);
+    }
+    if let Some(file) = state_file {
+        server = server.with_state_file(state::StateFile::new(file));
+    }
     let service = server.serve(rmcp::transport::stdio()).await?;
     service.waiting().await?;
 
     tracing::info!("rmcp-trash server stopped");
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::TempDir;
+
+    /// A server whose trash is a directory in `tmp`.
+    fn server(tmp: &TempDir) -> TrashServer {
+        let trash = CustomTrash::open(tmp.path().join("trash")).unwrap();
+        TrashServer::with_config(Config::default(), Backend::Custom(trash))
+    }
+
+    fn params<T: serde::de::DeserializeOwned>(args: serde_json::Value) -> Parameters<T> {
+        Parameters(serde_json::from_value(args).unwrap())
+    }
+
+    /// Names stored in the test trash.
+    fn trashed(tmp: &TempDir) -> Vec<String> {
+        let mut names: Vec<String> = std::fs::read_dir(tmp.path().join("trash/files"))
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    /// The text of a tool result.
+    fn text(result: &CallToolResult) -> String {
+        serde_json::to_value(result).unwrap()["content"][0]["text"].as_str().unwrap().to_owned()
+    }
+
+    #[tokio::test]
+    async fn file_removed_between_check_and_trash_is_reported_as_vanished() {
+        let tmp = TempDir::new("vanish");
+        let file = tmp.file("work/notes.txt", "notes");
+        let mut server = server(&tmp);
+        // Another process removes the file after every check has passed
+        server.before_trash = Some(|path| std::fs::remove_file(path).unwrap());
+        let path = file.to_string_lossy().into_owned();
+
+        let result = server.trash_file(params(serde_json::json!({ "path": path }))).await.unwrap();
+        assert_ne!(result.is_error, Some(true));
+        assert!(text(&result).starts_with("Path disappeared before it could be trashed"), "{}", text(&result));
+
+        tmp.file("work/notes.txt", "notes");
+        let result = server
+            .trash_file(params(serde_json::json!({ "path": path, "on_missing": "error" })))
+            .await
+            .unwrap();
+        assert_eq!(result.is_error, Some(true));
+        assert!(text(&result).starts_with("Path disappeared before it could be trashed"), "{}", text(&result));
+
+        tmp.file("work/notes.txt", "notes");
+        let result = server
+            .trash_file(params(serde_json::json!({ "path": path, "on_missing": "ignore" })))
+            .await
+            .unwrap();
+        assert!(text(&result).starts_with("Already gone"), "{}", text(&result));
+        assert!(trashed(&tmp).is_empty());
+        // Not counted as a trash failure
+        let stats = server.stats.snapshot();
+        assert_eq!((stats.files_trashed, stats.errors.other), (0, 0));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn batch_never_follows_a_symlink_out_of_its_directories() {
+        let tmp = TempDir::new("batch-symlink");
+        tmp.file("batch/a.txt", "a");
+        let outside = tmp.file("outside/keep.txt", "keep");
+        let link = tmp.path().join("batch/link");
+        std::os::unix::fs::symlink(tmp.path().join("outside"), &link).unwrap();
+        let server = server(&tmp);
+
+        // Expanding the directory trashes the link itself, not what it points to
+        let batch = tmp.path().join("batch").to_string_lossy().into_owned();
+        server
+            .trash_files(params(serde_json::json!({ "paths": [{ "path": batch, "recursive": true }] })))
+            .await
+            .unwrap();
+        assert_eq!(trashed(&tmp), ["a.txt", "link"]);
+        assert!(outside.exists());
+        assert!(tmp.path().join("trash/files/link").symlink_metadata().unwrap().file_type().is_symlink());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn skipped_symlink_and_its_target_stay_put() {
+        let tmp = TempDir::new("batch-symlink-skip");
+        let outside = tmp.file("outside/keep.txt", "keep");
+        let link = tmp.path().join("batch/link");
+        std::fs::create_dir_all(link.parent().unwrap()).unwrap();
+        std::os::unix::fs::symlink(tmp.path().join("outside"), &link).unwrap();
+        let server = server(&tmp);
+
+        let entry = link.to_string_lossy().into_owned();
+        server
+            .trash_files(params(serde_json::json!({
+                "paths": [{ "path": entry, "recursive": true }],
+                "symlink_dirs": "skip",
+            })))
+            .await
+            .unwrap();
+        assert!(trashed(&tmp).is_empty());
+        assert!(link.symlink_metadata().is_ok());
+        assert!(outside.exists());
+    }
+
+    #[cfg(all(unix, feature = "full"))]
+    #[tokio::test]
+    async fn secure_delete_leaves_hard_linked_files_intact() {
+        let tmp = TempDir::new("secure-linked");
+        tmp.file("work/item/plain.txt", "gone");
+        let linked = tmp.file("work/item/linked.txt", "still needed");
+        let other = tmp.path().join("elsewhere.txt");
+        std::fs::hard_link(&linked, &other).unwrap();
+        let server = server(&tmp);
+
+        let item = tmp.path().join("work/item").to_string_lossy().into_owned();
+        server.trash_file(params(serde_json::json!({ "path": item }))).await.unwrap();
+        let result = server
+            .permanently_delete(params(serde_json::json!({ "name": "item", "secure": true })))
+            .await
+            .unwrap();
+        assert_ne!(result.is_error, Some(true), "{}", text(&result));
+        let structured = serde_json::to_value(&result).unwrap()["structuredContent"].clone();
+        assert_eq!(structured["not_wiped"], serde_json::json!([format!("{}: has other hard links", linked.display())]));
+        assert!(trashed(&tmp).is_empty());
+        assert_eq!(std::fs::read_to_string(&other).unwrap(), "still needed");
+    }
+
+    #[cfg(all(target_os = "linux", feature = "full"))]
+    #[tokio::test]
+    async fn purge_group_keeps_the_members_it_could_not_delete() {
+        let tmp = TempDir::new("purge-group");
+        let plain = tmp.file("plain.txt", "x");
+        let stuck = tmp.dir("stuck");
+        tmp.dir("stuck/busy");
+        let empty = tmp.dir("empty");
+        let server = server(&tmp);
+        let paths = [plain.to_string_lossy(), stuck.to_string_lossy()];
+        server
+            .trash_files(params(serde_json::json!({ "paths": paths, "group": "g" })))
+            .await
+            .unwrap();
+        // A mount point inside the stored directory can't be removed
+        let Some(_mount) = crate::testutil::bind_mount(&empty, &tmp.path().join("trash/files/stuck/busy")) else {
+            return;
+        };
+
+        let result = server.purge_group(params(serde_json::json!({ "group": "g" }))).await.unwrap();
+        let structured = serde_json::to_value(&result).unwrap()["structuredContent"].clone();
+        assert_eq!(structured["deleted"], serde_json::json!([plain.to_string_lossy()]));
+        assert_eq!(structured["failed"].as_array().unwrap().len(), 1);
+        assert_eq!(trashed(&tmp), ["stuck"]);
+        let members = server.undo_groups.members("g").unwrap();
+        assert_eq!(members.len(), 1);
+        assert_eq!(members[0].original, stuck);
+    }
+
+    // `#[tokio::test]` runs on the single-threaded runtime, where the
+    // workers' old `block_in_place` panicked
+    #[tokio::test]
+    async fn batch_from_file_trashes_with_workers_on_any_runtime() {
+        let tmp = TempDir::new("batch-file");
+        let paths: Vec<String> = (0..5)
+            .map(|i| tmp.file(&format!("work/f{}.txt", i), "x").to_string_lossy().into_owned())
+            .collect();
+        let list = tmp.file("list.json", &serde_json::to_string(&paths).unwrap());
+        let server = server(&tmp);
+
+        let args = serde_json::json!({ "file": list, "concurrency": 3 });
+        let result = server.trash_files_from_file(params(args)).await.unwrap();
+        assert_ne!(result.is_error, Some(true), "{}", text(&result));
+        let structured = serde_json::to_value(&result).unwrap()["structuredContent"].clone();
+        assert_eq!(structured["trashed"], 5);
+        assert_eq!(trashed(&tmp), ["f0.txt", "f1.txt", "f2.txt", "f3.txt", "f4.txt"]);
+    }
+
+    #[tokio::test]
+    async fn refused_batch_still_reports_each_entry() {
+        let tmp = TempDir::new("batch-refused");
+        let server = server(&tmp);
+        let gone = tmp.path().join("gone.txt").to_string_lossy().into_owned();
+
+        // The working directory is always refused
+        let result = server
+            .trash_files(params(serde_json::json!({ "paths": [".", gone] })))
+            .await
+            .unwrap();
+        assert!(text(&result).starts_with("Refusing to trash:"), "{}", text(&result));
+        let structured = serde_json::to_value(&result).unwrap()["structuredContent"].clone();
+        assert_eq!(structured["outcome"], "refused");
+        let reasons: Vec<&str> = structured["skipped"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|entry| entry["reason"].as_str().unwrap())
+            .collect();
+        assert_eq!(reasons, ["not_found", "working_directory"]);
+        assert!(trashed(&tmp).is_empty());
+    }
+
+    #[tokio::test]
+    async fn batch_of_missing_paths_keeps_them_in_the_structured_result() {
+        let tmp = TempDir::new("batch-missing");
+        let server = server(&tmp);
+        let gone = tmp.path().join("gone.txt").to_string_lossy().into_owned();
+
+        for report_missing in [true, false] {
+            let result = server
+                .trash_files(params(serde_json::json!({ "paths": [gone], "report_missing": report_missing })))
+                .await
+                .unwrap();
+            assert_eq!(text(&result).contains("Skipped (not found)"), report_missing, "{}", text(&result));
+            let structured = serde_json::to_value(&result).unwrap()["structuredContent"].clone();
+            assert_eq!(structured["outcome"], "nothing_to_do");
+            assert_eq!(structured["missing"], serde_json::json!([gone]));
+            assert_eq!(structured["skipped"][0]["reason"], "not_found");
+        }
+    }
+}
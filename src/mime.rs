@@ -0,0 +1,135 @@
+//! Best-effort MIME types for trashed items, from the name's extension or,
+//! for names without a known one, from the first bytes of the content.
+
+use std::io::{self, Read};
+use std::path::Path;
+
+/// MIME type for the extension of `name`, if it's a common one.
+pub fn by_extension(name: &Path) -> Option<&'static str> {
+    let extension = name.extension()?.to_str()?.to_ascii_lowercase();
+    Some(match extension.as_str() {
+        "txt" | "log" => "text/plain",
+        "md" | "markdown" => "text/markdown",
+        "csv" => "text/csv",
+        "html" | "htm" => "text/html",
+        "css" => "text/css",
+        "js" | "mjs" => "text/javascript",
+        "json" => "application/json",
+        "xml" => "application/xml",
+        "yaml" | "yml" => "application/yaml",
+        "toml" => "application/toml",
+        "sh" => "application/x-sh",
+        "py" => "text/x-python",
+        "rs" => "text/x-rust",
+        "c" | "h" => "text/x-c",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "bmp" => "image/bmp",
+        "ico" => "image/vnd.microsoft.icon",
+        "tif" | "tiff" => "image/tiff",
+        "heic" => "image/heic",
+        "mp3" => "audio/mpeg",
+        "wav" => "audio/wav",
+        "flac" => "audio/flac",
+        "ogg" => "audio/ogg",
+        "m4a" => "audio/mp4",
+        "mp4" | "m4v" => "video/mp4",
+        "mkv" => "video/x-matroska",
+        "webm" => "video/webm",
+        "mov" => "video/quicktime",
+        "avi" => "video/x-msvideo",
+        "pdf" => "application/pdf",
+        "doc" => "application/msword",
+        "docx" => "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+        "xls" => "application/vnd.ms-excel",
+        "xlsx" => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        "ppt" => "application/vnd.ms-powerpoint",
+        "pptx" => "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+        "odt" => "application/vnd.oasis.opendocument.text",
+        "zip" => "application/zip",
+        "gz" | "tgz" => "application/gzip",
+        "bz2" => "application/x-bzip2",
+        "xz" => "application/x-xz",
+        "zst" => "application/zstd",
+        "7z" => "application/x-7z-compressed",
+        "tar" => "application/x-tar",
+        "rar" => "application/vnd.rar",
+        "iso" => "application/x-iso9660-image",
+        "exe" | "dll" => "application/vnd.microsoft.portable-executable",
+        "wasm" => "application/wasm",
+        "sqlite" | "db" => "application/vnd.sqlite3",
+        _ => return None,
+    })
+}
+
+/// MIME type from the magic bytes at the start of the file at `path`.
+///
+/// Directories are `inode/directory`; content that matches no known format
+/// is `text/plain` when it is valid UTF-8 and `application/octet-stream`
+/// otherwise.
+pub fn sniff(path: &Path) -> io::Result<&'static str> {
+    if path.symlink_metadata()?.is_dir() {
+        return Ok("inode/directory");
+    }
+    let mut head = [0u8; 512];
+    let mut file = std::fs::File::open(path)?;
+    let mut len = 0;
+    while len < head.len() {
+        match file.read(&mut head[len..])? {
+            0 => break,
+            n => len += n,
+        }
+    }
+    let head = &head[..len];
+
+    const MAGIC: &[(&[u8], &str)] = &[
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xff\xd8\xff", "image/jpeg"),
+        (b"GIF87a", "image/gif"),
+        (b"GIF89a", "image/gif"),
+        (b"%PDF-", "application/pdf"),
+        (b"PK\x03\x04", "application/zip"),
+        (b"\x1f\x8b", "application/gzip"),
+        (b"BZh", "application/x-bzip2"),
+        (b"\xfd7zXZ\x00", "application/x-xz"),
+        (b"\x28\xb5\x2f\xfd", "application/zstd"),
+        (b"7z\xbc\xaf\x27\x1c", "application/x-7z-compressed"),
+        (b"Rar!\x1a\x07", "application/vnd.rar"),
+        (b"\x7fELF", "application/x-executable"),
+        (b"MZ", "application/vnd.microsoft.portable-executable"),
+        (b"\x00asm", "application/wasm"),
+        (b"SQLite format 3\x00", "application/vnd.sqlite3"),
+        (b"ID3", "audio/mpeg"),
+        (b"fLaC", "audio/flac"),
+        (b"OggS", "audio/ogg"),
+        (b"\x1a\x45\xdf\xa3", "video/x-matroska"),
+        (b"#!", "text/x-shellscript"),
+    ];
+    if let Some(&(_, mime)) = MAGIC.iter().find(|(magic, _)| head.starts_with(magic)) {
+        return Ok(mime);
+    }
+    // RIFF and ISO media containers name their format a few bytes in
+    match (head.get(..4), head.get(8..12)) {
+        (Some(b"RIFF"), Some(b"WEBP")) => return Ok("image/webp"),
+        (Some(b"RIFF"), Some(b"WAVE")) => return Ok("audio/wav"),
+        (Some(b"RIFF"), Some(b"AVI ")) => return Ok("video/x-msvideo"),
+        _ => {}
+    }
+    if head.get(4..8) == Some(b"ftyp") {
+        return Ok(match head.get(8..12) {
+            Some(b"heic" | b"heix" | b"mif1") => "image/heic",
+            Some(b"M4A ") => "audio/mp4",
+            Some(b"qt  ") => "video/quicktime",
+            _ => "video/mp4",
+        });
+    }
+    // A multi-byte character may be cut off at the end of the sample
+    let text = match std::str::from_utf8(head) {
+        Ok(_) => true,
+        Err(e) => e.error_len().is_none(),
+    };
+    Ok(if text && !head.contains(&0) { "text/plain" } else { "application/octet-stream" })
+}
@@ -0,0 +1,236 @@
+//! Interpreting path arguments from clients.
+
+use std::path::{Component, Path, PathBuf};
+
+/// Expand a leading `~` and `$VAR` / `${VAR}` references.
+///
+/// Unknown variables are left as written so the caller sees what didn't resolve.
+pub fn expand(input: &str) -> PathBuf {
+    let mut out = String::with_capacity(input.len());
+
+    let rest = match input.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with(['/', '\\']) => match home_dir() {
+            Some(home) => {
+                out.push_str(&home.to_string_lossy());
+                rest
+            }
+            None => input,
+        },
+        _ => input,
+    };
+
+    let mut chars = rest.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        if c != '$' {
+            out.push(c);
+            continue;
+        }
+        let tail = &rest[i + 1..];
+        let (name, consumed) = if let Some(braced) = tail.strip_prefix('{') {
+            match braced.find('}') {
+                Some(end) => (&braced[..end], end + 2),
+                None => ("", 0),
+            }
+        } else {
+            let end = tail
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(tail.len());
+            (&tail[..end], end)
+        };
+        match (!name.is_empty()).then(|| std::env::var(name).ok()).flatten() {
+            Some(value) => {
+                out.push_str(&value);
+                for _ in 0..consumed {
+                    chars.next();
+                }
+            }
+            None => out.push('$'),
+        }
+    }
+
+    PathBuf::from(out)
+}
+
+/// Lexically normalize `path` so equivalent spellings compare equal.
+///
+/// Redundant and trailing separators and `.` components are dropped, and on
+/// Windows `/` becomes `\`. A `..` removes the preceding component unless that
+/// component is a symlink, in which case the link is resolved first so `..`
+/// goes where the filesystem would take it.
+pub fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match out.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    if out.symlink_metadata().is_ok_and(|m| m.file_type().is_symlink()) {
+                        if let Ok(target) = out.canonicalize() {
+                            out = target;
+                        }
+                    }
+                    out.pop();
+                }
+                // `..` at the root stays at the root
+                Some(Component::RootDir) | Some(Component::Prefix(_)) => {}
+                _ => out.push(".."),
+            },
+            other => out.push(other.as_os_str()),
+        }
+    }
+    if out.as_os_str().is_empty() {
+        out.push(".");
+    }
+    out
+}
+
+/// [`normalize`]d `path` with its longest existing prefix canonicalized, so
+/// a path that doesn't exist yet still sees through symlinked ancestors.
+pub fn canonicalize_existing(path: &Path) -> PathBuf {
+    let path = normalize(path);
+    for ancestor in path.ancestors().filter(|a| !a.as_os_str().is_empty()) {
+        if let Ok(canonical) = ancestor.canonicalize() {
+            return match path.strip_prefix(ancestor) {
+                Ok(rest) if !rest.as_os_str().is_empty() => canonical.join(rest),
+                _ => canonical,
+            };
+        }
+    }
+    path
+}
+
+/// Absolute form of `path` with its parent canonicalized as far as it exists.
+///
+/// The final component is kept as-is, so a symlink resolves to the link itself
+/// rather than its target, which matches what gets trashed.
+pub fn resolve(path: &Path) -> PathBuf {
+    let absolute = if path.is_relative() {
+        std::env::current_dir()
+            .map(|cwd| cwd.join(path))
+            .unwrap_or_else(|_| path.to_path_buf())
+    } else {
+        path.to_path_buf()
+    };
+    let absolute = normalize(&absolute);
+    match (absolute.parent(), absolute.file_name()) {
+        (Some(parent), Some(name)) => canonicalize_existing(parent).join(name),
+        _ => absolute.canonicalize().unwrap_or(absolute),
+    }
+}
+
+/// Whether the platform's default filesystems ignore case in file names.
+pub const CASE_INSENSITIVE: bool = cfg!(any(windows, target_os = "macos"));
+
+/// `path` in the form used for comparisons: lowercased where
+/// [`CASE_INSENSITIVE`], unchanged elsewhere.
+pub fn fold_case(path: &Path) -> PathBuf {
+    if CASE_INSENSITIVE {
+        PathBuf::from(path.to_string_lossy().to_lowercase())
+    } else {
+        path.to_path_buf()
+    }
+}
+
+/// Whether `path` is `base` or lies below it, ignoring case where the
+/// platform does.
+pub fn starts_with(path: &Path, base: &Path) -> bool {
+    fold_case(path).starts_with(fold_case(base))
+}
+
+pub fn home_dir() -> Option<PathBuf> {
+    let var = if cfg!(windows) { "USERPROFILE" } else { "HOME" };
+    std::env::var_os(var).filter(|h| !h.is_empty()).map(PathBuf::from)
+}
+
+/// Percent-encode a file name's raw bytes so names that aren't valid UTF-8
+/// survive a round trip through JSON. Printable ASCII other than `%` is kept.
+#[cfg(feature = "full")]
+pub fn encode_name(name: &std::ffi::OsStr) -> String {
+    let mut out = String::new();
+    for &byte in name.as_encoded_bytes() {
+        if byte.is_ascii_graphic() && byte != b'%' {
+            out.push(byte as char);
+        } else {
+            out.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    out
+}
+
+/// Raw bytes of a name produced by [`encode_name`], or `None` if malformed.
+#[cfg(feature = "full")]
+pub fn decode_name(encoded: &str) -> Option<Vec<u8>> {
+    let bytes = encoded.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = encoded.get(i + 1..i + 3)?;
+            out.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trailing_separator_is_dropped() {
+        assert_eq!(normalize(Path::new("/data/dir/")), normalize(Path::new("/data/dir")));
+        assert_eq!(normalize(Path::new("/data/dir///")), PathBuf::from("/data/dir"));
+    }
+
+    #[test]
+    fn doubled_separators_and_cur_dir_are_dropped() {
+        assert_eq!(normalize(Path::new("/data//dir")), PathBuf::from("/data/dir"));
+        assert_eq!(normalize(Path::new("/data/./dir/.")), PathBuf::from("/data/dir"));
+        assert_eq!(normalize(Path::new("./dir")), PathBuf::from("dir"));
+        assert_eq!(normalize(Path::new(".")), PathBuf::from("."));
+    }
+
+    #[test]
+    fn parent_dir_is_collapsed_lexically() {
+        assert_eq!(normalize(Path::new("/data/a/../b")), PathBuf::from("/data/b"));
+        assert_eq!(normalize(Path::new("/..")), PathBuf::from("/"));
+        assert_eq!(normalize(Path::new("../a")), PathBuf::from("../a"));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn forward_and_back_slashes_are_equivalent() {
+        assert_eq!(normalize(Path::new(r"C:\data/dir\")), PathBuf::from(r"C:\data\dir"));
+        assert_eq!(normalize(Path::new("C:/data/dir")), normalize(Path::new(r"C:\data\dir")));
+    }
+
+    #[cfg(any(windows, target_os = "macos"))]
+    #[test]
+    fn comparisons_ignore_case() {
+        assert_eq!(fold_case(Path::new("Data/File.TXT")), fold_case(Path::new("data/file.txt")));
+        assert!(starts_with(Path::new("Data/Sub/File.txt"), Path::new("data/SUB")));
+        assert!(!starts_with(Path::new("Data/Subway"), Path::new("data/sub")));
+    }
+
+    #[cfg(not(any(windows, target_os = "macos")))]
+    #[test]
+    fn comparisons_respect_case() {
+        assert_ne!(fold_case(Path::new("Data/File.TXT")), fold_case(Path::new("data/file.txt")));
+        assert!(!starts_with(Path::new("Data/Sub/File.txt"), Path::new("data/sub")));
+        assert!(starts_with(Path::new("data/sub/file.txt"), Path::new("data/sub")));
+    }
+
+    #[test]
+    fn resolve_treats_equivalent_spellings_alike() {
+        let tmp = crate::testutil::TempDir::new("resolve");
+        tmp.dir("dir");
+        let plain = resolve(&tmp.path().join("dir"));
+        for spelling in ["dir/", "dir//", "./dir", "dir/.", "other/../dir"] {
+            assert_eq!(resolve(&tmp.path().join(spelling)), plain, "{}", spelling);
+        }
+    }
+}
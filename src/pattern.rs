@@ -0,0 +1,70 @@
+//! Name and path matching for bulk tools.
+
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchKind {
+    /// The text appears anywhere.
+    #[default]
+    Substring,
+    /// The whole text is equal.
+    Exact,
+    /// Shell-style wildcards: `*` matches any run of characters, `?` one character.
+    Glob,
+}
+
+/// A case-insensitive pattern of a given kind.
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    kind: MatchKind,
+    pattern: Vec<char>,
+}
+
+impl Pattern {
+    pub fn new(pattern: &str, kind: MatchKind) -> Self {
+        Self {
+            kind,
+            pattern: pattern.to_lowercase().chars().collect(),
+        }
+    }
+
+    pub fn matches(&self, text: &str) -> bool {
+        let text: Vec<char> = text.to_lowercase().chars().collect();
+        match self.kind {
+            MatchKind::Substring => {
+                self.pattern.is_empty() || text.windows(self.pattern.len()).any(|w| w == self.pattern)
+            }
+            MatchKind::Exact => text == self.pattern,
+            MatchKind::Glob => glob_match(&self.pattern, &text),
+        }
+    }
+}
+
+/// Wildcard match with backtracking to the most recent `*`.
+fn glob_match(pattern: &[char], text: &[char]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                Some((sp, st)) => {
+                    p = sp + 1;
+                    t = st + 1;
+                    star = Some((sp, st + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
@@ -0,0 +1,77 @@
+//! Named scopes: a base directory plus extra protected paths per project.
+//!
+//! Profiles are loaded at startup from the JSON file named by
+//! `RMCP_TRASH_PROFILES`, shaped like:
+//!
+//! ```json
+//! { "web": { "base_dir": "~/projects/web", "protected": [".git", "deploy"] } }
+//! ```
+//!
+//! Relative `protected` entries are resolved against the profile's base dir.
+
+use crate::guard::{ProtectedPaths, RuleSource};
+use crate::paths;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Deserialize)]
+struct ProfileSpec {
+    base_dir: String,
+    #[serde(default)]
+    protected: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Profile {
+    pub base_dir: PathBuf,
+    pub protected: ProtectedPaths,
+}
+
+/// What a tool call may touch: an optional base directory and the protected rules.
+#[derive(Debug, Clone, Copy)]
+pub struct Scope<'a> {
+    pub name: Option<&'a str>,
+    pub base_dir: Option<&'a Path>,
+    pub protected: &'a ProtectedPaths,
+}
+
+impl Scope<'_> {
+    /// Expand `input`, anchor relative paths at the base dir, and normalize.
+    pub fn path(&self, input: &str) -> PathBuf {
+        let path = paths::expand(input);
+        let path = match self.base_dir {
+            Some(base) if path.is_relative() => base.join(path),
+            _ => path,
+        };
+        paths::normalize(&path)
+    }
+
+    /// Whether a resolved path falls inside this scope.
+    pub fn contains(&self, resolved: &Path) -> bool {
+        self.base_dir.is_none_or(|base| paths::starts_with(resolved, base))
+    }
+}
+
+/// Load profiles, layering each profile's rules on top of `global`.
+pub fn load(file: &Path, global: &ProtectedPaths) -> anyhow::Result<HashMap<String, Profile>> {
+    let contents = std::fs::read_to_string(file)?;
+    let specs: HashMap<String, ProfileSpec> = serde_json::from_str(&contents)?;
+
+    let mut profiles = HashMap::new();
+    for (name, spec) in specs {
+        let base_dir = paths::expand(&spec.base_dir);
+        let base_dir = base_dir.canonicalize().map_err(|e| {
+            anyhow::anyhow!("Profile '{}': base_dir {}: {}", name, base_dir.display(), e)
+        })?;
+
+        let mut protected = global.clone();
+        for entry in &spec.protected {
+            let path = paths::expand(entry);
+            let path = if path.is_relative() { base_dir.join(path) } else { path };
+            protected.add(path, RuleSource::Profile);
+        }
+        profiles.insert(name, Profile { base_dir, protected });
+    }
+    Ok(profiles)
+}
@@ -0,0 +1,66 @@
+//! Progress notifications for long directory walks.
+//!
+//! Sent only when the client asked for them by passing a `progressToken` in
+//! the request's `_meta`; otherwise every call here does nothing.
+//!
+//! Walks that report run on tokio's blocking pool, so the task forwarding
+//! the notifications keeps running while they walk.
+
+use rmcp::model::{ProgressNotificationParam, ProgressToken};
+use rmcp::service::RequestContext;
+use rmcp::RoleServer;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+/// Least time between two notifications.
+const INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug)]
+pub struct Progress {
+    target: Option<(ProgressToken, mpsc::UnboundedSender<ProgressNotificationParam>)>,
+    files: usize,
+    last: Instant,
+}
+
+impl Progress {
+    /// Reporter for the call behind `context`, sending in order through
+    /// one forwarding task.
+    pub fn new(context: &RequestContext<RoleServer>) -> Self {
+        let target = context.meta.get_progress_token().map(|token| {
+            let (tx, mut rx) = mpsc::unbounded_channel();
+            let peer = context.peer.clone();
+            tokio::spawn(async move {
+                while let Some(param) = rx.recv().await {
+                    if peer.notify_progress(param).await.is_err() {
+                        break;
+                    }
+                }
+            });
+            (token, tx)
+        });
+        Self {
+            target,
+            files: 0,
+            last: Instant::now(),
+        }
+    }
+
+    /// Count one more file scanned, reporting the total at most once per
+    /// [`INTERVAL`].
+    pub fn file_scanned(&mut self) {
+        let Some((token, tx)) = &self.target else {
+            return;
+        };
+        self.files += 1;
+        if self.last.elapsed() < INTERVAL {
+            return;
+        }
+        self.last = Instant::now();
+        let _ = tx.send(ProgressNotificationParam {
+            progress_token: token.clone(),
+            progress: self.files as f64,
+            total: None,
+            message: Some(format!("{} files scanned", self.files)),
+        });
+    }
+}
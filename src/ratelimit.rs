@@ -0,0 +1,116 @@
+//! Token-bucket limit on mutating tool calls (`RMCP_TRASH_RATE_LIMIT`).
+
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// At most `calls` mutating calls per `per`, allowing bursts up to `calls`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    pub calls: u32,
+    pub per: Duration,
+}
+
+impl RateLimit {
+    /// Parse `N/s`, `N/min` or `N/h`; a bare `N` means per minute.
+    pub fn parse(value: &str) -> Option<Self> {
+        let (calls, unit) = value.trim().split_once('/').unwrap_or((value.trim(), "min"));
+        let per = match unit.trim() {
+            "s" | "sec" => Duration::from_secs(1),
+            "m" | "min" => Duration::from_secs(60),
+            "h" | "hour" => Duration::from_secs(3_600),
+            _ => return None,
+        };
+        let calls = calls.trim().parse().ok().filter(|&n: &u32| n > 0)?;
+        Some(Self { calls, per })
+    }
+}
+
+impl fmt::Display for RateLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let unit = match self.per.as_secs() {
+            1 => "s",
+            60 => "min",
+            _ => "h",
+        };
+        write!(f, "{}/{}", self.calls, unit)
+    }
+}
+
+#[derive(Debug)]
+pub struct TokenBucket {
+    limit: RateLimit,
+    tokens: f64,
+    last: Instant,
+}
+
+impl TokenBucket {
+    pub fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            tokens: limit.calls as f64,
+            last: Instant::now(),
+        }
+    }
+
+    pub fn limit(&self) -> RateLimit {
+        self.limit
+    }
+
+    /// Take a token, or return how long until one is available.
+    pub fn try_take(&mut self) -> Result<(), Duration> {
+        let rate = self.limit.calls as f64 / self.limit.per.as_secs_f64();
+        let now = Instant::now();
+        let refill = now.duration_since(self.last).as_secs_f64() * rate;
+        self.tokens = (self.tokens + refill).min(self.limit.calls as f64);
+        self.last = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - self.tokens) / rate))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_counts_per_unit() {
+        let limit = |calls, secs| Some(RateLimit { calls, per: Duration::from_secs(secs) });
+        assert_eq!(RateLimit::parse("30/min"), limit(30, 60));
+        assert_eq!(RateLimit::parse("5"), limit(5, 60));
+        assert_eq!(RateLimit::parse(" 2 / s "), limit(2, 1));
+        assert_eq!(RateLimit::parse("100/hour"), limit(100, 3_600));
+        assert_eq!(RateLimit::parse("30/min").unwrap().to_string(), "30/min");
+    }
+
+    #[test]
+    fn rejects_zero_and_junk() {
+        for value in ["0/s", "0", "", "-1/min", "ten/min", "5/day", "5/", "/min", "1.5/s"] {
+            assert_eq!(RateLimit::parse(value), None, "{value:?}");
+        }
+    }
+
+    #[test]
+    fn bucket_allows_a_burst_then_denies_until_refilled() {
+        let mut bucket = TokenBucket::new(RateLimit::parse("2/s").unwrap());
+        assert_eq!(bucket.try_take(), Ok(()));
+        assert_eq!(bucket.try_take(), Ok(()));
+        let wait = bucket.try_take().unwrap_err();
+        assert!(wait > Duration::ZERO && wait <= Duration::from_millis(500), "{wait:?}");
+
+        // Half a second at 2/s refills one token
+        bucket.last -= Duration::from_millis(500);
+        assert_eq!(bucket.try_take(), Ok(()));
+        assert!(bucket.try_take().is_err());
+
+        // A long pause refills only up to the burst size
+        bucket.last -= Duration::from_secs(60);
+        assert_eq!(bucket.try_take(), Ok(()));
+        assert_eq!(bucket.try_take(), Ok(()));
+        assert!(bucket.try_take().is_err());
+    }
+}
@@ -0,0 +1,267 @@
+//! Periodic purge of old trash items (`RMCP_TRASH_SCHEDULE`).
+//!
+//! The schedule is `<age>` or `<age>/<interval>`, e.g. `30d` or `30d/6h`:
+//! every interval (default 6 h, first run at startup) items deleted longer
+//! ago than the age are purged. Items whose original path is protected are
+//! never purged. Items are purged one at a time, so one that can't be
+//! deleted only shows up in the run's report; it doesn't hold back the rest.
+
+use std::fmt;
+use std::time::Duration;
+
+#[cfg(feature = "full")]
+use crate::backend::Backend;
+#[cfg(feature = "full")]
+use crate::guard::ProtectedPaths;
+#[cfg(feature = "full")]
+use serde::Serialize;
+#[cfg(feature = "full")]
+use std::sync::{Arc, Mutex};
+
+/// Interval used when the schedule only gives an age.
+pub const DEFAULT_EVERY: Duration = Duration::from_secs(6 * 3_600);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Schedule {
+    /// Items deleted longer ago than this are purged.
+    pub max_age: Duration,
+    pub every: Duration,
+}
+
+impl Schedule {
+    /// Parse `<age>[/<interval>]`, each a number with unit `s`, `m`, `h` or `d`.
+    pub fn parse(value: &str) -> Option<Self> {
+        let (age, every) = match value.trim().split_once('/') {
+            Some((age, every)) => (age, Some(every)),
+            None => (value.trim(), None),
+        };
+        Some(Self {
+            max_age: parse_duration(age)?,
+            every: every.map_or(Some(DEFAULT_EVERY), parse_duration)?,
+        })
+    }
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", show_duration(self.max_age), show_duration(self.every))
+    }
+}
+
+/// `90s`, `15m`, `12h` or `30d`; zero is rejected.
+fn parse_duration(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    let split = value.find(|c: char| !c.is_ascii_digit())?;
+    let (count, unit) = value.split_at(split);
+    let unit_secs = match unit.trim() {
+        "s" | "sec" => 1,
+        "m" | "min" => 60,
+        "h" | "hour" => 3_600,
+        "d" | "day" | "days" => 86_400,
+        _ => return None,
+    };
+    let count: u64 = count.parse().ok().filter(|&n| n > 0)?;
+    count.checked_mul(unit_secs).map(Duration::from_secs)
+}
+
+/// The largest unit that divides `duration` evenly, so `parse_duration` reads it back.
+fn show_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    match [(86_400, "d"), (3_600, "h"), (60, "m")].into_iter().find(|&(unit, _)| secs.is_multiple_of(unit)) {
+        Some((unit, label)) => format!("{}{}", secs / unit, label),
+        None => format!("{}s", secs),
+    }
+}
+
+#[cfg(feature = "full")]
+#[derive(Debug, Clone, Serialize)]
+pub struct RunReport {
+    /// Unix time the run started.
+    pub at: i64,
+    pub purged: usize,
+    /// Size of the purged items, where the trash reports it.
+    pub bytes: u64,
+    /// Old enough items left alone because their original path is protected.
+    pub skipped_protected: usize,
+    /// Due items that could not be purged, with the error.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub failed: Vec<String>,
+    /// Why the trash could not be listed, if it couldn't.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[cfg(feature = "full")]
+#[derive(Debug, Serialize)]
+pub struct SchedulerStatus {
+    pub schedule: String,
+    pub max_age_secs: u64,
+    pub every_secs: u64,
+    pub runs: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_run: Option<RunReport>,
+    /// Unix time of the next run.
+    pub next_run: i64,
+}
+
+#[cfg(feature = "full")]
+#[derive(Debug)]
+struct State {
+    runs: u64,
+    last_run: Option<RunReport>,
+    next_run: i64,
+}
+
+#[cfg(feature = "full")]
+#[derive(Debug)]
+pub struct Scheduler {
+    schedule: Schedule,
+    state: Mutex<State>,
+}
+
+#[cfg(feature = "full")]
+impl Scheduler {
+    pub fn new(schedule: Schedule) -> Self {
+        Self {
+            schedule,
+            state: Mutex::new(State {
+                runs: 0,
+                last_run: None,
+                next_run: now(),
+            }),
+        }
+    }
+
+    pub fn status(&self) -> SchedulerStatus {
+        let state = self.state.lock().unwrap();
+        SchedulerStatus {
+            schedule: self.schedule.to_string(),
+            max_age_secs: self.schedule.max_age.as_secs(),
+            every_secs: self.schedule.every.as_secs(),
+            runs: state.runs,
+            last_run: state.last_run.clone(),
+            next_run: state.next_run,
+        }
+    }
+
+    /// Purge the items that are due now.
+    fn run(&self, backend: &Backend, protected: &ProtectedPaths) {
+        let at = now();
+        let mut report = RunReport {
+            at,
+            purged: 0,
+            bytes: 0,
+            skipped_protected: 0,
+            failed: Vec::new(),
+            error: None,
+        };
+        let cutoff = at.saturating_sub(self.schedule.max_age.as_secs() as i64);
+        match backend.list() {
+            Ok(items) => {
+                let (kept, due): (Vec<_>, Vec<_>) = items
+                    .into_iter()
+                    .filter(|item| item.time_deleted < cutoff)
+                    .partition(|item| protected.check(&item.original_path()).is_some());
+                report.skipped_protected = kept.len();
+                for item in due {
+                    let size = backend.metadata(&item).ok().and_then(|meta| meta.size.size());
+                    let original = item.original_path();
+                    match backend.purge_all(vec![item]) {
+                        Ok(()) => {
+                            report.purged += 1;
+                            report.bytes += size.unwrap_or(0);
+                        }
+                        Err(e) => report.failed.push(format!("{}: {}", original.display(), e)),
+                    }
+                }
+            }
+            Err(e) => report.error = Some(e.to_string()),
+        }
+        match &report.error {
+            None => tracing::info!(
+                "Scheduled cleanup purged {} item(s), reclaiming {} ({} protected item(s) kept)",
+                report.purged,
+                crate::format::format_bytes(report.bytes),
+                report.skipped_protected
+            ),
+            Some(e) => tracing::warn!("Scheduled cleanup failed: {}", e),
+        }
+        for failure in &report.failed {
+            tracing::warn!("Scheduled cleanup could not purge {}", failure);
+        }
+
+        let mut state = self.state.lock().unwrap();
+        state.runs += 1;
+        state.last_run = Some(report);
+        state.next_run = at.saturating_add(self.schedule.every.as_secs() as i64);
+    }
+}
+
+#[cfg(feature = "full")]
+fn now() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64)
+}
+
+/// Run the scheduled cleanup for the life of the process, starting now.
+#[cfg(feature = "full")]
+pub fn spawn(scheduler: Arc<Scheduler>, backend: Backend, protected: ProtectedPaths) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(scheduler.schedule.every);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            let scheduler = scheduler.clone();
+            let (backend, protected) = (backend.clone(), protected.clone());
+            let _ = tokio::task::spawn_blocking(move || scheduler.run(&backend, &protected)).await;
+        }
+    });
+}
+
+#[cfg(all(test, feature = "full", target_os = "linux"))]
+mod tests {
+    use super::*;
+    use crate::backend::CustomTrash;
+    use crate::testutil::TempDir;
+
+    /// Make every item in the custom trash look trashed at the epoch.
+    fn backdate(trash: &std::path::Path) {
+        for entry in std::fs::read_dir(trash.join("info")).unwrap() {
+            let path = entry.unwrap().path();
+            let mut sidecar: serde_json::Value =
+                serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+            sidecar["time_deleted"] = 0.into();
+            std::fs::write(&path, serde_json::to_vec(&sidecar).unwrap()).unwrap();
+        }
+    }
+
+    #[test]
+    fn item_that_cannot_be_purged_does_not_hold_back_the_rest() {
+        let tmp = TempDir::new("schedule-run");
+        let backend = Backend::Custom(CustomTrash::open(tmp.path().join("trash")).unwrap());
+        let good = tmp.file("good.txt", "hello");
+        let stuck = tmp.dir("stuck");
+        tmp.dir("stuck/busy");
+        let empty = tmp.dir("empty");
+        backend.delete_all(&[&good, &stuck]).unwrap();
+        backdate(&tmp.path().join("trash"));
+        // A mount point inside the stored directory can't be removed
+        let Some(_mount) = crate::testutil::bind_mount(&empty, &tmp.path().join("trash/files/stuck/busy"))
+        else {
+            return;
+        };
+
+        let scheduler = Scheduler::new(Schedule::parse("1d").unwrap());
+        scheduler.run(&backend, &ProtectedPaths::builtin());
+        let report = scheduler.status().last_run.unwrap();
+        assert_eq!(report.purged, 1);
+        assert_eq!(report.bytes, 5);
+        assert_eq!(report.failed.len(), 1);
+        assert!(report.failed[0].starts_with(&stuck.display().to_string()));
+        assert!(report.error.is_none());
+        let left = backend.list().unwrap();
+        assert_eq!(left.len(), 1);
+        assert_eq!(left[0].original_path(), stuck);
+    }
+}
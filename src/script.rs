@@ -0,0 +1,174 @@
+//! Standalone restore scripts for a batch of trashed items
+//! (`emit_restore_script`).
+//!
+//! The script moves each item's data out of the trash back to its original
+//! path and removes its metadata record, so it works without the server. It
+//! is a shell script on Unix and a PowerShell script on Windows, with paths
+//! embedded in single quotes.
+
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+
+/// One item to put back.
+#[derive(Debug)]
+pub struct Step {
+    /// The item's data inside the trash.
+    pub data: PathBuf,
+    /// The record that keeps it listed, removed once the data is back.
+    pub info: Option<PathBuf>,
+    pub original: PathBuf,
+}
+
+/// The script for `steps`, for the platform the server runs on.
+pub fn render(steps: &[Step]) -> Vec<u8> {
+    if cfg!(windows) {
+        powershell(steps)
+    } else {
+        sh(steps)
+    }
+}
+
+fn sh(steps: &[Step]) -> Vec<u8> {
+    let mut out = format!(
+        "#!/bin/sh\n\
+         # Restores {} item(s) trashed by rmcp-trash to their original paths.\n\
+         # Items no longer in the trash, or whose path is taken again, are skipped.\n\
+         restore() {{\n\
+         \x20   if [ ! -e \"$1\" ] && [ ! -L \"$1\" ]; then echo \"not in trash, skipped: $3\" >&2; return; fi\n\
+         \x20   if [ -e \"$3\" ] || [ -L \"$3\" ]; then echo \"path exists, skipped: $3\" >&2; return; fi\n\
+         \x20   mkdir -p -- \"$(dirname -- \"$3\")\" && mv -- \"$1\" \"$3\" && {{ [ -z \"$2\" ] || rm -f -- \"$2\"; }}\n\
+         }}\n",
+        steps.len()
+    )
+    .into_bytes();
+    for step in steps {
+        out.extend_from_slice(b"restore ");
+        sh_quote(step.data.as_os_str(), &mut out);
+        out.push(b' ');
+        sh_quote(step.info.as_deref().map_or(OsStr::new(""), Path::as_os_str), &mut out);
+        out.push(b' ');
+        sh_quote(step.original.as_os_str(), &mut out);
+        out.push(b'\n');
+    }
+    out
+}
+
+/// Single-quote for sh: everything is literal except `'`, written as `'\''`.
+fn sh_quote(value: &OsStr, out: &mut Vec<u8>) {
+    out.push(b'\'');
+    for &byte in value.as_encoded_bytes() {
+        if byte == b'\'' {
+            out.extend_from_slice(b"'\\''");
+        } else {
+            out.push(byte);
+        }
+    }
+    out.push(b'\'');
+}
+
+fn powershell(steps: &[Step]) -> Vec<u8> {
+    // The BOM makes Windows PowerShell 5 read the file as UTF-8
+    let mut out = format!(
+        "\u{feff}# Restores {} item(s) trashed by rmcp-trash to their original paths.\r\n\
+         # Items no longer in the trash, or whose path is taken again, are skipped.\r\n\
+         function Restore-Item($Data, $Info, $Original) {{\r\n\
+         \x20   if (-not (Test-Path -LiteralPath $Data)) {{ Write-Warning \"not in trash, skipped: $Original\"; return }}\r\n\
+         \x20   if (Test-Path -LiteralPath $Original) {{ Write-Warning \"path exists, skipped: $Original\"; return }}\r\n\
+         \x20   [void][System.IO.Directory]::CreateDirectory((Split-Path -LiteralPath $Original -Parent))\r\n\
+         \x20   Move-Item -LiteralPath $Data -Destination $Original -ErrorAction Stop\r\n\
+         \x20   if ($Info) {{ Remove-Item -LiteralPath $Info -Force }}\r\n\
+         }}\r\n",
+        steps.len()
+    );
+    for step in steps {
+        out.push_str("Restore-Item ");
+        ps_quote(step.data.as_os_str(), &mut out);
+        out.push(' ');
+        ps_quote(step.info.as_deref().map_or(OsStr::new(""), Path::as_os_str), &mut out);
+        out.push(' ');
+        ps_quote(step.original.as_os_str(), &mut out);
+        out.push_str("\r\n");
+    }
+    out.into_bytes()
+}
+
+/// Single-quote for PowerShell, which also treats the typographic single
+/// quotes as quote characters; each is escaped by doubling it.
+fn ps_quote(value: &OsStr, out: &mut String) {
+    out.push('\'');
+    for c in value.to_string_lossy().chars() {
+        if matches!(c, '\'' | '\u{2018}' | '\u{2019}' | '\u{201a}' | '\u{201b}') {
+            out.push(c);
+        }
+        out.push(c);
+    }
+    out.push('\'');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A step whose paths hold quotes, spaces, a newline and a command substitution.
+    fn awkward_step() -> Step {
+        Step {
+            data: PathBuf::from("/trash/files/it's here"),
+            info: Some(PathBuf::from("/trash/info/$(touch pwned)\n.trashinfo")),
+            original: PathBuf::from("/home/u/don\u{2019}t `x` $HOME"),
+        }
+    }
+
+    fn lines_after_header(script: &[u8], header_end: &str) -> String {
+        let text = String::from_utf8(script.to_vec()).unwrap();
+        let (_, calls) = text.split_once(header_end).unwrap();
+        calls.to_string()
+    }
+
+    #[test]
+    fn sh_paths_are_single_quoted_literally() {
+        let steps = [
+            awkward_step(),
+            Step {
+                data: PathBuf::from("/trash/files/plain"),
+                info: None,
+                original: PathBuf::from("/home/u/plain"),
+            },
+        ];
+        assert_eq!(
+            lines_after_header(&sh(&steps), "\n}\n"),
+            "restore '/trash/files/it'\\''s here' '/trash/info/$(touch pwned)\n.trashinfo' '/home/u/don\u{2019}t `x` $HOME'\n\
+             restore '/trash/files/plain' '' '/home/u/plain'\n"
+        );
+    }
+
+    #[test]
+    fn powershell_doubles_every_single_quote() {
+        assert_eq!(
+            lines_after_header(&powershell(&[awkward_step()]), "\r\n}\r\n"),
+            "Restore-Item '/trash/files/it''s here' '/trash/info/$(touch pwned)\n.trashinfo' '/home/u/don\u{2019}\u{2019}t `x` $HOME'\r\n"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn sh_script_parses_and_restores_awkward_names() {
+        use crate::testutil::TempDir;
+
+        let tmp = TempDir::new("script");
+        let name = "it's a \"$(touch pwned)\"\nname\u{2019}";
+        let data = tmp.file(&format!("trash/{name}"), "x");
+        let info = tmp.file("trash/record", "");
+        let original = tmp.path().join("home").join(name);
+        let script = tmp.path().join("restore.sh");
+        std::fs::write(&script, sh(&[Step { data: data.clone(), info: Some(info.clone()), original: original.clone() }]))
+            .unwrap();
+
+        let check = std::process::Command::new("sh").arg("-n").arg(&script).status().unwrap();
+        assert!(check.success());
+        let run = std::process::Command::new("sh").arg(&script).current_dir(tmp.path()).status().unwrap();
+        assert!(run.success());
+        assert_eq!(std::fs::read_to_string(&original).unwrap(), "x");
+        assert!(!data.exists() && !info.exists());
+        assert!(!tmp.path().join("pwned").exists());
+    }
+}
@@ -0,0 +1,160 @@
+//! End-to-end check that trashing (and, where supported, restoring) works.
+//!
+//! Uses a uniquely named file in the temp dir and always cleans up after
+//! itself, so it is safe to run repeatedly.
+
+use crate::backend::Backend;
+use serde::Serialize;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StepStatus {
+    Ok,
+    Failed,
+    Skipped,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Step {
+    pub name: &'static str,
+    pub status: StepStatus,
+    pub detail: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SelfTestReport {
+    pub passed: bool,
+    pub steps: Vec<Step>,
+}
+
+impl SelfTestReport {
+    fn step(&mut self, name: &'static str, status: StepStatus, detail: impl Into<String>) {
+        if status == StepStatus::Failed {
+            self.passed = false;
+        }
+        self.steps.push(Step {
+            name,
+            status,
+            detail: detail.into(),
+        });
+    }
+
+    /// Human-readable summary, one line per step.
+    pub fn to_text(&self) -> String {
+        let mut text = format!(
+            "Self-test {}",
+            if self.passed { "passed" } else { "FAILED" }
+        );
+        for step in &self.steps {
+            let status = match step.status {
+                StepStatus::Ok => "ok",
+                StepStatus::Failed => "FAILED",
+                StepStatus::Skipped => "skipped",
+            };
+            text.push_str(&format!("\n  [{}] {}: {}", status, step.name, step.detail));
+        }
+        text
+    }
+}
+
+pub fn run(backend: &Backend) -> SelfTestReport {
+    let mut report = SelfTestReport {
+        passed: true,
+        steps: Vec::new(),
+    };
+
+    let path = probe_path();
+    if let Err(e) = fs::write(&path, b"rmcp-trash self-test\n") {
+        report.step("create", StepStatus::Failed, format!("{}: {}", path.display(), e));
+        return report;
+    }
+    // Resolve symlinked temp dirs (e.g. /tmp -> /private/tmp) so the path
+    // matches what the trash records as the original location.
+    let path = path.canonicalize().unwrap_or(path);
+    report.step("create", StepStatus::Ok, path.display().to_string());
+
+    if let Err(e) = backend.delete(&path) {
+        report.step("trash", StepStatus::Failed, e.to_string());
+        let _ = fs::remove_file(&path);
+        return report;
+    }
+    report.step("trash", StepStatus::Ok, "moved to trash");
+
+    if path.exists() {
+        report.step("gone", StepStatus::Failed, "file still exists after trashing");
+        let _ = fs::remove_file(&path);
+        return report;
+    }
+    report.step("gone", StepStatus::Ok, "file no longer at original path");
+
+    if !backend.supports_listing() {
+        report.step("listed", StepStatus::Skipped, "listing not available in this build or on this platform");
+        report.step("restore", StepStatus::Skipped, "restore not available in this build or on this platform");
+        report.step("cleanup", StepStatus::Skipped, "probe file left in the OS trash");
+        return report;
+    }
+
+    #[cfg(feature = "full")]
+    verify_restore(backend, &path, &mut report);
+    report
+}
+
+/// List, restore and clean up the probe that `run` trashed.
+#[cfg(feature = "full")]
+fn verify_restore(backend: &Backend, path: &std::path::Path, report: &mut SelfTestReport) {
+    let item = match backend.list() {
+        Ok(items) => items.into_iter().find(|item| item.original_path() == *path),
+        Err(e) => {
+            report.step("listed", StepStatus::Failed, format!("failed to list trash: {}", e));
+            return;
+        }
+    };
+    let Some(item) = item else {
+        report.step(
+            "listed",
+            StepStatus::Failed,
+            "item not found in trash listing (trash may have degraded to permanent delete)",
+        );
+        return;
+    };
+    report.step("listed", StepStatus::Ok, "item appears in trash listing");
+
+    match backend.restore_all(vec![item.clone()]) {
+        Ok(()) if path.exists() => {
+            report.step("restore", StepStatus::Ok, "restored to original path");
+        }
+        Ok(()) => {
+            report.step("restore", StepStatus::Failed, "restore reported success but file is missing");
+        }
+        Err(e) => {
+            report.step("restore", StepStatus::Failed, e.to_string());
+            // Don't leave the probe behind in the trash.
+            let _ = backend.purge_all(vec![item]);
+            report.step("cleanup", StepStatus::Ok, "purged probe from trash");
+            return;
+        }
+    }
+
+    match fs::remove_file(path) {
+        Ok(()) => report.step("cleanup", StepStatus::Ok, "removed probe file"),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            report.step("cleanup", StepStatus::Ok, "nothing to remove")
+        }
+        Err(e) => report.step("cleanup", StepStatus::Failed, e.to_string()),
+    }
+}
+
+fn probe_path() -> PathBuf {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    std::env::temp_dir().join(format!(
+        "rmcp-trash-self-test-{}-{}",
+        std::process::id(),
+        nanos
+    ))
+}
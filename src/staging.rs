@@ -0,0 +1,378 @@
+//! Optional holding area that trashed files pass through on their way to the
+//! trash (`RMCP_TRASH_STAGING_DIR`).
+//!
+//! Staging is a rename into a server-managed directory, so undoing it within
+//! the delay is a rename back. A background task hands each file to the real
+//! trash once it has been staged for `RMCP_TRASH_STAGING_DELAY_SECS`; before
+//! that, the file is moved back to its original path so the trash records
+//! where it really came from. That move never replaces a file created at the
+//! original path in the meantime.
+//!
+//! Each staged file's original path is also written to `.origins/` in the
+//! staging directory, so files still staged when the server stops keep it
+//! across a restart.
+
+use crate::backend::Backend;
+use serde::Serialize;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Default time a file stays staged before it is handed to the trash.
+pub const DEFAULT_DELAY: Duration = Duration::from_secs(600);
+
+/// Longest the promoter sleeps between checks.
+const MAX_TICK: Duration = Duration::from_secs(5);
+
+/// Subdirectory recording each staged file's original path, in a file named
+/// like the staged file. Staged names start with a number, so it can't clash
+/// with one.
+const ORIGINS: &str = ".origins";
+
+#[derive(Debug)]
+struct Staged {
+    original: PathBuf,
+    staged: PathBuf,
+    since: Instant,
+}
+
+#[derive(Debug)]
+pub struct Staging {
+    dir: PathBuf,
+    delay: Duration,
+    entries: Mutex<Vec<Staged>>,
+    next_id: AtomicU64,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct FlushReport {
+    /// Original paths of files handed to the trash.
+    pub promoted: Vec<String>,
+    /// Files that stayed staged, with the reason.
+    pub failed: Vec<String>,
+}
+
+impl Staging {
+    /// Open (and create if needed) the staging directory.
+    ///
+    /// Files left over from an earlier run get a fresh delay and go back to
+    /// their recorded original paths. One without a record (e.g. staged by a
+    /// version that kept none) is trashed from the staging directory itself.
+    pub fn open(dir: impl Into<PathBuf>, delay: Duration) -> io::Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(dir.join(ORIGINS))?;
+        let dir = dir.canonicalize()?;
+
+        let now = Instant::now();
+        let mut entries = Vec::new();
+        for entry in std::fs::read_dir(&dir)? {
+            let staged = entry?.path();
+            if staged.file_name() == Some(std::ffi::OsStr::new(ORIGINS)) {
+                continue;
+            }
+            let original = match std::fs::read(origin_path(&dir, &staged)) {
+                Ok(bytes) => path_from_bytes(bytes),
+                Err(e) => {
+                    tracing::warn!(
+                        "No original path recorded for staged {} ({}); it will be trashed from the staging directory",
+                        staged.display(),
+                        e
+                    );
+                    staged.clone()
+                }
+            };
+            entries.push(Staged {
+                original,
+                staged,
+                since: now,
+            });
+        }
+        // Records whose file is gone, e.g. after a crash between the two writes
+        for entry in std::fs::read_dir(dir.join(ORIGINS))? {
+            let origin = entry?.path();
+            if origin.file_name().is_some_and(|name| dir.join(name).symlink_metadata().is_err()) {
+                let _ = std::fs::remove_file(&origin);
+            }
+        }
+        if !entries.is_empty() {
+            tracing::info!("Found {} leftover staged item(s) in {}", entries.len(), dir.display());
+        }
+
+        Ok(Self {
+            dir,
+            delay,
+            entries: Mutex::new(entries),
+            next_id: AtomicU64::new(0),
+        })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn delay(&self) -> Duration {
+        self.delay
+    }
+
+    /// Move `path` into the staging directory.
+    ///
+    /// Fails when the staging directory is on another filesystem, since the
+    /// move would then be a copy; callers fall back to trashing directly.
+    pub fn stage(&self, path: &Path) -> io::Result<()> {
+        let original = crate::paths::resolve(path);
+        let name = original.file_name().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, format!("{} has no file name", path.display()))
+        })?;
+
+        let staged = loop {
+            let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+            let mut file_name = std::ffi::OsString::from(format!("{}-", id));
+            file_name.push(name);
+            let candidate = self.dir.join(file_name);
+            if candidate.symlink_metadata().is_err() {
+                break candidate;
+            }
+        };
+        let origin = origin_path(&self.dir, &staged);
+        std::fs::write(&origin, path_bytes(&original))?;
+        if let Err(e) = std::fs::rename(&original, &staged) {
+            let _ = std::fs::remove_file(&origin);
+            return Err(e);
+        }
+
+        self.entries.lock().unwrap().push(Staged {
+            original,
+            staged,
+            since: Instant::now(),
+        });
+        Ok(())
+    }
+
+    /// Hand every staged file to the trash now, regardless of the delay.
+    pub fn flush(&self, backend: &Backend) -> FlushReport {
+        let all = std::mem::take(&mut *self.entries.lock().unwrap());
+        self.promote(backend, all)
+    }
+
+    /// Hand files staged for at least the delay to the trash.
+    fn promote_due(&self, backend: &Backend) -> FlushReport {
+        let due = {
+            let mut entries = self.entries.lock().unwrap();
+            let (due, waiting) = std::mem::take(&mut *entries)
+                .into_iter()
+                .partition(|entry| entry.since.elapsed() >= self.delay);
+            *entries = waiting;
+            due
+        };
+        self.promote(backend, due)
+    }
+
+    fn promote(&self, backend: &Backend, entries: Vec<Staged>) -> FlushReport {
+        let mut report = FlushReport::default();
+        for mut entry in entries {
+            match promote_one(backend, &entry) {
+                Ok(()) => {
+                    tracing::info!("Moved staged {} to trash", entry.original.display());
+                    let _ = std::fs::remove_file(origin_path(&self.dir, &entry.staged));
+                    report.promoted.push(entry.original.to_string_lossy().into_owned());
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to move staged {} to trash: {}", entry.original.display(), e);
+                    report.failed.push(format!("{}: {}", entry.original.display(), e));
+                    // Try again after another full delay rather than on every tick
+                    entry.since = Instant::now();
+                    self.entries.lock().unwrap().push(entry);
+                }
+            }
+        }
+        report
+    }
+}
+
+/// Trash one staged file, from its original path when that is still free.
+fn promote_one(backend: &Backend, entry: &Staged) -> Result<(), trash::Error> {
+    if entry.original == entry.staged || rename_no_replace(&entry.staged, &entry.original).is_err() {
+        return backend.delete(&entry.staged);
+    }
+    backend.delete(&entry.original).inspect_err(|_| {
+        // Keep the grace period intact: put it back where the next attempt expects it
+        let _ = std::fs::rename(&entry.original, &entry.staged);
+    })
+}
+
+/// Where the original path of the file staged at `staged` is recorded.
+fn origin_path(dir: &Path, staged: &Path) -> PathBuf {
+    dir.join(ORIGINS).join(staged.file_name().unwrap_or_default())
+}
+
+#[cfg(unix)]
+fn path_bytes(path: &Path) -> Vec<u8> {
+    use std::os::unix::ffi::OsStrExt;
+
+    path.as_os_str().as_bytes().to_vec()
+}
+
+#[cfg(unix)]
+fn path_from_bytes(bytes: Vec<u8>) -> PathBuf {
+    use std::os::unix::ffi::OsStringExt;
+
+    PathBuf::from(std::ffi::OsString::from_vec(bytes))
+}
+
+#[cfg(not(unix))]
+fn path_bytes(path: &Path) -> Vec<u8> {
+    path.to_string_lossy().into_owned().into_bytes()
+}
+
+#[cfg(not(unix))]
+fn path_from_bytes(bytes: Vec<u8>) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Move `from` to `to`, failing rather than replacing whatever is at `to`.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn rename_no_replace(from: &Path, to: &Path) -> io::Result<()> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_from = CString::new(from.as_os_str().as_bytes()).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let c_to = CString::new(to.as_os_str().as_bytes()).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    // SAFETY: both paths are valid NUL-terminated strings.
+    let renamed = unsafe {
+        libc::renameat2(libc::AT_FDCWD, c_from.as_ptr(), libc::AT_FDCWD, c_to.as_ptr(), libc::RENAME_NOREPLACE)
+    };
+    if renamed == 0 {
+        return Ok(());
+    }
+    let e = io::Error::last_os_error();
+    match e.raw_os_error() {
+        // Filesystems (or kernels) without the flag
+        Some(libc::EINVAL | libc::ENOSYS) => link_then_unlink(from, to),
+        _ => Err(e),
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn rename_no_replace(from: &Path, to: &Path) -> io::Result<()> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_from = CString::new(from.as_os_str().as_bytes()).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let c_to = CString::new(to.as_os_str().as_bytes()).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    // SAFETY: both paths are valid NUL-terminated strings.
+    if unsafe { libc::renamex_np(c_from.as_ptr(), c_to.as_ptr(), libc::RENAME_EXCL) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(windows)]
+fn rename_no_replace(from: &Path, to: &Path) -> io::Result<()> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::Storage::FileSystem::MoveFileExW;
+
+    let wide = |path: &Path| -> Vec<u16> { path.as_os_str().encode_wide().chain(Some(0)).collect() };
+    let (from, to) = (wide(from), wide(to));
+    // SAFETY: both paths are NUL-terminated. Without MOVEFILE_REPLACE_EXISTING
+    // the move fails if `to` exists.
+    if unsafe { MoveFileExW(from.as_ptr(), to.as_ptr(), 0) } == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android", target_os = "macos", windows)))]
+fn rename_no_replace(from: &Path, to: &Path) -> io::Result<()> {
+    link_then_unlink(from, to)
+}
+
+/// [`rename_no_replace`] for files where the OS has no such rename: a hard
+/// link fails if `to` exists. Directories can't be moved this way.
+#[cfg(not(any(target_os = "macos", windows)))]
+fn link_then_unlink(from: &Path, to: &Path) -> io::Result<()> {
+    if from.symlink_metadata()?.is_dir() {
+        return Err(io::Error::new(io::ErrorKind::Unsupported, "no-replace rename of a directory"));
+    }
+    std::fs::hard_link(from, to)?;
+    std::fs::remove_file(from)
+}
+
+/// Run the promoter for the life of the process.
+pub fn spawn_promoter(staging: Arc<Staging>, backend: Backend) {
+    let tick = staging.delay().clamp(Duration::from_millis(100), MAX_TICK);
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(tick).await;
+            staging.promote_due(&backend);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::CustomTrash;
+    use crate::testutil::TempDir;
+
+    /// Original paths recorded by the trash sidecars under `root`.
+    fn trashed_from(root: &Path) -> Vec<PathBuf> {
+        std::fs::read_dir(root.join("info"))
+            .unwrap()
+            .map(|entry| {
+                let json: serde_json::Value = serde_json::from_slice(&std::fs::read(entry.unwrap().path()).unwrap()).unwrap();
+                PathBuf::from(json["original_path"].as_str().unwrap())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn leftovers_keep_their_original_path() {
+        let tmp = TempDir::new("staging-restart");
+        let file = tmp.file("work/notes.txt", "notes");
+        Staging::open(tmp.path().join("staging"), DEFAULT_DELAY).unwrap().stage(&file).unwrap();
+        assert!(!file.exists());
+
+        // A new server process finds the staged file and where it came from
+        let staging = Staging::open(tmp.path().join("staging"), DEFAULT_DELAY).unwrap();
+        let backend = Backend::Custom(CustomTrash::open(tmp.path().join("trash")).unwrap());
+        let report = staging.flush(&backend);
+        assert_eq!(report.promoted, vec![file.to_string_lossy().into_owned()]);
+        assert_eq!(trashed_from(&tmp.path().join("trash")), vec![file]);
+        assert_eq!(std::fs::read_dir(tmp.path().join("staging").join(ORIGINS)).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn promotion_never_replaces_a_new_file_at_the_original_path() {
+        let tmp = TempDir::new("staging-noreplace");
+        let file = tmp.file("work/notes.txt", "old");
+        let staging = Staging::open(tmp.path().join("staging"), DEFAULT_DELAY).unwrap();
+        staging.stage(&file).unwrap();
+        std::fs::write(&file, "new").unwrap();
+
+        let backend = Backend::Custom(CustomTrash::open(tmp.path().join("trash")).unwrap());
+        let report = staging.flush(&backend);
+        assert!(report.failed.is_empty());
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "new");
+        let stored: Vec<String> = std::fs::read_dir(tmp.path().join("trash").join("files"))
+            .unwrap()
+            .map(|entry| std::fs::read_to_string(entry.unwrap().path()).unwrap())
+            .collect();
+        assert_eq!(stored, vec!["old"]);
+    }
+
+    #[test]
+    fn rename_no_replace_refuses_an_existing_target() {
+        let tmp = TempDir::new("noreplace");
+        let from = tmp.file("from", "from");
+        let to = tmp.file("to", "to");
+        assert!(rename_no_replace(&from, &to).is_err());
+        assert_eq!(std::fs::read_to_string(&to).unwrap(), "to");
+
+        let free = tmp.path().join("free");
+        rename_no_replace(&from, &free).unwrap();
+        assert!(!from.exists());
+        assert_eq!(std::fs::read_to_string(&free).unwrap(), "from");
+    }
+}
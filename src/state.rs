@@ -0,0 +1,168 @@
+//! Session state kept across restarts in `RMCP_TRASH_STATE_FILE`: the paths
+//! `redo_last_restore` would trash again, undo groups and usage snapshots.
+//!
+//! The file is read once at startup and rewritten (to a temporary file, then
+//! renamed over it) after every change. A missing file starts an empty
+//! session; an unreadable or corrupt one is moved aside to `<file>.corrupt`
+//! with a warning, so the next save doesn't destroy it.
+//!
+//! Nothing else needs keeping. There are no pending confirmation tokens to
+//! carry over: `empty_trash` checks a fixed phrase (or `confirm`) given in
+//! the same call. The trash history is the trash itself, which outlives the
+//! server on its own; only the redo list and undo groups built on top of it
+//! are saved here.
+
+use crate::groups::Member;
+use crate::UsageSnapshot;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionState {
+    pub last_restore: Vec<PathBuf>,
+    pub undo_groups: BTreeMap<String, Vec<Member>>,
+    pub usage_snapshots: HashMap<String, UsageSnapshot>,
+}
+
+#[derive(Debug)]
+pub struct StateFile {
+    path: PathBuf,
+    /// Held from collecting the state until it is written, so a slower save
+    /// can't overwrite a newer one.
+    write: Mutex<()>,
+}
+
+impl StateFile {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            write: Mutex::new(()),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The saved state, or an empty one if there is none or it can't be used.
+    pub fn load(&self) -> SessionState {
+        let bytes = match std::fs::read(&self.path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                tracing::info!("No session state in {} yet; starting fresh", self.path.display());
+                return SessionState::default();
+            }
+            Err(e) => {
+                tracing::warn!("Failed to read session state {}: {}; starting fresh", self.path.display(), e);
+                return SessionState::default();
+            }
+        };
+        match serde_json::from_slice(&bytes) {
+            Ok(state) => state,
+            Err(e) => {
+                let aside = self.aside_path();
+                match std::fs::rename(&self.path, &aside) {
+                    Ok(()) => tracing::warn!(
+                        "Session state {} is corrupt ({}); moved it to {} and starting fresh",
+                        self.path.display(),
+                        e,
+                        aside.display()
+                    ),
+                    Err(rename) => tracing::warn!(
+                        "Session state {} is corrupt ({}) and could not be moved aside ({}); starting fresh",
+                        self.path.display(),
+                        e,
+                        rename
+                    ),
+                }
+                SessionState::default()
+            }
+        }
+    }
+
+    /// Write the state returned by `collect`. Failures are logged, not
+    /// returned: the change they record has already happened.
+    pub fn save(&self, collect: impl FnOnce() -> SessionState) {
+        let _write = self.write.lock().unwrap();
+        let state = collect();
+        if let Err(e) = self.write_atomically(&state) {
+            tracing::warn!("Failed to save session state to {}: {}", self.path.display(), e);
+        }
+    }
+
+    fn write_atomically(&self, state: &SessionState) -> std::io::Result<()> {
+        let json = serde_json::to_vec_pretty(state).map_err(std::io::Error::other)?;
+        let mut temp = self.path.clone().into_os_string();
+        temp.push(format!(".{}.tmp", std::process::id()));
+        let temp = PathBuf::from(temp);
+        std::fs::write(&temp, json)?;
+        std::fs::rename(&temp, &self.path).inspect_err(|_| {
+            let _ = std::fs::remove_file(&temp);
+        })
+    }
+
+    fn aside_path(&self) -> PathBuf {
+        let mut aside = self.path.clone().into_os_string();
+        aside.push(".corrupt");
+        PathBuf::from(aside)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::TempDir;
+
+    #[test]
+    fn missing_file_starts_fresh() {
+        let tmp = TempDir::new("state-missing");
+        let state = StateFile::new(tmp.path().join("state.json")).load();
+        assert!(state.last_restore.is_empty());
+        assert!(state.undo_groups.is_empty());
+        assert!(state.usage_snapshots.is_empty());
+    }
+
+    #[test]
+    fn corrupt_file_is_moved_aside() {
+        let tmp = TempDir::new("state-corrupt");
+        let path = tmp.file("state.json", "{not json");
+        let state = StateFile::new(&path).load();
+        assert!(state.last_restore.is_empty());
+        assert!(!path.exists());
+        let aside = tmp.path().join("state.json.corrupt");
+        assert_eq!(std::fs::read_to_string(aside).unwrap(), "{not json");
+    }
+
+    #[test]
+    fn saved_state_loads_back() {
+        let tmp = TempDir::new("state-round-trip");
+        let file = StateFile::new(tmp.path().join("state.json"));
+        let saved: SessionState = serde_json::from_value(serde_json::json!({
+            "last_restore": ["/home/u/a.txt", "/home/u/b"],
+            "undo_groups": {
+                "cleanup": [{ "original": "/home/u/c.log", "trashed_at": 1_700_000_000 }],
+            },
+            "usage_snapshots": {
+                "before": {
+                    "label": "before",
+                    "dir": "/home/u",
+                    "files": 3,
+                    "bytes": 1024,
+                    "taken_at": 1_700_000_100,
+                },
+            },
+        }))
+        .unwrap();
+        let expected = serde_json::to_value(&saved).unwrap();
+        file.save(|| saved);
+
+        let loaded = file.load();
+        assert_eq!(serde_json::to_value(&loaded).unwrap(), expected);
+        assert_eq!(loaded.last_restore.len(), 2);
+        assert_eq!(loaded.undo_groups["cleanup"].len(), 1);
+        assert!(loaded.usage_snapshots.contains_key("before"));
+    }
+}
@@ -0,0 +1,127 @@
+//! Activity counters for the `stats` tool.
+//!
+//! Counters live for the lifetime of the server process: they start at zero
+//! when the server starts, are never reset while it runs, and are not
+//! persisted. They're shared by all requests, so they use relaxed atomics.
+//! The most recent error is kept in full for `last_error_detail`.
+
+use crate::errors::ErrorDetail;
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
+#[derive(Debug)]
+pub struct Stats {
+    started: Instant,
+    files_trashed: AtomicU64,
+    bytes_trashed: AtomicU64,
+    batches: AtomicU64,
+    restores: AtomicU64,
+    purges: AtomicU64,
+    refused: AtomicU64,
+    permission_denied: AtomicU64,
+    collisions: AtomicU64,
+    other_errors: AtomicU64,
+    last_error: Mutex<Option<ErrorDetail>>,
+}
+
+impl Default for Stats {
+    fn default() -> Self {
+        Self {
+            started: Instant::now(),
+            files_trashed: AtomicU64::new(0),
+            bytes_trashed: AtomicU64::new(0),
+            batches: AtomicU64::new(0),
+            restores: AtomicU64::new(0),
+            purges: AtomicU64::new(0),
+            refused: AtomicU64::new(0),
+            permission_denied: AtomicU64::new(0),
+            collisions: AtomicU64::new(0),
+            other_errors: AtomicU64::new(0),
+            last_error: Mutex::new(None),
+        }
+    }
+}
+
+/// Point-in-time copy of the counters.
+#[derive(Debug, Serialize)]
+pub struct StatsSnapshot {
+    pub uptime_secs: u64,
+    pub files_trashed: u64,
+    /// Size of trashed regular files; directories aren't walked.
+    pub bytes_trashed: u64,
+    pub batches: u64,
+    pub restores: u64,
+    pub purges: u64,
+    pub errors: ErrorCounts,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ErrorCounts {
+    /// Targets refused by safety checks (protected paths, mount points, ...).
+    pub refused: u64,
+    pub permission_denied: u64,
+    /// Restores blocked because the original path was occupied.
+    pub collisions: u64,
+    pub other: u64,
+}
+
+impl Stats {
+    pub fn record_trashed(&self, files: usize, bytes: u64) {
+        self.files_trashed.fetch_add(files as u64, Ordering::Relaxed);
+        self.bytes_trashed.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    pub fn record_batch(&self) {
+        self.batches.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_restored(&self, items: usize) {
+        self.restores.fetch_add(items as u64, Ordering::Relaxed);
+    }
+
+    pub fn record_purged(&self, items: usize) {
+        self.purges.fetch_add(items as u64, Ordering::Relaxed);
+    }
+
+    pub fn record_refused(&self, targets: usize) {
+        self.refused.fetch_add(targets as u64, Ordering::Relaxed);
+    }
+
+    /// Count a failed trash, restore or purge by its category.
+    pub fn record_error(&self, e: &trash::Error) {
+        let counter = if crate::errors::is_permission_denied(e) {
+            &self.permission_denied
+        } else if matches!(e, trash::Error::RestoreCollision { .. }) {
+            &self.collisions
+        } else {
+            &self.other_errors
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        *self.last_error.lock().unwrap() = Some(ErrorDetail::of(e));
+    }
+
+    /// The most recent error passed to `record_error`, if any.
+    pub fn last_error(&self) -> Option<ErrorDetail> {
+        self.last_error.lock().unwrap().clone()
+    }
+
+    pub fn snapshot(&self) -> StatsSnapshot {
+        let get = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+        StatsSnapshot {
+            uptime_secs: self.started.elapsed().as_secs(),
+            files_trashed: get(&self.files_trashed),
+            bytes_trashed: get(&self.bytes_trashed),
+            batches: get(&self.batches),
+            restores: get(&self.restores),
+            purges: get(&self.purges),
+            errors: ErrorCounts {
+                refused: get(&self.refused),
+                permission_denied: get(&self.permission_denied),
+                collisions: get(&self.collisions),
+                other: get(&self.other_errors),
+            },
+        }
+    }
+}
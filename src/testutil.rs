@@ -0,0 +1,77 @@
+//! Helpers shared by the unit tests.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// A fresh directory under the system temp directory, removed on drop.
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new(name: &str) -> Self {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let dir = std::env::temp_dir().join(format!(
+            "rmcp-trash-test-{}-{}-{}",
+            std::process::id(),
+            name,
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        // Canonical, so results that resolve symlinks (e.g. macOS /var) compare equal
+        Self(dir.canonicalize().unwrap())
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+
+    /// Create the directory `rel` (and its parents) and return its path.
+    pub fn dir(&self, rel: &str) -> PathBuf {
+        let path = self.0.join(rel);
+        std::fs::create_dir_all(&path).unwrap();
+        path
+    }
+
+    /// Create the file `rel` holding `contents` and return its path.
+    pub fn file(&self, rel: &str, contents: &str) -> PathBuf {
+        let path = self.0.join(rel);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).unwrap();
+        }
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// A bind mount, unmounted when dropped.
+#[cfg(target_os = "linux")]
+pub struct BindMount(PathBuf);
+
+/// Bind-mount `source` onto `target`, or `None` without the privileges to.
+#[cfg(target_os = "linux")]
+pub fn bind_mount(source: &Path, target: &Path) -> Option<BindMount> {
+    let mounted = std::process::Command::new("mount")
+        .arg("--bind")
+        .arg(source)
+        .arg(target)
+        .stderr(std::process::Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success());
+    if !mounted {
+        eprintln!("skipped: bind mounts need privileges");
+        return None;
+    }
+    Some(BindMount(target.to_path_buf()))
+}
+
+#[cfg(target_os = "linux")]
+impl Drop for BindMount {
+    fn drop(&mut self) {
+        let _ = std::process::Command::new("umount").arg(&self.0).status();
+    }
+}
@@ -0,0 +1,203 @@
+//! Directory walks for the scanning tools (sizes, junk, policy and pattern
+//! searches).
+//!
+//! Directories are visited from an explicit stack rather than by recursion,
+//! so a very deep tree can't overflow the stack. Symlinks are never
+//! followed; a directory reached a second time (same device and inode, e.g.
+//! through a bind mount of one of its ancestors) is not entered again, and
+//! nothing below the depth limit (`RMCP_TRASH_MAX_WALK_DEPTH`) is read. Both
+//! are reported in [`WalkLimits`] so callers can say the results are partial.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs::Metadata;
+use std::io;
+use std::path::Path;
+
+/// Depth limit used unless `RMCP_TRASH_MAX_WALK_DEPTH` sets one.
+pub const DEFAULT_MAX_DEPTH: usize = 256;
+
+/// Examples kept of each kind of limit hit.
+const EXAMPLES: usize = 5;
+
+/// What to do with a directory the walk reached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Visit {
+    Descend,
+    Skip,
+}
+
+/// Where a walk stopped short of the whole tree.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WalkLimits {
+    /// The depth limit in effect.
+    pub max_depth: usize,
+    /// Directories not entered because they are at the depth limit.
+    pub depth_limited: usize,
+    /// Directories not entered because they were already visited.
+    pub cycles: usize,
+    /// Up to a few of the directories counted above.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub examples: Vec<String>,
+}
+
+impl WalkLimits {
+    /// Whether the whole tree was walked.
+    pub fn is_empty(&self) -> bool {
+        self.depth_limited == 0 && self.cycles == 0
+    }
+
+    fn note_dir(&mut self, path: &Path) {
+        if self.examples.len() < EXAMPLES {
+            self.examples.push(path.to_string_lossy().into_owned());
+        }
+    }
+
+    /// Text line saying the results are partial, if they are.
+    pub fn note(&self) -> Option<String> {
+        if self.is_empty() {
+            return None;
+        }
+        let mut parts = Vec::new();
+        if self.depth_limited > 0 {
+            parts.push(format!(
+                "{} director(ies) at the depth limit of {} were not entered (RMCP_TRASH_MAX_WALK_DEPTH)",
+                self.depth_limited, self.max_depth
+            ));
+        }
+        if self.cycles > 0 {
+            parts.push(format!("{} director(ies) already visited through a loop were skipped", self.cycles));
+        }
+        Some(format!(
+            "Partial walk: {}; e.g. {}",
+            parts.join(", "),
+            self.examples.join(", ")
+        ))
+    }
+}
+
+/// Walk the tree below `root`, calling `visit` for every entry with its
+/// (not followed) metadata. Directories for which `visit` returns
+/// [`Visit::Descend`] are entered, unless they are `max_depth` levels below
+/// `root` or were entered already.
+pub fn walk(
+    root: &Path,
+    max_depth: usize,
+    mut visit: impl FnMut(&Path, &Metadata) -> Visit,
+) -> io::Result<WalkLimits> {
+    let mut limits = WalkLimits {
+        max_depth,
+        ..WalkLimits::default()
+    };
+    let mut visited = HashSet::new();
+    if let Some(key) = dir_key(root, &root.symlink_metadata()?) {
+        visited.insert(key);
+    }
+    let mut stack = vec![(root.to_path_buf(), 0)];
+    while let Some((dir, depth)) = stack.pop() {
+        let mut subdirs = Vec::new();
+        for entry in std::fs::read_dir(&dir)? {
+            let path = entry?.path();
+            let meta = path.symlink_metadata()?;
+            if visit(&path, &meta) == Visit::Skip || !meta.is_dir() {
+                continue;
+            }
+            if depth + 1 >= max_depth {
+                limits.depth_limited += 1;
+                limits.note_dir(&path);
+            } else if dir_key(&path, &meta).is_some_and(|key| !visited.insert(key)) {
+                limits.cycles += 1;
+                limits.note_dir(&path);
+            } else {
+                subdirs.push((path, depth + 1));
+            }
+        }
+        // Reversed, so the entries of a directory are entered in read order
+        stack.extend(subdirs.into_iter().rev());
+    }
+    Ok(limits)
+}
+
+#[cfg(unix)]
+type DirKey = (u64, u64);
+
+#[cfg(unix)]
+fn dir_key(_path: &Path, meta: &Metadata) -> Option<DirKey> {
+    use std::os::unix::fs::MetadataExt;
+
+    Some((meta.dev(), meta.ino()))
+}
+
+/// Without inode numbers in std, the canonical path stands in for them.
+#[cfg(not(unix))]
+type DirKey = std::path::PathBuf;
+
+#[cfg(not(unix))]
+fn dir_key(path: &Path, _meta: &Metadata) -> Option<DirKey> {
+    path.canonicalize().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::TempDir;
+
+    /// Walk everything below `root`, returning the entries seen (relative
+    /// to `root`, with `/` separators) and the limits hit.
+    fn walk_all(root: &Path, max_depth: usize) -> (Vec<String>, WalkLimits) {
+        let mut seen = Vec::new();
+        let limits = walk(root, max_depth, |path, _| {
+            seen.push(path.strip_prefix(root).unwrap().to_string_lossy().replace('\\', "/"));
+            Visit::Descend
+        })
+        .unwrap();
+        seen.sort();
+        (seen, limits)
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlink_loop_is_never_followed() {
+        let tmp = TempDir::new("walk-loop");
+        tmp.file("a/b/f.txt", "x");
+        std::os::unix::fs::symlink(tmp.path(), tmp.path().join("a/b/up")).unwrap();
+        let (seen, limits) = walk_all(tmp.path(), DEFAULT_MAX_DEPTH);
+        assert_eq!(seen, ["a", "a/b", "a/b/f.txt", "a/b/up"]);
+        assert!(limits.is_empty());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn directory_reached_again_is_counted_as_a_cycle() {
+        let tmp = TempDir::new("walk-bind");
+        tmp.file("a/f.txt", "x");
+        let target = tmp.dir("a/again");
+        // Declared after `tmp`, so it is unmounted before the directory is removed
+        let Some(_mount) = crate::testutil::bind_mount(tmp.path(), &target) else {
+            return;
+        };
+        let (seen, limits) = walk_all(tmp.path(), DEFAULT_MAX_DEPTH);
+        assert_eq!(seen, ["a", "a/again", "a/f.txt"]);
+        assert_eq!(limits.cycles, 1);
+        assert_eq!(limits.depth_limited, 0);
+        assert_eq!(limits.examples, [tmp.path().join("a/again").to_string_lossy()]);
+        assert!(limits.note().is_some());
+    }
+
+    #[test]
+    fn directories_at_the_depth_limit_are_not_entered() {
+        let tmp = TempDir::new("walk-depth");
+        tmp.file("a/b/c/f.txt", "x");
+        tmp.file("a/g.txt", "x");
+        let (seen, limits) = walk_all(tmp.path(), 2);
+        assert_eq!(seen, ["a", "a/b", "a/g.txt"]);
+        assert_eq!(limits.max_depth, 2);
+        assert_eq!(limits.depth_limited, 1);
+        assert_eq!(limits.cycles, 0);
+
+        let (seen, limits) = walk_all(tmp.path(), DEFAULT_MAX_DEPTH);
+        assert_eq!(seen.len(), 5);
+        assert!(limits.is_empty());
+    }
+}
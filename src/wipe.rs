@@ -0,0 +1,108 @@
+//! Best-effort overwrite of file contents before permanent deletion.
+//!
+//! This only overwrites the bytes in place through the filesystem. On SSDs
+//! (wear levelling), copy-on-write filesystems (Btrfs, ZFS, APFS), journaling
+//! with data=journal, or storage with snapshots, the old blocks may survive
+//! elsewhere. It raises the bar against casual recovery; it is not a
+//! guarantee.
+//!
+//! Files with other hard links are left alone: their data is still reachable
+//! through a name outside the trash, and wiping it would destroy that copy.
+
+use std::fs::{self, OpenOptions};
+use std::io::{self, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+const CHUNK: usize = 64 * 1024;
+
+/// Totals for one wipe.
+#[derive(Debug, Default, Clone)]
+pub struct WipeStats {
+    pub files: usize,
+    pub bytes: u64,
+    /// Files not overwritten because they have other hard links.
+    pub linked: Vec<PathBuf>,
+}
+
+/// Zero-fill `path`, or every regular file below it if it is a directory.
+///
+/// Symlinks are never followed, so a link can't redirect the wipe elsewhere,
+/// and files with other hard links are skipped into [`WipeStats::linked`].
+pub fn wipe(path: &Path) -> io::Result<WipeStats> {
+    let mut stats = WipeStats::default();
+    wipe_into(path, &mut stats)?;
+    Ok(stats)
+}
+
+fn wipe_into(path: &Path, stats: &mut WipeStats) -> io::Result<()> {
+    let meta = fs::symlink_metadata(path)?;
+    if meta.is_dir() {
+        for entry in fs::read_dir(path)? {
+            wipe_into(&entry?.path(), stats)?;
+        }
+    } else if meta.is_file() && crate::guard::other_links(path) > 0 {
+        stats.linked.push(path.to_path_buf());
+    } else if meta.is_file() {
+        let len = meta.len();
+        overwrite(path, len)?;
+        stats.files += 1;
+        stats.bytes += len;
+    }
+    Ok(())
+}
+
+fn overwrite(path: &Path, len: u64) -> io::Result<()> {
+    // Read-only files still have to be wiped
+    let mut perms = fs::metadata(path)?.permissions();
+    if perms.readonly() {
+        #[allow(clippy::permissions_set_readonly_false)]
+        perms.set_readonly(false);
+        fs::set_permissions(path, perms)?;
+    }
+
+    let mut file = OpenOptions::new().write(true).open(path)?;
+    file.seek(SeekFrom::Start(0))?;
+    let zeros = [0u8; CHUNK];
+    let mut remaining = len;
+    while remaining > 0 {
+        let n = remaining.min(CHUNK as u64) as usize;
+        file.write_all(&zeros[..n])?;
+        remaining -= n as u64;
+    }
+    file.sync_all()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::TempDir;
+
+    #[test]
+    fn zero_fills_every_file_below_a_directory() {
+        let tmp = TempDir::new("wipe");
+        let a = tmp.file("item/a.txt", "secret");
+        let b = tmp.file("item/sub/b.txt", "more secret");
+        let stats = wipe(&tmp.path().join("item")).unwrap();
+        assert_eq!((stats.files, stats.bytes), (2, 17));
+        assert!(stats.linked.is_empty());
+        assert_eq!(fs::read(a).unwrap(), [0; 6]);
+        assert_eq!(fs::read(b).unwrap(), [0; 11]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn hard_linked_files_are_not_overwritten() {
+        let tmp = TempDir::new("wipe-linked");
+        let linked = tmp.file("item/linked.txt", "still needed");
+        let other = tmp.path().join("elsewhere.txt");
+        fs::hard_link(&linked, &other).unwrap();
+        let plain = tmp.file("item/plain.txt", "gone");
+
+        let stats = wipe(&tmp.path().join("item")).unwrap();
+        assert_eq!((stats.files, stats.bytes), (1, 4));
+        assert_eq!(stats.linked, [linked.as_path()]);
+        assert_eq!(fs::read_to_string(&other).unwrap(), "still needed");
+        assert_eq!(fs::read_to_string(&linked).unwrap(), "still needed");
+        assert_eq!(fs::read(plain).unwrap(), [0; 4]);
+    }
+}